@@ -0,0 +1,17 @@
+use std::env;
+
+fn main() {
+    // `cfg(sanitize = "...")` is unstable, so we can't test for it directly in the crate without
+    // breaking stable builds. Cargo does tell build scripts about it though.
+    let sanitizers = env::var("CARGO_CFG_SANITIZE").unwrap_or_default();
+    if sanitizers.split(',').any(|s| s == "thread") {
+        println!("cargo:rustc-cfg=tsan");
+    }
+
+    // a 64-bit length never needs its top byte, so the header keeps its flags there, unless the
+    // length is packed into 32 bits, in which case they already fit next to the weak count
+    let width = env::var("CARGO_CFG_TARGET_POINTER_WIDTH").unwrap_or_default();
+    if width == "64" && env::var_os("CARGO_FEATURE_PACKED_HEADER").is_none() {
+        println!("cargo:rustc-cfg=flags_in_len");
+    }
+}
@@ -0,0 +1,37 @@
+/*
+ * C declarations for the functions in arccstr's `capi` module.
+ *
+ * A shared string is a `const char *` to its null-terminated bytes, and can be read like any
+ * other C string. Each one holds a reference to the string, which must be released exactly
+ * once with arccstr_release.
+ */
+
+#ifndef ARCCSTR_H
+#define ARCCSTR_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Copies `s` into a new shared string. Returns NULL if `s` is NULL or allocation fails. */
+const char *arccstr_new(const char *s);
+
+/* Adds a reference to `s`, and returns `s`. */
+const char *arccstr_clone(const char *s);
+
+/* Releases one reference to `s`, freeing it if that was the last one. Ignores NULL. */
+void arccstr_release(const char *s);
+
+/* Returns the null-terminated bytes of `s`, which is `s` itself. */
+const char *arccstr_data(const char *s);
+
+/* Returns the length of `s` in bytes, without searching for the terminator. */
+size_t arccstr_len(const char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* ARCCSTR_H */
@@ -0,0 +1,3 @@
+//! The `arccstr_*` functions, for linking into C programs (see `Cargo.toml`).
+
+pub use arccstr::capi::*;
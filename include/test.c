@@ -0,0 +1,60 @@
+/*
+ * Checks that arccstr.h matches the functions the `capi` feature exports, by calling each of
+ * them from C. Build the static library in include/staticlib, and link this against it:
+ *
+ *     cargo build --release --manifest-path include/staticlib/Cargo.toml
+ *     cc -std=c99 -Wall -Wextra -Werror -Iinclude include/test.c \
+ *         include/staticlib/target/release/libarccstr.a -lpthread -ldl -lm -o target/capi-test
+ *     target/capi-test
+ */
+
+#include "arccstr.h"
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define CHECK(cond)                                                                            \
+	do {                                                                                       \
+		if (!(cond)) {                                                                         \
+			fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond);          \
+			exit(1);                                                                           \
+		}                                                                                      \
+	} while (0)
+
+int main(void) {
+	char input[] = "hello";
+	const char *s = arccstr_new(input);
+	CHECK(s != NULL);
+	CHECK(s != input);
+	CHECK(arccstr_data(s) == s);
+	CHECK(strcmp(arccstr_data(s), "hello") == 0);
+	CHECK(arccstr_len(s) == 5);
+
+	/* the string was copied, so changing the input does not change it */
+	input[0] = 'j';
+	CHECK(strcmp(s, "hello") == 0);
+
+	const char *t = arccstr_clone(s);
+	CHECK(t == s);
+	arccstr_release(s);
+	/* t still holds a reference */
+	CHECK(strcmp(arccstr_data(t), "hello") == 0);
+	CHECK(arccstr_len(t) == 5);
+	arccstr_release(t);
+
+	const char *empty = arccstr_new("");
+	CHECK(empty != NULL);
+	CHECK(arccstr_len(empty) == 0);
+	CHECK(*arccstr_data(empty) == '\0');
+	arccstr_release(empty);
+
+	CHECK(arccstr_new(NULL) == NULL);
+	CHECK(arccstr_clone(NULL) == NULL);
+	CHECK(arccstr_data(NULL) == NULL);
+	CHECK(arccstr_len(NULL) == 0);
+	arccstr_release(NULL);
+
+	printf("ok\n");
+	return 0;
+}
@@ -0,0 +1,132 @@
+use crate::raw::RawArcCStr;
+use crate::{ArcCStr, ArcCStrError};
+use core::alloc::GlobalAlloc;
+
+impl ArcCStr {
+    /// Constructs a new `ArcCStr` holding a copy of `bytes`, allocated by `alloc` rather than by
+    /// the global allocator.
+    ///
+    /// `alloc` can be anything that implements [`GlobalAlloc`], such as an arena or a
+    /// `jemallocator::Jemalloc` pool, as long as it lives in a `static`. The string is freed by
+    /// `alloc` when the last reference to it is dropped, on whichever thread that happens. Apart
+    /// from that, it is an ordinary `ArcCStr`: clones share the allocation, and copies (for
+    /// example from [`to_allocation`](ArcCStr::to_allocation)) are made by the global allocator.
+    ///
+    /// Such a string takes two more words of memory than one from the global allocator, which
+    /// say how to free it.
+    ///
+    /// Fails if `bytes` contains an internal `\0`, or if `alloc` fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::alloc::System;
+    /// use arccstr::ArcCStr;
+    ///
+    /// static POOL: System = System;
+    ///
+    /// let s = ArcCStr::try_from_bytes_in(b"from the pool", &POOL).unwrap();
+    /// assert_eq!(s, "from the pool");
+    /// ```
+    pub fn try_from_bytes_in<A: GlobalAlloc + Sync>(
+        bytes: &[u8],
+        alloc: &'static A,
+    ) -> Result<Self, ArcCStrError> {
+        ArcCStrError::check_no_nul(bytes)?;
+        ArcCStrError::check_len(bytes.len())?;
+        // we just checked for \0
+        let raw =
+            unsafe { RawArcCStr::try_allocate_in(bytes, alloc) }.ok_or(ArcCStrError::Alloc)?;
+        let s = unsafe { ArcCStr::from_raw_arc(raw) };
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::construct(&s);
+        Ok(s)
+    }
+
+    /// Like [`try_from_bytes_in`](ArcCStr::try_from_bytes_in), but for a `&str`, which also
+    /// records that the string is valid UTF-8.
+    pub fn try_from_str_in<A: GlobalAlloc + Sync>(
+        s: &str,
+        alloc: &'static A,
+    ) -> Result<Self, ArcCStrError> {
+        let s = ArcCStr::try_from_bytes_in(s.as_bytes(), alloc)?;
+        unsafe { ArcCStr::as_raw_arc(&s).header().set_utf8(true) };
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCStr, ArcCStrError};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+
+    struct Counting(AtomicUsize);
+
+    unsafe impl GlobalAlloc for Counting {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self.0.fetch_add(layout.size(), SeqCst);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.0.fetch_sub(layout.size(), SeqCst);
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    struct Failing;
+
+    unsafe impl GlobalAlloc for Failing {
+        unsafe fn alloc(&self, _: Layout) -> *mut u8 {
+            std::ptr::null_mut()
+        }
+
+        unsafe fn dealloc(&self, _: *mut u8, _: Layout) {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn freed_by_allocator() {
+        static ALLOC: Counting = Counting(AtomicUsize::new(0));
+        let s = ArcCStr::try_from_str_in("hello", &ALLOC).unwrap();
+        assert!(ALLOC.0.load(SeqCst) > 0);
+        assert_eq!(s, "hello");
+        assert_eq!(s.as_str(), Some("hello"));
+
+        let weak = ArcCStr::downgrade(&s);
+        let copy = unsafe { ArcCStr::from_allocation(s.to_allocation()) };
+        let clones = vec![s.clone(); 3];
+        drop(s);
+        drop(clones);
+        assert!(ALLOC.0.load(SeqCst) > 0);
+        drop(weak);
+        assert_eq!(ALLOC.0.load(SeqCst), 0);
+        // the copy came from the global allocator
+        assert_eq!(copy, "hello");
+    }
+
+    #[test]
+    fn mutated_in_place() {
+        static ALLOC: Counting = Counting(AtomicUsize::new(0));
+        let mut s = ArcCStr::try_from_bytes_in(b"abc", &ALLOC).unwrap();
+        ArcCStr::get_mut(&mut s).unwrap().make_ascii_uppercase();
+        assert_eq!(s, "ABC");
+        drop(s);
+        assert_eq!(ALLOC.0.load(SeqCst), 0);
+    }
+
+    #[test]
+    fn errors() {
+        static FAILING: Failing = Failing;
+        assert_eq!(
+            ArcCStr::try_from_bytes_in(b"abc", &FAILING),
+            Err(ArcCStrError::Alloc)
+        );
+        assert_eq!(
+            ArcCStr::try_from_bytes_in(b"a\0c", &FAILING),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+}
@@ -0,0 +1,2747 @@
+use crate::raw::{Count, RawArcCStr};
+use crate::{ArcCStrBuilder, ArcCStrMut, WeakCStr};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::borrow;
+use core::cmp::Ordering;
+use core::convert::From;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem;
+use core::ops::Deref;
+use core::ptr::NonNull;
+use core::slice::SliceIndex;
+use core::str::Utf8Error;
+use core::sync::atomic::Ordering::Relaxed;
+
+/// A thread-safe reference-counted null-terminated string.
+///
+/// The type `ArcCStr` provides shared ownership of a C-style null-terminated string allocated in
+/// the heap. Invoking [`clone`] on `ArcCStr` produces a new pointer to the same value in the heap.
+/// When the last `ArcCStr` pointer to a given string is destroyed, the pointed-to string is also
+/// destroyed. Behind the scenes, `ArcCStr` works much like [`Arc`].
+///
+/// Strings pointed to using `ArcCStr` are meant to be immutable. The only exception is
+/// [`get_mut`](ArcCStr::get_mut), which allows in-place fix-ups of a string while there are no
+/// other pointers to it, such as right after it was created.
+///
+/// `ArcCStr` uses atomic operations for reference counting, so `ArcCStr`s can be sent freely
+/// between threads. In other words, `ArcCStr` implements cheap [`Send`] for strings using the fact
+/// that [`CStr`] is [`Sync`]. `ArcCStr` tries to minimize the space overhead of this feature by
+/// sharing the string data. The disadvantage of this approach is that it requires atomic
+/// operations that are more expensive than ordinary memory accesses. Thus, if you have many
+/// threads accessing the same data, you may see contention. However, in the common case, using
+/// `ArcCStr` should still be faster than cloning the full string.
+///
+/// `ArcCStr` automatically dereferences to [`CStr`] (via the [`Deref`] trait), so you can call
+/// [`CStr`]'s methods on a value of type `ArcCStr`. Methods that read the string are inherent
+/// methods, called with method syntax: [`len`](ArcCStr::len), [`is_empty`](ArcCStr::is_empty),
+/// [`as_bytes`](ArcCStr::as_bytes), [`bytes`](ArcCStr::bytes), [`to_str`](ArcCStr::to_str),
+/// [`as_str`](ArcCStr::as_str), [`get`](ArcCStr::get), [`chunks`](ArcCStr::chunks),
+/// [`eq_bytes`](ArcCStr::eq_bytes), and others. Those that have the same name as a method of
+/// [`CStr`], like `to_str` and `is_empty`, shadow it with the same semantics, but use the length
+/// stored in the header instead of scanning for the terminator.
+///
+/// Methods that deal with the pointer or its reference counts rather than with the string, like
+/// [`strong_count`](ArcCStr::strong_count) and [`ptr_eq`](ArcCStr::ptr_eq), are
+/// [associated functions][assoc], called using function-like syntax, as they are on [`Arc`]:
+///
+/// ```
+/// use arccstr::ArcCStr;
+/// use std::convert::TryFrom;
+/// let my_arc = ArcCStr::try_from("foobar").unwrap();
+/// assert_eq!(my_arc.len(), 6);
+/// assert_eq!(ArcCStr::strong_count(&my_arc), 1);
+/// ```
+///
+/// [`clone`]: https://doc.rust-lang.org/std/clone/trait.Clone.html#tymethod.clone
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`Send`]: https://doc.rust-lang.org/std/marker/trait.Send.html
+/// [`Sync`]: https://doc.rust-lang.org/std/marker/trait.Sync.html
+/// [`Deref`]: https://doc.rust-lang.org/std/ops/trait.Deref.html
+/// [`CStr`]: https://doc.rust-lang.org/std/ffi/struct.CStr.html
+/// [assoc]: https://doc.rust-lang.org/book/method-syntax.html#associated-functions
+///
+/// # Examples
+///
+/// Sharing some immutable strings between threads:
+///
+// Note that we **do not** run these tests here. The windows builders get super
+// unhappy if a thread outlives the main thread and then exits at the same time
+// (something deadlocks) so we just avoid this entirely by not running these
+// tests.
+/// ```no_run
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCStr;
+/// use std::thread;
+///
+/// let five = ArcCStr::try_from("5").unwrap();
+///
+/// for _ in 0..10 {
+///     let five = ArcCStr::clone(&five);
+///
+///     thread::spawn(move || {
+///         println!("{:?}", five);
+///     });
+/// }
+/// ```
+pub struct ArcCStr {
+    raw: RawArcCStr,
+}
+
+use crate::ArcCStrError;
+
+unsafe impl Send for ArcCStr {}
+unsafe impl Sync for ArcCStr {}
+
+use core::convert::TryFrom;
+impl<'a> TryFrom<&'a [u8]> for ArcCStr {
+    type Error = ArcCStrError;
+    fn try_from(b: &'a [u8]) -> Result<Self, Self::Error> {
+        unsafe { ArcCStr::from_raw_cstr_no_nul(b) }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ArcCStr {
+    type Error = ArcCStrError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        ArcCStr::from_str_no_nul(s)
+    }
+}
+
+impl TryFrom<String> for ArcCStr {
+    type Error = ArcCStrError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        ArcCStr::from_str_no_nul(&s)
+    }
+}
+
+impl TryFrom<Vec<u8>> for ArcCStr {
+    type Error = ArcCStrError;
+    /// Copies the bytes into a new allocation, failing if they contain a `\0`.
+    ///
+    /// Unlike going through [`CString::new`], this does not have to append a terminator to the
+    /// `Vec` first: the bytes are checked, and then copied once, straight after the header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::{ArcCStr, ArcCStrError};
+    ///
+    /// let received: Vec<u8> = b"HELO example.com".to_vec();
+    /// assert_eq!(ArcCStr::try_from(received).unwrap().to_bytes(), b"HELO example.com");
+    /// assert_eq!(
+    ///     ArcCStr::try_from(b"HE\0LO".to_vec()),
+    ///     Err(ArcCStrError::InteriorNul { pos: 2 })
+    /// );
+    /// ```
+    fn try_from(v: Vec<u8>) -> Result<Self, Self::Error> {
+        ArcCStr::try_from(&*v)
+    }
+}
+
+impl TryFrom<Box<[u8]>> for ArcCStr {
+    type Error = ArcCStrError;
+    /// Copies the bytes into a new allocation, failing if they contain a `\0`. See the
+    /// conversion from `Vec<u8>`.
+    fn try_from(b: Box<[u8]>) -> Result<Self, Self::Error> {
+        ArcCStr::try_from(&*b)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> TryFrom<&'a OsStr> for ArcCStr {
+    type Error = ArcCStrError;
+    /// Copies the OS string into a new allocation.
+    ///
+    /// On Unix, this copies the bytes as they are, and fails only if they contain a `\0`.
+    /// Elsewhere, OS strings are not arbitrary bytes, so this also fails with
+    /// [`NotUnicode`](ArcCStrError::NotUnicode) if the string is not valid Unicode, rather than
+    /// replacing the parts that are not.
+    fn try_from(s: &'a OsStr) -> Result<Self, Self::Error> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            ArcCStr::try_from(s.as_bytes())
+        }
+        #[cfg(not(unix))]
+        {
+            ArcCStr::try_from(s.to_str().ok_or(ArcCStrError::NotUnicode)?)
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<OsString> for ArcCStr {
+    type Error = ArcCStrError;
+    /// Copies the OS string into a new allocation. See the conversion from `&OsStr`.
+    fn try_from(s: OsString) -> Result<Self, Self::Error> {
+        ArcCStr::try_from(&*s)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> TryFrom<&'a Path> for ArcCStr {
+    type Error = ArcCStrError;
+    /// Copies the path into a new allocation. See the conversion from `&OsStr`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use std::path::Path;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let path = ArcCStr::try_from(Path::new("/etc/hosts")).unwrap();
+    /// assert_eq!(path, "/etc/hosts");
+    /// ```
+    fn try_from(p: &'a Path) -> Result<Self, Self::Error> {
+        ArcCStr::try_from(p.as_os_str())
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<PathBuf> for ArcCStr {
+    type Error = ArcCStrError;
+    /// Copies the path into a new allocation. See the conversion from `&OsStr`.
+    fn try_from(p: PathBuf) -> Result<Self, Self::Error> {
+        ArcCStr::try_from(p.as_os_str())
+    }
+}
+
+impl TryFrom<char> for ArcCStr {
+    type Error = ArcCStrError;
+    /// Constructs a one-character string holding the UTF-8 encoding of `c`.
+    ///
+    /// Fails only if `c` is `'\0'`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from(',').unwrap(), b",");
+    /// assert_eq!(ArcCStr::try_from('é').unwrap(), b"\xc3\xa9");
+    /// assert!(ArcCStr::try_from('\0').is_err());
+    /// ```
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        let mut buf = [0; 4];
+        ArcCStr::from_str_no_nul(c.encode_utf8(&mut buf))
+    }
+}
+
+/// Returns the largest index no greater than `index` that is on a character boundary of `s`.
+fn floor_char_boundary(s: &str, index: usize) -> usize {
+    if index >= s.len() {
+        return s.len();
+    }
+    // a character is at most four bytes long
+    (index.saturating_sub(3)..=index)
+        .rev()
+        .find(|&i| s.is_char_boundary(i))
+        .unwrap_or(0)
+}
+
+/// Calls `f` with each piece of `buf` in turn, with every invalid UTF-8 sequence and every `\0`
+/// replaced by `U+FFFD REPLACEMENT CHARACTER`.
+fn for_each_lossy_piece(mut buf: &[u8], mut f: impl FnMut(&str)) {
+    const REPLACEMENT: &str = "\u{FFFD}";
+    loop {
+        let (valid, rest) = match core::str::from_utf8(buf) {
+            Ok(valid) => (valid, None),
+            Err(e) => {
+                let (valid, invalid) = buf.split_at(e.valid_up_to());
+                // an incomplete sequence at the end is replaced as a whole
+                let skip = e.error_len().unwrap_or(invalid.len());
+                let valid = unsafe { core::str::from_utf8_unchecked(valid) };
+                (valid, Some(&invalid[skip..]))
+            }
+        };
+        for (i, piece) in valid.split('\0').enumerate() {
+            if i > 0 {
+                f(REPLACEMENT);
+            }
+            f(piece);
+        }
+        match rest {
+            Some(rest) => {
+                f(REPLACEMENT);
+                buf = rest;
+            }
+            None => return,
+        }
+    }
+}
+
+use core::str::FromStr;
+impl FromStr for ArcCStr {
+    type Err = ArcCStrError;
+
+    /// Parses a string by copying it, failing if it contains an internal `\0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s: ArcCStr = "foo".parse().unwrap();
+    /// assert_eq!(s, b"foo");
+    /// assert!("f\0o".parse::<ArcCStr>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ArcCStr::try_from(s)
+    }
+}
+
+use alloc::ffi::CString;
+#[cfg(feature = "std")]
+use std::ffi::{OsStr, OsString};
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+impl From<CString> for ArcCStr {
+    fn from(s: CString) -> Self {
+        unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(s.to_bytes()) }
+    }
+}
+
+use core::ffi::{c_char, CStr};
+impl<'a> From<&'a CStr> for ArcCStr {
+    fn from(s: &'a CStr) -> Self {
+        unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(s.to_bytes()) }
+    }
+}
+
+impl From<Box<CStr>> for ArcCStr {
+    /// Copies the string into a new allocation, and frees the box.
+    fn from(s: Box<CStr>) -> Self {
+        ArcCStr::from(&*s)
+    }
+}
+
+impl From<Arc<CStr>> for ArcCStr {
+    /// Copies the string into a new allocation, and releases `s`.
+    ///
+    /// The string has to be copied, since an `Arc<CStr>` keeps its reference counts in a
+    /// different place than an `ArcCStr` does.
+    fn from(s: Arc<CStr>) -> Self {
+        ArcCStr::from(&*s)
+    }
+}
+
+impl<'a> From<&'a ArcCStr> for Arc<CStr> {
+    /// Copies the string into a new `Arc<CStr>`.
+    fn from(s: &'a ArcCStr) -> Self {
+        Arc::from(&**s)
+    }
+}
+
+impl From<ArcCStr> for Arc<CStr> {
+    /// Copies the string into a new `Arc<CStr>`, and releases `s`.
+    fn from(s: ArcCStr) -> Self {
+        Arc::from(&*s)
+    }
+}
+
+#[cfg(feature = "legacy-from")]
+impl<'a> From<&'a ArcCStr> for String {
+    /// Converts the string to a `String`, replacing any invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER` (like `CStr::to_string_lossy`).
+    ///
+    /// This conversion is lossy, and is only available with the `legacy-from` feature. Without
+    /// it, the conversion fails on invalid UTF-8 instead.
+    fn from(s: &'a ArcCStr) -> Self {
+        s.to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(feature = "legacy-from")]
+impl From<ArcCStr> for String {
+    /// Converts the string to a `String`, replacing any invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER` (like `CStr::to_string_lossy`).
+    ///
+    /// This conversion is lossy, and is only available with the `legacy-from` feature. Without
+    /// it, the conversion fails on invalid UTF-8 instead.
+    fn from(s: ArcCStr) -> Self {
+        String::from(&s)
+    }
+}
+
+#[cfg(not(feature = "legacy-from"))]
+impl<'a> TryFrom<&'a ArcCStr> for String {
+    type Error = ArcCStrError;
+    /// Copies the string into a `String`, failing if it is not valid UTF-8.
+    fn try_from(s: &'a ArcCStr) -> Result<Self, Self::Error> {
+        Ok(String::from(s.to_str()?))
+    }
+}
+
+#[cfg(not(feature = "legacy-from"))]
+impl TryFrom<ArcCStr> for String {
+    type Error = ArcCStrError;
+    /// Copies the string into a `String`, failing if it is not valid UTF-8.
+    fn try_from(s: ArcCStr) -> Result<Self, Self::Error> {
+        String::try_from(&s)
+    }
+}
+
+// on Unix, the standard library provides this through `AsRef<OsStr>`
+#[cfg(all(feature = "std", not(unix), feature = "legacy-from"))]
+impl<'a> From<&'a ArcCStr> for OsString {
+    /// Converts the string to an `OsString`, lossily. See [`ArcCStr::to_os_string`].
+    ///
+    /// This is only available with the `legacy-from` feature.
+    fn from(s: &'a ArcCStr) -> Self {
+        s.to_os_string()
+    }
+}
+
+#[cfg(all(feature = "std", any(unix, feature = "legacy-from")))]
+impl From<ArcCStr> for OsString {
+    /// Converts the string to an `OsString`. See [`ArcCStr::to_os_string`].
+    ///
+    /// Outside of Unix, this conversion is lossy, and is only available with the `legacy-from`
+    /// feature.
+    fn from(s: ArcCStr) -> Self {
+        s.to_os_string()
+    }
+}
+
+#[cfg(all(feature = "std", not(unix), not(feature = "legacy-from")))]
+impl<'a> TryFrom<&'a ArcCStr> for OsString {
+    type Error = ArcCStrError;
+    /// Converts the string to an `OsString`, failing if it is not valid UTF-8.
+    fn try_from(s: &'a ArcCStr) -> Result<Self, Self::Error> {
+        Ok(OsString::from(s.to_str()?))
+    }
+}
+
+#[cfg(all(feature = "std", not(unix), not(feature = "legacy-from")))]
+impl TryFrom<ArcCStr> for OsString {
+    type Error = ArcCStrError;
+    /// Converts the string to an `OsString`, failing if it is not valid UTF-8.
+    fn try_from(s: ArcCStr) -> Result<Self, Self::Error> {
+        OsString::try_from(&s)
+    }
+}
+
+impl ArcCStr {
+    unsafe fn from_raw_cstr_no_nul(buf: &[u8]) -> Result<Self, ArcCStrError> {
+        // check that buf doesn't contain any internal \0s
+        ArcCStrError::check_no_nul(buf)?;
+        ArcCStr::try_concat_unchecked(&[buf])
+    }
+
+    /// Constructs a new `ArcCStr` holding `parts` one after the other, in a single allocation.
+    ///
+    /// The caller must make sure that none of `parts` contains a `\0`.
+    pub(crate) unsafe fn try_concat_unchecked(parts: &[&[u8]]) -> Result<Self, ArcCStrError> {
+        let len = parts
+            .iter()
+            .try_fold(0, |len: usize, p| len.checked_add(p.len()))
+            .unwrap_or(usize::MAX);
+        ArcCStrError::check_len(len)?;
+        let raw = RawArcCStr::try_allocate_concat(parts).ok_or(ArcCStrError::Alloc)?;
+        let s = ArcCStr { raw };
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::construct(&s);
+        Ok(s)
+    }
+
+    fn from_str_no_nul(s: &str) -> Result<Self, ArcCStrError> {
+        let s = unsafe { ArcCStr::from_raw_cstr_no_nul(s.as_bytes())? };
+        unsafe { s.raw.header().set_utf8(true) };
+        Ok(s)
+    }
+
+    /// Constructs a new `ArcCStr` from `s`, failing if `s` is longer than `max_len` bytes.
+    ///
+    /// `max_len` does not include the null terminator, so a fixed-size wire field of `N` bytes
+    /// that must also hold the terminator should pass `N - 1`. Like `TryFrom<&str>`, this also
+    /// fails if `s` contains an internal `\0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::{ArcCStr, ArcCStrError};
+    ///
+    /// assert!(ArcCStr::try_from_str_bounded("PING", 4).is_ok());
+    /// assert_eq!(
+    ///     ArcCStr::try_from_str_bounded("PING!", 4),
+    ///     Err(ArcCStrError::TooLong { len: 5, max_len: 4 })
+    /// );
+    /// ```
+    pub fn try_from_str_bounded(s: &str, max_len: usize) -> Result<Self, ArcCStrError> {
+        if s.len() > max_len {
+            return Err(ArcCStrError::TooLong {
+                len: s.len(),
+                max_len,
+            });
+        }
+        ArcCStr::from_str_no_nul(s)
+    }
+
+    /// Constructs a new `ArcCStr` holding a copy of the C string at `ptr`.
+    ///
+    /// This measures the string like [`CStr::from_ptr`], and copies it into a new allocation. The
+    /// caller keeps ownership of the original. Not to be confused with
+    /// [`from_raw`](ArcCStr::from_raw), which takes back a pointer that came from an `ArcCStr`.
+    ///
+    /// # Safety
+    ///
+    /// As for `CStr::from_ptr`: `ptr` must be non-null, and point to a `\0`-terminated string
+    /// that is valid for reads, and not written to, during this call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ffi::CString;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let owned_by_c = CString::new("from C").unwrap();
+    /// let s = unsafe { ArcCStr::from_ptr(owned_by_c.as_ptr()) };
+    /// drop(owned_by_c);
+    /// assert_eq!(s, "from C");
+    /// ```
+    pub unsafe fn from_ptr(ptr: *const c_char) -> Self {
+        ArcCStr::from(CStr::from_ptr(ptr))
+    }
+
+    /// Like [`from_ptr`](ArcCStr::from_ptr), but reads at most `max_len` bytes before the
+    /// terminator.
+    ///
+    /// This is for strings that C code may have failed to terminate, such as a fixed-size field
+    /// of a struct, which should pass its size minus one as `max_len`. If there is no `\0` among
+    /// the first `max_len + 1` bytes, this fails with
+    /// [`NotTerminated`](ArcCStrError::NotTerminated) rather than reading further. It also fails
+    /// if the string cannot be allocated.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be non-null, and the bytes from `ptr` up to the first `\0` or up to
+    /// `ptr + max_len`, whichever comes first, must be valid for reads, and not written to,
+    /// during this call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::{ArcCStr, ArcCStrError};
+    ///
+    /// let name: [u8; 8] = *b"eth0\0\0\0\0";
+    /// let s = unsafe { ArcCStr::try_from_ptr_bounded(name.as_ptr().cast(), 7) };
+    /// assert_eq!(s.unwrap(), "eth0");
+    ///
+    /// let full: [u8; 8] = *b"overflow";
+    /// assert_eq!(
+    ///     unsafe { ArcCStr::try_from_ptr_bounded(full.as_ptr().cast(), 7) },
+    ///     Err(ArcCStrError::NotTerminated { max_len: 7 })
+    /// );
+    /// ```
+    pub unsafe fn try_from_ptr_bounded(
+        ptr: *const c_char,
+        max_len: usize,
+    ) -> Result<Self, ArcCStrError> {
+        let bytes = ptr as *const u8;
+        let mut len = 0;
+        while *bytes.add(len) != 0 {
+            if len == max_len {
+                return Err(ArcCStrError::NotTerminated { max_len });
+            }
+            len += 1;
+        }
+        // the bytes before the first \0 contain no \0
+        ArcCStr::try_concat_unchecked(&[core::slice::from_raw_parts(bytes, len)])
+    }
+
+    /// Constructs a new `ArcCStr` from the longest prefix of `s` that is at most `max_bytes` long
+    /// and ends on a character boundary.
+    ///
+    /// Fails if that prefix contains an internal `\0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::from_str_truncated("héllo", 3).unwrap(), b"h\xc3\xa9");
+    /// assert_eq!(ArcCStr::from_str_truncated("héllo", 2).unwrap(), b"h");
+    /// assert_eq!(ArcCStr::from_str_truncated("héllo", 10).unwrap().to_str(), Ok("héllo"));
+    /// ```
+    pub fn from_str_truncated(s: &str, max_bytes: usize) -> Result<Self, ArcCStrError> {
+        ArcCStr::from_str_no_nul(&s[..floor_char_boundary(s, max_bytes)])
+    }
+
+    /// Like [`from_str_truncated`](ArcCStr::from_str_truncated), but ends the string with
+    /// `ellipsis` if it had to be shortened.
+    ///
+    /// The result, including the ellipsis, is at most `max_bytes` long, and is built in a single
+    /// allocation. If `ellipsis` itself is longer than `max_bytes`, it is truncated as well. Fails
+    /// if the kept part of `s` or `ellipsis` contains an internal `\0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::from_str_truncated_with("a rather long message", 10, "…").unwrap();
+    /// assert_eq!(s.to_str(), Ok("a rathe…"));
+    /// let s = ArcCStr::from_str_truncated_with("short", 10, "…").unwrap();
+    /// assert_eq!(s.to_str(), Ok("short"));
+    /// ```
+    pub fn from_str_truncated_with(
+        s: &str,
+        max_bytes: usize,
+        ellipsis: &str,
+    ) -> Result<Self, ArcCStrError> {
+        if s.len() <= max_bytes {
+            return ArcCStr::from_str_no_nul(s);
+        }
+        let ellipsis = &ellipsis[..floor_char_boundary(ellipsis, max_bytes)];
+        let s = &s[..floor_char_boundary(s, max_bytes - ellipsis.len())];
+        ArcCStrError::check_no_nul(s.as_bytes())?;
+        ArcCStrError::check_no_nul(ellipsis.as_bytes())?;
+        let s = unsafe { ArcCStr::try_concat_unchecked(&[s.as_bytes(), ellipsis.as_bytes()])? };
+        unsafe { s.raw.header().set_utf8(true) };
+        Ok(s)
+    }
+
+    /// Constructs a new `ArcCStr` from `buf`, replacing any invalid UTF-8 sequences and any `\0`s
+    /// with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// This is like `String::from_utf8_lossy`, except that the result also cannot contain a
+    /// `\0`, so this never fails. The length of the result is worked out first, so the string is
+    /// written directly into a single allocation, and it is known to be valid UTF-8.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::from_utf8_lossy(b"caf\xc3\xa9 \xff\0ok");
+    /// assert_eq!(s.to_str(), Ok("café \u{FFFD}\u{FFFD}ok"));
+    /// ```
+    pub fn from_utf8_lossy(buf: &[u8]) -> Self {
+        let mut len = 0;
+        for_each_lossy_piece(buf, |piece| len += piece.len());
+        let mut b = ArcCStrBuilder::with_capacity(len);
+        for_each_lossy_piece(buf, |piece| {
+            b.push_str(piece).expect("pieces are free of \\0 and fit");
+        });
+        b.build().expect("the builder is already the right size")
+    }
+
+    /// Constructs a new `ArcCStr` from `buf`, replacing each `\0` in it with `marker`.
+    ///
+    /// An empty `marker` removes the `\0`s instead. Like
+    /// [`from_utf8_lossy`](ArcCStr::from_utf8_lossy), the string is written directly into a
+    /// single allocation. The bytes are otherwise kept as they are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `marker` itself contains a `\0`, or if the memory cannot be allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::from_bytes_lossy(b"a\0b\0", b"\\0").to_bytes(), b"a\\0b\\0");
+    /// assert_eq!(ArcCStr::from_bytes_lossy(b"a\0b\0", b"").to_bytes(), b"ab");
+    /// ```
+    pub fn from_bytes_lossy(buf: &[u8], marker: &[u8]) -> Self {
+        assert!(!marker.contains(&0), "marker contains a \\0");
+        let nuls = buf.iter().filter(|&&b| b == 0).count();
+        let mut b = ArcCStrBuilder::with_capacity(buf.len() - nuls + nuls * marker.len());
+        for (i, piece) in buf.split(|&b| b == 0).enumerate() {
+            if i > 0 {
+                b.push_bytes(marker)
+                    .expect("marker is free of \\0 and fits");
+            }
+            b.push_bytes(piece).expect("pieces are free of \\0 and fit");
+        }
+        b.build().expect("the builder is already the right size")
+    }
+
+    /// Constructs a new `ArcCStr` holding all of `parts` one after the other.
+    ///
+    /// The total length is computed up front, so the parts are copied directly into a single
+    /// allocation, without going through `format!` or a temporary buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let host = ArcCStr::try_from("example.org").unwrap();
+    /// let port = ArcCStr::try_from("443").unwrap();
+    /// let colon = ArcCStr::try_from(":").unwrap();
+    /// let addr = ArcCStr::concat(&[&host, &colon, &port]);
+    /// assert_eq!(addr.to_str(), Ok("example.org:443"));
+    /// ```
+    pub fn concat<S: AsRef<CStr>>(parts: &[S]) -> Self {
+        ArcCStr::join(parts, Default::default())
+    }
+
+    /// Constructs a new `ArcCStr` holding all of `parts`, with `sep` between each of them.
+    ///
+    /// Like [`concat`](ArcCStr::concat), this makes a single allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::ffi::CStr;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let parts = ["usr", "local", "bin"].map(|p| p.parse::<ArcCStr>().unwrap());
+    /// let sep = CStr::from_bytes_with_nul(b"/\0").unwrap();
+    /// assert_eq!(ArcCStr::join(&parts, sep).to_str(), Ok("usr/local/bin"));
+    /// ```
+    pub fn join<S: AsRef<CStr>>(parts: &[S], sep: &CStr) -> Self {
+        let sep = sep.to_bytes();
+        let seps = sep.len().checked_mul(parts.len().saturating_sub(1));
+        let len = parts
+            .iter()
+            .map(|p| p.as_ref().to_bytes().len())
+            .try_fold(seps.expect("string too long"), usize::checked_add)
+            .expect("string too long");
+        let mut b = ArcCStrBuilder::with_capacity(len);
+        for (i, part) in parts.iter().enumerate() {
+            // neither a CStr nor the separator can contain a \0
+            unsafe {
+                if i > 0 {
+                    b.push_bytes_unchecked(sep).expect("fits");
+                }
+                b.push_bytes_unchecked(part.as_ref().to_bytes())
+                    .expect("fits");
+            }
+        }
+        b.build().expect("the builder is already the right size")
+    }
+
+    /// Constructs a one-character string holding the digit `d` in the given `radix`.
+    ///
+    /// Like [`char::from_digit`], this returns `None` if `d` is not a valid digit in `radix`, and
+    /// uses lowercase letters for digits above 9.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is greater than 36.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::from_digit(7, 10).unwrap(), b"7");
+    /// assert_eq!(ArcCStr::from_digit(11, 16).unwrap(), b"b");
+    /// assert!(ArcCStr::from_digit(10, 10).is_none());
+    /// ```
+    pub fn from_digit(d: u32, radix: u32) -> Option<Self> {
+        let c = core::char::from_digit(d, radix)?;
+        // digits are always a single non-zero ASCII byte
+        let s = unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(&[c as u8]) };
+        unsafe { s.raw.header().set_utf8(true) };
+        Some(s)
+    }
+
+    /// Constructs a new `ArcCStr` holding a copy of `bytes`, without checking for internal `\0`s.
+    ///
+    /// This is for callers that have already validated `bytes`, and want to skip the scan that
+    /// `TryFrom<&[u8]>` does.
+    ///
+    /// # Safety
+    ///
+    /// `bytes` must not contain any `\0` bytes. Otherwise, the string would appear to end at the
+    /// first one when viewed as a `CStr`, and could not be freed correctly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = unsafe { ArcCStr::from_bytes_unchecked(b"foo") };
+    /// assert_eq!(s, b"foo");
+    /// ```
+    pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> Self {
+        debug_assert!(!bytes.contains(&0));
+        Self::from_raw_cstr_no_nul_unchecked(bytes)
+    }
+
+    pub(crate) unsafe fn from_raw_cstr_no_nul_unchecked(buf: &[u8]) -> Self {
+        let s = ArcCStr {
+            raw: RawArcCStr::allocate(buf),
+        };
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::construct(&s);
+        s
+    }
+
+    /// Returns a copy of the string as a `CString`, and frees it, if `this` is the only pointer to
+    /// it.
+    ///
+    /// Otherwise, `this` is returned unchanged. Like [`Arc::try_unwrap`], this succeeds for
+    /// exactly one of two racing callers holding the last two pointers, and weak pointers do not
+    /// prevent it from succeeding.
+    ///
+    /// The string has to be copied, since a `CString` has its own allocation, but this avoids
+    /// cloning the `ArcCStr` or keeping its allocation alive alongside the copy.
+    ///
+    /// [`Arc::try_unwrap`]: alloc::sync::Arc::try_unwrap
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let foo = ArcCStr::try_from("foo").unwrap();
+    /// let foo2 = ArcCStr::clone(&foo);
+    /// let foo = ArcCStr::try_unwrap(foo).unwrap_err();
+    /// drop(foo2);
+    /// assert_eq!(ArcCStr::try_unwrap(foo).unwrap().as_bytes(), b"foo");
+    /// ```
+    pub fn try_unwrap(this: Self) -> Result<CString, Self> {
+        unsafe {
+            if !this.raw.try_release_unique() {
+                return Err(this);
+            }
+            let s = CString::from(&*this);
+            // we now hold a weak reference rather than the strong one that `this` would drop
+            let this = mem::ManuallyDrop::new(this);
+            #[cfg(feature = "alloc-observer")]
+            crate::observer::drop(&this);
+            if this.raw.decrement_weak_count() {
+                this.raw.deallocate();
+            }
+            Ok(s)
+        }
+    }
+
+    /// Returns a copy of the string as a `CString`, releasing `this`.
+    ///
+    /// If `this` was the only pointer to the string, the string is freed. See
+    /// [`try_unwrap`](ArcCStr::try_unwrap).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let foo = ArcCStr::try_from("foo").unwrap();
+    /// assert_eq!(ArcCStr::into_c_string(foo).as_bytes(), b"foo");
+    /// ```
+    pub fn into_c_string(this: Self) -> CString {
+        ArcCStr::try_unwrap(this).unwrap_or_else(|this| CString::from(&*this))
+    }
+
+    /// Gets the number of pointers to this string.
+    ///
+    /// # Safety
+    ///
+    /// This method by itself is safe, but using it correctly requires extra care.
+    /// Another thread can change the strong count at any time,
+    /// including potentially between calling this method and acting on the result.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    /// let _also_five = ArcCStr::clone(&five);
+    ///
+    /// // This assertion is deterministic because we haven't shared
+    /// // the `ArcCStr` between threads.
+    /// assert_eq!(2, ArcCStr::strong_count(&five));
+    /// ```
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        unsafe { this.raw.strong_count() }
+    }
+
+    /// Gets the number of [`WeakCStr`] pointers to this string.
+    ///
+    /// Like [`strong_count`](ArcCStr::strong_count), the result may be out of date by the time it
+    /// is used if the string is shared between threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    /// let _weak_five = ArcCStr::downgrade(&five);
+    ///
+    /// assert_eq!(1, ArcCStr::weak_count(&five));
+    /// ```
+    #[inline]
+    #[allow(clippy::unnecessary_cast)] // `Count` is `usize` without the `u32-refcount` feature
+    pub fn weak_count(this: &Self) -> usize {
+        match unsafe { this.raw.weak_count() } {
+            // get_mut is checking for other references; it only does that when there are no weak
+            // ones
+            n if n == Count::MAX as usize => 0,
+            // don't count the weak reference held by the strong ones; we know there is at least
+            // one of those, since `this` exists
+            n => n - 1,
+        }
+    }
+
+    /// Returns mutable access to the string's bytes, if there are no other pointers to it.
+    ///
+    /// Like [`Arc::get_mut`], this returns `None` if there are other `ArcCStr` or [`WeakCStr`]
+    /// pointers to the same string. The bytes cannot be made to contain a `\0`, and the length
+    /// of the string cannot change; see [`ArcCStrMut`].
+    ///
+    /// [`Arc::get_mut`]: alloc::sync::Arc::get_mut
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let mut s = ArcCStr::try_from("Content-Type").unwrap();
+    /// ArcCStr::get_mut(&mut s).unwrap().make_ascii_lowercase();
+    /// assert_eq!(s.to_str(), Ok("content-type"));
+    ///
+    /// let shared = ArcCStr::clone(&s);
+    /// assert!(ArcCStr::get_mut(&mut s).is_none());
+    /// ```
+    pub fn get_mut(this: &mut Self) -> Option<ArcCStrMut<'_>> {
+        if unsafe { this.raw.is_unique() } {
+            Some(unsafe { ArcCStrMut::new(this) })
+        } else {
+            None
+        }
+    }
+
+    /// Creates a new [`WeakCStr`] pointer to this string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    /// let weak_five = ArcCStr::downgrade(&five);
+    /// assert_eq!(weak_five.upgrade(), Some(five));
+    /// ```
+    pub fn downgrade(this: &Self) -> WeakCStr {
+        unsafe {
+            this.raw.increment_weak_count();
+            WeakCStr::from_raw_arc(this.raw)
+        }
+    }
+
+    /// Gets the highest number of pointers this string has had at any one time.
+    ///
+    /// This helps tell whether strings that are expected to be widely shared actually are. Like
+    /// [`strong_count`](ArcCStr::strong_count), the result may be out of date by the time it is
+    /// used if the string is shared between threads.
+    ///
+    /// Only available with the `refcount-telemetry` feature, as it adds a counter to every
+    /// allocation and extra work to every clone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    /// let also_five = ArcCStr::clone(&five);
+    /// drop(also_five);
+    ///
+    /// assert_eq!(1, ArcCStr::strong_count(&five));
+    /// assert_eq!(2, ArcCStr::max_strong_count(&five));
+    /// ```
+    #[cfg(feature = "refcount-telemetry")]
+    #[inline]
+    #[allow(clippy::unnecessary_cast)] // `Count` is `usize` without the `u32-refcount` feature
+    pub fn max_strong_count(this: &Self) -> usize {
+        unsafe { this.raw.header() }.max_strong().load(Relaxed) as usize
+    }
+
+    #[inline]
+    /// Returns true if the two `ArcCStr`s point to the same value (not
+    /// just values that compare as equal).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    /// let same_five = ArcCStr::clone(&five);
+    /// let other_five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// assert!(ArcCStr::ptr_eq(&five, &same_five));
+    /// assert!(!ArcCStr::ptr_eq(&five, &other_five));
+    /// ```
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.raw == other.raw
+    }
+
+    /// Returns the address of this string's allocation, for sorting by identity.
+    ///
+    /// Sorting by this key never reads the strings themselves. Two strings have the same key
+    /// exactly when [`ptr_eq`](ArcCStr::ptr_eq) holds for them, so for interned strings it
+    /// groups equal strings together just like sorting by content would, only faster. The order
+    /// is arbitrary and will differ between runs. [`OrdByPtr`](crate::OrdByPtr) wraps an
+    /// `ArcCStr` to use this order in sets and maps, and
+    /// [`OrdByPtrThenContent`](crate::OrdByPtrThenContent) uses it only for interned strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let a = ArcCStr::try_from("a").unwrap();
+    /// let b = ArcCStr::try_from("b").unwrap();
+    /// let mut index = vec![a.clone(), b.clone(), a.clone()];
+    /// index.sort_unstable_by_key(ArcCStr::sort_key);
+    /// assert!(ArcCStr::ptr_eq(&index[0], &index[1]) || ArcCStr::ptr_eq(&index[1], &index[2]));
+    /// ```
+    #[inline]
+    pub fn sort_key(this: &Self) -> usize {
+        this.raw.as_ptr().as_ptr() as usize
+    }
+
+    /// Consumes the `ArcCStr`, returning the underlying [`RawArcCStr`].
+    ///
+    /// The reference held by `this` is transferred to the caller, who becomes responsible for
+    /// eventually releasing it, for example by passing it back to
+    /// [`from_raw_arc`](ArcCStr::from_raw_arc).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let foo = ArcCStr::try_from("foo").unwrap();
+    /// let raw = ArcCStr::into_raw_arc(foo);
+    /// assert_eq!(unsafe { raw.strong_count() }, 1);
+    /// let foo = unsafe { ArcCStr::from_raw_arc(raw) };
+    /// assert_eq!(foo.to_bytes(), b"foo");
+    /// ```
+    pub fn into_raw_arc(this: Self) -> RawArcCStr {
+        let raw = this.raw;
+        mem::forget(this);
+        raw
+    }
+
+    /// Consumes the `ArcCStr`, returning a pointer to the null-terminated string.
+    ///
+    /// The pointer can be handed to C code as an ordinary `const char *`, or stashed in a
+    /// type-erased context such as a callback's `void *`. The reference held by `this` is
+    /// transferred to the caller, and must eventually be released by passing the pointer back to
+    /// [`from_raw`](ArcCStr::from_raw) or
+    /// [`decrement_strong_count`](ArcCStr::decrement_strong_count), or the string is leaked.
+    ///
+    /// The string is copied first if it was made by [`from_external`](ArcCStr::from_external),
+    /// since there is no way back from its bytes to its reference counts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use std::ffi::CStr;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let ptr = ArcCStr::into_raw(ArcCStr::try_from("foo").unwrap());
+    /// assert_eq!(unsafe { CStr::from_ptr(ptr) }.to_bytes(), b"foo");
+    /// let foo = unsafe { ArcCStr::from_raw(ptr) };
+    /// assert_eq!(foo.to_bytes(), b"foo");
+    /// ```
+    pub fn into_raw(this: Self) -> *const c_char {
+        // from_raw finds the counts right in front of the bytes, which is not where an external
+        // string keeps them
+        let this = if unsafe { this.raw.header() }.is_external() {
+            ArcCStr::from(&*this)
+        } else {
+            this
+        };
+        ArcCStr::into_raw_arc(this).data().as_ptr() as *const c_char
+    }
+
+    /// Constructs an `ArcCStr` from a pointer returned by [`into_raw`](ArcCStr::into_raw),
+    /// taking over the reference that it held.
+    ///
+    /// The reference count is not changed.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `into_raw`, and the reference it holds must not have been
+    /// released already. Calling this twice for a single call to `into_raw` releases the
+    /// reference twice, which is a use-after-free.
+    pub unsafe fn from_raw(ptr: *const c_char) -> Self {
+        ArcCStr::from_raw_arc(RawArcCStr::from_data(NonNull::new_unchecked(
+            ptr as *mut u8,
+        )))
+    }
+
+    /// Increments the strong count of the string behind a pointer returned by
+    /// [`into_raw`](ArcCStr::into_raw).
+    ///
+    /// The new reference is also owned through `ptr`, and has to be released separately, for
+    /// example with [`decrement_strong_count`](ArcCStr::decrement_strong_count).
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `into_raw`, and must still hold at least one reference
+    /// for the duration of this call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let foo = ArcCStr::try_from("foo").unwrap();
+    /// let ptr = ArcCStr::into_raw(ArcCStr::clone(&foo));
+    /// unsafe { ArcCStr::increment_strong_count(ptr) };
+    /// assert_eq!(ArcCStr::strong_count(&foo), 3);
+    /// unsafe { ArcCStr::decrement_strong_count(ptr) };
+    /// unsafe { ArcCStr::decrement_strong_count(ptr) };
+    /// assert_eq!(ArcCStr::strong_count(&foo), 1);
+    /// ```
+    pub unsafe fn increment_strong_count(ptr: *const c_char) {
+        let this = mem::ManuallyDrop::new(ArcCStr::from_raw(ptr));
+        mem::forget(ArcCStr::clone(&this));
+    }
+
+    /// Releases one reference held through a pointer returned by
+    /// [`into_raw`](ArcCStr::into_raw).
+    ///
+    /// If it was the last one, the string is freed.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `into_raw`, and must still hold at least one reference,
+    /// which this releases.
+    pub unsafe fn decrement_strong_count(ptr: *const c_char) {
+        drop(ArcCStr::from_raw(ptr));
+    }
+
+    /// Constructs an `ArcCStr` that takes over one reference held through `raw`.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must point to a live allocation, and the caller must own one of the references
+    /// counted by its strong count. That reference is transferred to the returned `ArcCStr`.
+    pub const unsafe fn from_raw_arc(raw: RawArcCStr) -> Self {
+        ArcCStr { raw }
+    }
+
+    /// Returns the underlying [`RawArcCStr`] without affecting the reference count.
+    pub fn as_raw_arc(this: &Self) -> RawArcCStr {
+        this.raw
+    }
+
+    /// Returns a copy of the string's whole allocation: its header, the string bytes, and the
+    /// null terminator.
+    ///
+    /// This lets storage engines persist a string with its header in place, and later restore it
+    /// with [`from_allocation`](ArcCStr::from_allocation). The header in the copy always has a
+    /// reference count of one. See [`raw`](crate::raw) for the layout. Note that the header is
+    /// stored in native byte order, so images should not be moved between targets.
+    ///
+    /// This returns a copy rather than a reference to the allocation itself, since other threads
+    /// may update the reference count in the header at any time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let foo = ArcCStr::try_from("foo").unwrap();
+    /// let image = foo.to_allocation();
+    /// assert!(image.ends_with(b"foo\0"));
+    ///
+    /// let restored = unsafe { ArcCStr::from_allocation(image) };
+    /// assert_eq!(restored, foo);
+    /// ```
+    pub fn to_allocation(&self) -> Box<[u8]> {
+        unsafe { self.raw.to_image() }
+    }
+
+    /// Constructs an `ArcCStr` from an allocation image produced by
+    /// [`to_allocation`](ArcCStr::to_allocation).
+    ///
+    /// The image is copied into a fresh allocation with the alignment the header requires.
+    ///
+    /// # Safety
+    ///
+    /// `image` must be an unmodified image produced by `to_allocation` using the same version of
+    /// this crate on the same target.
+    pub unsafe fn from_allocation(image: Box<[u8]>) -> Self {
+        let raw = RawArcCStr::from_image(&image);
+        // the image's header may carry any counts; it now represents just us
+        raw.header().strong().store(1, Relaxed);
+        raw.header().weak().store(1, Relaxed);
+        let s = ArcCStr { raw };
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::construct(&s);
+        s
+    }
+
+    /// Returns the length of the string in bytes, excluding the null terminator.
+    ///
+    /// The length is stored alongside the string, so unlike `CStr::count_bytes` on most
+    /// platforms, this never has to search for the terminator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from("foo").unwrap().len(), 3);
+    /// assert!(ArcCStr::try_from("").unwrap().is_empty());
+    /// ```
+    #[inline]
+    pub fn len(&self) -> usize {
+        unsafe { self.raw.header() }.len()
+    }
+
+    /// Returns true if the string is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bytes of the string, excluding the null terminator.
+    ///
+    /// This is the same as [`CStr::to_bytes`], but uses the stored length directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from("foo").unwrap().as_bytes(), b"foo");
+    /// ```
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        // the string bytes live as long as we do
+        unsafe { core::slice::from_raw_parts(self.raw.bytes().as_ptr(), self.len()) }
+    }
+
+    /// Returns the bytes of the string, including the null terminator.
+    ///
+    /// This is the same as [`CStr::to_bytes_with_nul`], but uses the stored length directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from("foo").unwrap().as_bytes_with_nul(), b"foo\0");
+    /// ```
+    #[inline]
+    pub fn as_bytes_with_nul(&self) -> &[u8] {
+        // the terminator directly follows the string bytes
+        unsafe { core::slice::from_raw_parts(self.raw.bytes().as_ptr(), self.len() + 1) }
+    }
+
+    /// Returns an iterator over the bytes of the string, excluding the null terminator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("a1b2").unwrap();
+    /// let digits = s.bytes().filter(u8::is_ascii_digit).count();
+    /// assert_eq!(digits, 2);
+    /// ```
+    #[inline]
+    pub fn bytes(&self) -> core::iter::Copied<core::slice::Iter<'_, u8>> {
+        self.as_bytes().iter().copied()
+    }
+
+    /// Returns the first byte of the string, or `None` if it is empty.
+    ///
+    /// This reads the byte directly, without forming a `&CStr` or even reading the length: only
+    /// the empty string starts with its terminator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from("GET").unwrap().first_byte(), Some(b'G'));
+    /// assert_eq!(ArcCStr::try_from("").unwrap().first_byte(), None);
+    /// ```
+    #[inline]
+    pub fn first_byte(&self) -> Option<u8> {
+        // there is always at least the terminator to read
+        let b = unsafe { *self.raw.bytes().as_ptr() };
+        if b == 0 {
+            None
+        } else {
+            Some(b)
+        }
+    }
+
+    /// Returns the last byte of the string, excluding the null terminator, or `None` if it is
+    /// empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from("/static/").unwrap().last_byte(), Some(b'/'));
+    /// assert_eq!(ArcCStr::try_from("").unwrap().last_byte(), None);
+    /// ```
+    #[inline]
+    pub fn last_byte(&self) -> Option<u8> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        // the string bytes are in bounds
+        Some(unsafe { *self.raw.bytes().as_ptr().add(len - 1) })
+    }
+
+    /// Returns the first `n` bytes of the string, or the whole string (excluding the null
+    /// terminator) if it is shorter than that.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("/api/users").unwrap();
+    /// assert_eq!(s.prefix(4), b"/api");
+    /// assert_eq!(s.prefix(100), b"/api/users");
+    /// ```
+    #[inline]
+    pub fn prefix(&self, n: usize) -> &[u8] {
+        let len = self.len().min(n);
+        // the first len bytes are string bytes, which live as long as we do
+        unsafe { core::slice::from_raw_parts(self.raw.bytes().as_ptr(), len) }
+    }
+
+    /// Yields a `&str` slice if the string is valid UTF-8.
+    ///
+    /// This is equivalent to [`to_str`](ArcCStr::to_str).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from("foo").unwrap().try_as_str(), Ok("foo"));
+    /// assert!(ArcCStr::try_from(&b"f\xffo"[..]).unwrap().try_as_str().is_err());
+    /// ```
+    #[inline]
+    pub fn try_as_str(&self) -> Result<&str, Utf8Error> {
+        self.to_str()
+    }
+
+    /// Yields a `&str` slice if the string is valid UTF-8.
+    ///
+    /// Like [`CStr::to_str`](std::ffi::CStr::to_str), except that the result of checking the
+    /// string is remembered in its header, and shared by all handles to it. Only the first call
+    /// on an unchecked string has to look at its bytes; later calls on valid strings are O(1).
+    /// Strings constructed from a `str` are known to be UTF-8 from the start.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from(&b"caf\xc3\xa9"[..]).unwrap();
+    /// assert_eq!(s.to_str(), Ok("café")); // checks the bytes
+    /// assert_eq!(s.clone().to_str(), Ok("café")); // does not
+    /// ```
+    #[inline]
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        let header = unsafe { self.raw.header() };
+        match header.utf8() {
+            // the header flag is only set once the bytes have been checked
+            Some(true) => Ok(unsafe { core::str::from_utf8_unchecked(self.to_bytes()) }),
+            Some(false) => core::str::from_utf8(self.to_bytes()),
+            None => {
+                let r = core::str::from_utf8(self.to_bytes());
+                unsafe { header.set_utf8(r.is_ok()) };
+                r
+            }
+        }
+    }
+
+    /// Yields a `&str` slice if the string is valid UTF-8, or `None` if it is not.
+    ///
+    /// This is [`to_str`](ArcCStr::to_str) without the error, and is O(1) for strings that are
+    /// known to be valid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::try_from("foo").unwrap().as_str(), Some("foo"));
+    /// assert_eq!(ArcCStr::try_from(&b"f\xffo"[..]).unwrap().as_str(), None);
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        self.to_str().ok()
+    }
+
+    /// Returns a subslice of the string's bytes (excluding the null terminator), or `None` if
+    /// `range` is out of bounds.
+    ///
+    /// This is the non-panicking counterpart to slicing `to_bytes()`, like [`slice::get`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("GET /").unwrap();
+    /// assert_eq!(s.get(..3), Some(&b"GET"[..]));
+    /// assert_eq!(s.get(4..), Some(&b"/"[..]));
+    /// assert_eq!(s.get(4..6), None);
+    /// ```
+    #[inline]
+    pub fn get<R>(&self, range: R) -> Option<&[u8]>
+    where
+        R: SliceIndex<[u8], Output = [u8]>,
+    {
+        self.to_bytes().get(range)
+    }
+
+    /// Returns a subslice of the string as a `&str`, or `None` if the string is not valid UTF-8,
+    /// if `range` is out of bounds, or if it does not fall on character boundaries.
+    ///
+    /// This mirrors [`str::get`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("héllo").unwrap();
+    /// assert_eq!(s.get_str(..1), Some("h"));
+    /// assert_eq!(s.get_str(1..3), Some("é"));
+    /// assert_eq!(s.get_str(1..2), None);
+    /// assert_eq!(ArcCStr::try_from(&b"\xff"[..]).unwrap().get_str(..0), None);
+    /// ```
+    #[inline]
+    pub fn get_str<R>(&self, range: R) -> Option<&str>
+    where
+        R: SliceIndex<str, Output = str>,
+    {
+        self.to_str().ok()?.get(range)
+    }
+
+    /// Returns an iterator over the string's bytes (excluding the null terminator) in chunks of
+    /// `size` bytes.
+    ///
+    /// The last chunk is shorter if the length is not a multiple of `size`, and an empty string
+    /// has no chunks. This is [`slice::chunks`] on [`to_bytes`](CStr::to_bytes), for sending large
+    /// strings to sinks that take a limited amount at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("abcdefg").unwrap();
+    /// let chunks: Vec<&[u8]> = s.chunks(3).collect();
+    /// assert_eq!(chunks, [&b"abc"[..], b"def", b"g"]);
+    /// ```
+    #[inline]
+    pub fn chunks(&self, size: usize) -> core::slice::Chunks<'_, u8> {
+        self.to_bytes().chunks(size)
+    }
+
+    /// Returns the string's bytes (excluding the null terminator) in chunks of `size` bytes, as
+    /// [`IoSlice`](std::io::IoSlice)s.
+    ///
+    /// The chunks are those of [`chunks`](ArcCStr::chunks), ready to be collected into a batch
+    /// for [`Write::write_vectored`](std::io::Write::write_vectored).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use std::io::{IoSlice, Write};
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("abcdefg").unwrap();
+    /// let batch: Vec<IoSlice<'_>> = s.io_slices(4).collect();
+    /// let mut out = Vec::new();
+    /// let n = out.write_vectored(&batch).unwrap();
+    /// assert_eq!(&out[..n], &s.to_bytes()[..n]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn io_slices(&self, size: usize) -> impl Iterator<Item = std::io::IoSlice<'_>> {
+        self.chunks(size).map(std::io::IoSlice::new)
+    }
+
+    /// Copies the string into an [`OsString`], for passing to APIs such as
+    /// [`Command::env`](std::process::Command::env) or [`fs::write`](std::fs::write).
+    ///
+    /// On Unix, this is lossless. Elsewhere, OS strings are not arbitrary bytes, so the string is
+    /// interpreted as UTF-8, and any invalid sequences are replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use std::ffi::OsString;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let path = ArcCStr::try_from("/tmp/foo").unwrap();
+    /// assert_eq!(path.to_os_string(), OsString::from("/tmp/foo"));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn to_os_string(&self) -> OsString {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            std::ffi::OsStr::from_bytes(self.to_bytes()).to_os_string()
+        }
+        #[cfg(not(unix))]
+        {
+            OsString::from(self.to_string_lossy().into_owned())
+        }
+    }
+
+    /// Returns the string as an `OsStr`, without copying it.
+    ///
+    /// Only available on Unix, where OS strings are arbitrary bytes. Elsewhere, see
+    /// [`to_os_string`](ArcCStr::to_os_string).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::convert::TryFrom;
+    /// use std::ffi::OsStr;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let name = ArcCStr::try_from("hosts").unwrap();
+    /// assert_eq!(name.as_os_str(), OsStr::new("hosts"));
+    /// # }
+    /// ```
+    #[cfg(all(feature = "std", unix))]
+    pub fn as_os_str(&self) -> &OsStr {
+        use std::os::unix::ffi::OsStrExt;
+        OsStr::from_bytes(self.to_bytes())
+    }
+
+    /// Returns the string as a `Path`, without copying it.
+    ///
+    /// Only available on Unix. Since the string is also null-terminated, it can be passed both to
+    /// Rust APIs that take a path and, through [`as_ptr`](CStr::as_ptr), to system calls like
+    /// `open(2)`, with no conversions. `ArcCStr` also implements `AsRef<Path>` on Unix, so it
+    /// can be passed to functions like [`File::open`](std::fs::File::open) directly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::convert::TryFrom;
+    /// use std::path::Path;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let path = ArcCStr::try_from(Path::new("/etc/hosts")).unwrap();
+    /// assert_eq!(path.as_path().file_name().unwrap(), "hosts");
+    /// let _ = std::fs::metadata(&path);
+    /// # }
+    /// ```
+    #[cfg(all(feature = "std", unix))]
+    pub fn as_path(&self) -> &Path {
+        Path::new(self.as_os_str())
+    }
+
+    /// Returns true if the string's bytes (excluding the null terminator) are equal to `bytes`.
+    ///
+    /// This is the same as `self.to_bytes() == bytes`. Since the length of the string is stored
+    /// with it, strings of a different length are rejected without reading their bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let get = ArcCStr::try_from("GET").unwrap();
+    /// assert!(get.eq_bytes(b"GET"));
+    /// assert!(!get.eq_bytes(b"GETS"));
+    /// ```
+    #[inline]
+    pub fn eq_bytes(&self, bytes: &[u8]) -> bool {
+        self.to_bytes() == bytes
+    }
+
+    /// Compares the string's bytes (excluding the null terminator) to `bytes`.
+    ///
+    /// This is the same as `self.to_bytes().cmp(bytes)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let get = ArcCStr::try_from("GET").unwrap();
+    /// assert_eq!(get.cmp_bytes(b"GET"), Ordering::Equal);
+    /// assert_eq!(get.cmp_bytes(b"GE"), Ordering::Greater);
+    /// assert_eq!(get.cmp_bytes(b"PUT"), Ordering::Less);
+    /// ```
+    #[inline]
+    pub fn cmp_bytes(&self, bytes: &[u8]) -> Ordering {
+        self.to_bytes().cmp(bytes)
+    }
+}
+
+impl Clone for ArcCStr {
+    /// Makes a clone of the `ArcCStr` pointer.
+    ///
+    /// This creates another pointer to the same underlying string, increasing the reference count.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// ArcCStr::clone(&five);
+    /// ```
+    #[inline]
+    fn clone(&self) -> ArcCStr {
+        // we hold a reference, so the allocation is live
+        unsafe { self.raw.increment_strong_count() };
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::clone(self);
+        ArcCStr { raw: self.raw }
+    }
+}
+
+impl Deref for ArcCStr {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // as long as this arc is alive, we know that the allocation is too
+        unsafe { self.raw.as_cstr() }
+    }
+}
+
+impl Drop for ArcCStr {
+    /// Drops the `ArcCStr`.
+    ///
+    /// This will decrement the reference count. If the reference count reaches zero then we also
+    /// deallocate the underlying string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let foo  = ArcCStr::try_from("foo").unwrap();
+    /// let foo2 = ArcCStr::clone(&foo);
+    ///
+    /// drop(foo);    // "foo" is still in memory
+    /// drop(foo2);   // "foo" is deallocated
+    /// ```
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::drop(self);
+        unsafe {
+            if self.raw.decrement_strong_count() {
+                self.raw.deallocate();
+            }
+        }
+    }
+}
+
+impl PartialEq for ArcCStr {
+    /// Equality for two `ArcCStr`s.
+    ///
+    /// Two `ArcCStr`s are equal if their underlying strings are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5");
+    ///
+    /// assert_eq!(five, ArcCStr::try_from("5"));
+    /// assert_ne!(five, ArcCStr::try_from("6"));
+    /// ```
+    fn eq(&self, other: &ArcCStr) -> bool {
+        if ArcCStr::ptr_eq(self, other) {
+            return true;
+        }
+        #[cfg(feature = "fingerprint")]
+        {
+            let (a, b) = unsafe { (self.raw.header(), other.raw.header()) };
+            if a.fingerprint() != b.fingerprint() {
+                return false;
+            }
+        }
+        *(*self) == *(*other)
+    }
+}
+impl PartialOrd for ArcCStr {
+    /// Partial comparison for two `ArcCStr`s.
+    ///
+    /// The two are compared by calling `partial_cmp()` on their underlying strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    /// use std::cmp::Ordering;
+    /// use std::convert::TryFrom;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// assert_eq!(Some(Ordering::Less), five.partial_cmp(&ArcCStr::try_from("6").unwrap()));
+    /// ```
+    fn partial_cmp(&self, other: &ArcCStr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+
+    /// Less-than comparison for two `ArcCStr`s.
+    ///
+    /// The two are compared by calling `<` on their inner values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// assert!(five < ArcCStr::try_from("6").unwrap());
+    /// ```
+    fn lt(&self, other: &ArcCStr) -> bool {
+        *(*self) < *(*other)
+    }
+
+    /// 'Less than or equal to' comparison for two `ArcCStr`s.
+    ///
+    /// The two are compared by calling `<=` on their underlying strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// assert!(five <= ArcCStr::try_from("5").unwrap());
+    /// ```
+    fn le(&self, other: &ArcCStr) -> bool {
+        *(*self) <= *(*other)
+    }
+
+    /// Greater-than comparison for two `ArcCStr`s.
+    ///
+    /// The two are compared by calling `>` on their underlying strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// assert!(five > ArcCStr::try_from("4").unwrap());
+    /// ```
+    fn gt(&self, other: &ArcCStr) -> bool {
+        *(*self) > *(*other)
+    }
+
+    /// 'Greater than or equal to' comparison for two `ArcCStr`s.
+    ///
+    /// The two are compared by calling `>=` on their underlying strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// assert!(five >= ArcCStr::try_from("5").unwrap());
+    /// ```
+    fn ge(&self, other: &ArcCStr) -> bool {
+        *(*self) >= *(*other)
+    }
+}
+impl Ord for ArcCStr {
+    /// Comparison for two `ArcCStr`s.
+    ///
+    /// The two are compared by calling `cmp()` on their underlying strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    /// use std::cmp::Ordering;
+    /// use std::convert::TryFrom;
+    ///
+    /// let five = ArcCStr::try_from("5").unwrap();
+    ///
+    /// assert_eq!(Ordering::Less, five.cmp(&ArcCStr::try_from("6").unwrap()));
+    /// ```
+    fn cmp(&self, other: &ArcCStr) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+impl Eq for ArcCStr {}
+
+impl<const N: usize> PartialEq<[u8; N]> for ArcCStr {
+    /// Equality between an `ArcCStr` and a byte array.
+    ///
+    /// The array is compared against the bytes of the string, excluding the null terminator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let ping = ArcCStr::try_from("PING").unwrap();
+    ///
+    /// assert!(ping == *b"PING");
+    /// assert!(ping != *b"PING\0");
+    /// ```
+    fn eq(&self, other: &[u8; N]) -> bool {
+        self.to_bytes() == &other[..]
+    }
+}
+
+impl<'a, const N: usize> PartialEq<&'a [u8; N]> for ArcCStr {
+    /// Equality between an `ArcCStr` and a reference to a byte array.
+    ///
+    /// The array is compared against the bytes of the string, excluding the null terminator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let ping = ArcCStr::try_from("PING").unwrap();
+    ///
+    /// assert_eq!(ping, b"PING");
+    /// assert_ne!(ping, b"PONG");
+    /// ```
+    fn eq(&self, other: &&'a [u8; N]) -> bool {
+        *self == **other
+    }
+}
+
+/// Implements `PartialEq` and `PartialOrd` in both directions between `ArcCStr` and each given
+/// type, by comparing the bytes of the string, excluding the null terminator, with `$bytes`.
+macro_rules! impl_cmp_bytes {
+    ($([$($lt:lifetime)?] $ty:ty, |$other:ident| $bytes:expr;)*) => {$(
+        impl<$($lt)?> PartialEq<$ty> for ArcCStr {
+            #[inline]
+            fn eq(&self, $other: &$ty) -> bool {
+                self.to_bytes() == $bytes
+            }
+        }
+
+        impl<$($lt)?> PartialEq<ArcCStr> for $ty {
+            #[inline]
+            fn eq(&self, other: &ArcCStr) -> bool {
+                other == self
+            }
+        }
+
+        impl<$($lt)?> PartialOrd<$ty> for ArcCStr {
+            #[inline]
+            fn partial_cmp(&self, $other: &$ty) -> Option<Ordering> {
+                Some(self.to_bytes().cmp($bytes))
+            }
+        }
+
+        impl<$($lt)?> PartialOrd<ArcCStr> for $ty {
+            #[inline]
+            fn partial_cmp(&self, other: &ArcCStr) -> Option<Ordering> {
+                other.partial_cmp(self).map(Ordering::reverse)
+            }
+        }
+    )*};
+}
+
+impl_cmp_bytes! {
+    [] str, |other| other.as_bytes();
+    ['a] &'a str, |other| other.as_bytes();
+    [] String, |other| other.as_bytes();
+    [] CStr, |other| other.to_bytes();
+    ['a] &'a CStr, |other| other.to_bytes();
+    [] CString, |other| other.to_bytes();
+    [] [u8], |other| other;
+    ['a] &'a [u8], |other| *other;
+}
+
+impl fmt::Debug for ArcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for ArcCStr {
+    /// Writes the string, replacing any invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER` (like `CStr::to_string_lossy`).
+    ///
+    /// Width and alignment are only applied to strings that are valid UTF-8.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(s) = self.as_str() {
+            return f.pad(s);
+        }
+        let mut bytes = self.to_bytes();
+        loop {
+            match core::str::from_utf8(bytes) {
+                Ok(s) => return f.write_str(s),
+                Err(e) => {
+                    let (valid, rest) = bytes.split_at(e.valid_up_to());
+                    // from_utf8 just checked that this prefix is valid
+                    f.write_str(unsafe { core::str::from_utf8_unchecked(valid) })?;
+                    f.write_str("\u{FFFD}")?;
+                    match e.error_len() {
+                        Some(n) => bytes = &rest[n..],
+                        // the string ends in the middle of a character
+                        None => return Ok(()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Pointer for ArcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.raw, f)
+    }
+}
+
+impl Hash for ArcCStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl borrow::Borrow<CStr> for ArcCStr {
+    fn borrow(&self) -> &CStr {
+        self
+    }
+}
+
+impl AsRef<CStr> for ArcCStr {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+impl AsRef<[u8]> for ArcCStr {
+    /// Returns the bytes of the string, excluding the null terminator.
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl AsRef<OsStr> for ArcCStr {
+    fn as_ref(&self) -> &OsStr {
+        self.as_os_str()
+    }
+}
+
+#[cfg(all(feature = "std", unix))]
+impl AsRef<Path> for ArcCStr {
+    fn as_ref(&self) -> &Path {
+        self.as_path()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ArcCStr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        // human-readable formats would otherwise spell out text as a list of numbers
+        if serializer.is_human_readable() {
+            if let Some(s) = self.as_str() {
+                return serializer.serialize_str(s);
+            }
+        }
+        serializer.serialize_bytes(self.to_bytes())
+    }
+}
+
+#[cfg(feature = "serde")]
+struct ArcCStrVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for ArcCStrVisitor {
+    type Value = ArcCStr;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a C-style string with no nulls, as a string or as bytes")
+    }
+
+    #[inline]
+    fn visit_seq<A>(self, mut seq: A) -> Result<ArcCStr, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        // don't trust the hint too far, since it comes from the input
+        let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(value) = seq.next_element()? {
+            out.push(value);
+        }
+
+        let s = unsafe { ArcCStr::from_raw_cstr_no_nul(&out) };
+        let err = "a null-terminated, UTF-encoded string with no internal nulls";
+        s.map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Seq, &err))
+    }
+
+    #[inline]
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<ArcCStr, E>
+    where
+        E: serde::de::Error,
+    {
+        let s = unsafe { ArcCStr::from_raw_cstr_no_nul(v) };
+        let err = "a null-terminated, UTF-encoded string with no internal nulls";
+        s.map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Bytes(v), &err))
+    }
+
+    // Self-describing formats and string-only sources like URL paths and query strings hand us
+    // strings rather than bytes, even though we ask for bytes.
+    #[inline]
+    fn visit_str<E>(self, v: &str) -> Result<ArcCStr, E>
+    where
+        E: serde::de::Error,
+    {
+        let s = ArcCStr::from_str_no_nul(v);
+        let err = "a null-terminated, UTF-encoded string with no internal nulls";
+        s.map_err(|_| serde::de::Error::invalid_value(serde::de::Unexpected::Str(v), &err))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ArcCStr {
+    fn deserialize<D>(deserializer: D) -> Result<ArcCStr, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // a human-readable format may hold either a string or a list of bytes
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(ArcCStrVisitor)
+        } else {
+            deserializer.deserialize_bytes(ArcCStrVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCStr;
+    use std::clone::Clone;
+    use std::convert::TryFrom;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    #[test]
+    #[cfg_attr(any(miri, target_os = "emscripten"), ignore)]
+    fn manually_share_arc() {
+        let v = "0123456789";
+        let arc_v = ArcCStr::try_from(v).unwrap();
+
+        let (tx, rx) = channel();
+
+        let _t = thread::spawn(move || {
+            let arc_v: ArcCStr = rx.recv().unwrap();
+            assert_eq!((*arc_v).to_bytes()[3], b'3');
+        });
+
+        tx.send(arc_v.clone()).unwrap();
+
+        assert_eq!((*arc_v).to_bytes()[2], b'2');
+        assert_eq!((*arc_v).to_bytes()[4], b'4');
+    }
+
+    #[test]
+    fn show_arc() {
+        let a = ArcCStr::try_from("foo").unwrap();
+        assert_eq!(format!("{:?}", a), "\"foo\"");
+    }
+
+    #[test]
+    #[allow(clippy::useless_format)]
+    fn test_from_string() {
+        let foo_arc = ArcCStr::try_from(format!("foo")).unwrap();
+        assert!("foo" == foo_arc.to_string_lossy());
+    }
+
+    #[test]
+    fn test_ptr_eq() {
+        let five = ArcCStr::try_from("5").unwrap();
+        let same_five = five.clone();
+        let other_five = ArcCStr::try_from("5").unwrap();
+
+        assert!(ArcCStr::ptr_eq(&five, &same_five));
+        assert!(!ArcCStr::ptr_eq(&five, &other_five));
+    }
+
+    #[test]
+    #[allow(clippy::octal_escapes)]
+    fn test_from_invalid() {
+        assert!(ArcCStr::try_from("5\05").is_err());
+        assert!(ArcCStr::try_from("5\05".to_string()).is_err());
+        assert!(ArcCStr::try_from(&b"5\05"[..]).is_err());
+    }
+
+    #[test]
+    fn test_interior_nul_position() {
+        assert_eq!(
+            ArcCStr::try_from("\x0055"),
+            Err(crate::ArcCStrError::InteriorNul { pos: 0 })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "refcount-telemetry")]
+    fn test_max_strong_count() {
+        let a = ArcCStr::try_from("foo").unwrap();
+        assert_eq!(ArcCStr::max_strong_count(&a), 1);
+        let clones: Vec<_> = (0..4).map(|_| a.clone()).collect();
+        assert_eq!(ArcCStr::max_strong_count(&a), 5);
+        drop(clones);
+        assert_eq!(ArcCStr::strong_count(&a), 1);
+        assert_eq!(ArcCStr::max_strong_count(&a), 5);
+    }
+
+    #[test]
+    fn test_bounded() {
+        use crate::ArcCStrError;
+        assert!(ArcCStr::try_from_str_bounded("", 0).is_ok());
+        assert_eq!(
+            &*ArcCStr::try_from_str_bounded("abc", 3).unwrap(),
+            &*ArcCStr::try_from("abc").unwrap()
+        );
+        assert_eq!(
+            ArcCStr::try_from_str_bounded("abcd", 3),
+            Err(ArcCStrError::TooLong { len: 4, max_len: 3 })
+        );
+        assert_eq!(
+            ArcCStr::try_from_str_bounded("a\x00c", 3),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+
+    #[test]
+    fn test_back_to_str() {
+        // http://stackoverflow.com/a/3886015/472927
+        assert!(
+            ArcCStr::try_from(&b"a"[..]).unwrap().to_str().is_ok(),
+            "valid ASCII"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xc3\xb1"[..])
+                .unwrap()
+                .to_str()
+                .is_ok(),
+            "valid 2 Octet Sequence"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xc3\x28"[..])
+                .unwrap()
+                .to_str()
+                .is_err(),
+            "invalid 2 Octet Sequence"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xa0\xa1"[..])
+                .unwrap()
+                .to_str()
+                .is_err(),
+            "invalid Sequence Identifier"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xe2\x82\xa1"[..])
+                .unwrap()
+                .to_str()
+                .is_ok(),
+            "valid 3 Octet Sequence"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xe2\x28\xa1"[..])
+                .unwrap()
+                .to_str()
+                .is_err(),
+            "invalid 3 Octet Sequence (in 2nd Octet)"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xe2\x82\x28"[..])
+                .unwrap()
+                .to_str()
+                .is_err(),
+            "invalid 3 Octet Sequence (in 3rd Octet)"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xf0\x90\x8c\xbc"[..])
+                .unwrap()
+                .to_str()
+                .is_ok(),
+            "valid 4 Octet Sequence"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xf0\x28\x8c\xbc"[..])
+                .unwrap()
+                .to_str()
+                .is_err(),
+            "invalid 4 Octet Sequence (in 2nd Octet)"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xf0\x90\x28\xbc"[..])
+                .unwrap()
+                .to_str()
+                .is_err(),
+            "invalid 4 Octet Sequence (in 3rd Octet)"
+        );
+        assert!(
+            ArcCStr::try_from(&b"\xf0\x28\x8c\x28"[..])
+                .unwrap()
+                .to_str()
+                .is_err(),
+            "invalid 4 Octet Sequence (in 4th Octet)"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde() {
+        use serde_test::{assert_tokens, Configure, Token};
+        let five = ArcCStr::try_from("5").unwrap();
+        assert_tokens(&five.clone().compact(), &[Token::Bytes(b"5")]);
+        assert_tokens(&five.readable(), &[Token::Str("5")]);
+        let non = ArcCStr::try_from("").unwrap();
+        assert_tokens(&non.clone().compact(), &[Token::Bytes(b"")]);
+        assert_tokens(&non.readable(), &[Token::Str("")]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_readable_and_compact() {
+        use serde_test::{assert_de_tokens, assert_tokens, Configure, Token};
+        let s = ArcCStr::try_from(&b"f\xffo"[..]).unwrap();
+        // strings that are not UTF-8 are written as bytes even in human-readable formats
+        assert_tokens(&s.clone().readable(), &[Token::Bytes(b"f\xffo")]);
+        assert_tokens(&s.clone().compact(), &[Token::Bytes(b"f\xffo")]);
+        assert_de_tokens(&s.clone().readable(), &[Token::ByteBuf(b"f\xffo")]);
+        assert_de_tokens(
+            &s.compact(),
+            &[
+                Token::Seq { len: Some(3) },
+                Token::U8(b'f'),
+                Token::U8(0xff),
+                Token::U8(b'o'),
+                Token::SeqEnd,
+            ],
+        );
+        // and either form is read back
+        let foo = ArcCStr::try_from("foo").unwrap();
+        assert_de_tokens(&foo.clone().readable(), &[Token::Bytes(b"foo")]);
+        assert_de_tokens(&foo.compact(), &[Token::Str("foo")]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_from_str() {
+        use serde_test::{assert_de_tokens, assert_de_tokens_error, Configure, Readable, Token};
+        let foo = ArcCStr::try_from("foo").unwrap();
+        assert_de_tokens(&foo.clone().readable(), &[Token::Str("foo")]);
+        assert_de_tokens(&foo.readable(), &[Token::String("foo")]);
+        assert_de_tokens_error::<Readable<ArcCStr>>(
+            &[Token::Str("f\0o")],
+            "invalid value: string \"f\\0o\", expected \
+             a null-terminated, UTF-encoded string with no internal nulls",
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-from")]
+    fn test_into_string() {
+        let foo = ArcCStr::try_from("foo").unwrap();
+        assert_eq!(String::from(&foo), "foo");
+        let s: String = foo.into();
+        assert_eq!(s, "foo");
+        let invalid = ArcCStr::try_from(&b"f\xffo"[..]).unwrap();
+        assert_eq!(String::from(invalid), "f\u{FFFD}o");
+    }
+
+    #[test]
+    #[cfg(not(feature = "legacy-from"))]
+    fn test_try_into_string() {
+        use crate::ArcCStrError;
+        let foo = ArcCStr::try_from("foo").unwrap();
+        assert_eq!(String::try_from(&foo).unwrap(), "foo");
+        assert_eq!(String::try_from(foo).unwrap(), "foo");
+        let invalid = ArcCStr::try_from(&b"f\xffo"[..]).unwrap();
+        assert!(matches!(
+            String::try_from(invalid),
+            Err(ArcCStrError::Utf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_ptr() {
+        use crate::ArcCStrError;
+        use std::ffi::CString;
+
+        let c = CString::new("héllo").unwrap();
+        let s = unsafe { ArcCStr::from_ptr(c.as_ptr()) };
+        assert_eq!(s, "héllo");
+        assert_ne!(s.as_ptr(), c.as_ptr());
+        assert_eq!(ArcCStr::strong_count(&s), 1);
+
+        let bounded = |max_len| unsafe { ArcCStr::try_from_ptr_bounded(c.as_ptr(), max_len) };
+        assert_eq!(bounded(6).unwrap(), "héllo");
+        assert_eq!(bounded(usize::MAX).unwrap(), "héllo");
+        assert_eq!(bounded(5), Err(ArcCStrError::NotTerminated { max_len: 5 }));
+        let empty = CString::default();
+        let s = unsafe { ArcCStr::try_from_ptr_bounded(empty.as_ptr(), 0) };
+        assert_eq!(s.unwrap(), "");
+    }
+
+    #[test]
+    fn test_try_from_owned_and_os() {
+        use crate::ArcCStrError;
+        assert_eq!(ArcCStr::try_from(b"foo".to_vec()).unwrap(), "foo");
+        assert_eq!(
+            ArcCStr::try_from(b"f\0o".to_vec()),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+        let boxed: Box<[u8]> = Box::from(&b"foo"[..]);
+        assert_eq!(ArcCStr::try_from(boxed).unwrap(), "foo");
+        assert_eq!(ArcCStr::try_from(Box::<[u8]>::default()).unwrap(), "");
+        let boxed: Box<[u8]> = Box::from(&b"fo\0"[..]);
+        assert_eq!(
+            ArcCStr::try_from(boxed),
+            Err(ArcCStrError::InteriorNul { pos: 2 })
+        );
+        #[cfg(feature = "std")]
+        {
+            use std::ffi::{OsStr, OsString};
+            use std::path::{Path, PathBuf};
+            assert_eq!(ArcCStr::try_from(OsStr::new("foo")).unwrap(), "foo");
+            assert_eq!(ArcCStr::try_from(OsString::from("foo")).unwrap(), "foo");
+            assert_eq!(ArcCStr::try_from(Path::new("/a/b")).unwrap(), "/a/b");
+            assert_eq!(ArcCStr::try_from(PathBuf::from("/a/b")).unwrap(), "/a/b");
+            assert_eq!(
+                ArcCStr::try_from(Path::new("/a\0b")),
+                Err(ArcCStrError::InteriorNul { pos: 2 })
+            );
+            #[cfg(unix)]
+            {
+                use std::os::unix::ffi::OsStrExt;
+                let invalid = ArcCStr::try_from(OsStr::from_bytes(b"f\xffo")).unwrap();
+                assert_eq!(invalid, &b"f\xffo"[..]);
+                assert_eq!(invalid.as_os_str().as_bytes(), b"f\xffo");
+
+                let path = ArcCStr::try_from(Path::new("/a/b.txt")).unwrap();
+                assert_eq!(path.as_path().extension(), Some(OsStr::new("txt")));
+                assert_eq!(
+                    path.as_path().as_os_str().as_bytes().as_ptr(),
+                    path.as_ptr() as _
+                );
+                let as_ref: &Path = path.as_ref();
+                assert_eq!(as_ref, Path::new("/a/b.txt"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_cmp_other_types() {
+        use std::cmp::Ordering;
+        use std::ffi::{CStr, CString};
+
+        let root = ArcCStr::try_from("root").unwrap();
+        let string = String::from("root");
+        let c_string = CString::new("root").unwrap();
+        let c_str = CStr::from_bytes_with_nul(b"root\0").unwrap();
+        let bytes = &b"root"[..];
+        assert_eq!(root, "root");
+        assert_eq!("root", root);
+        assert_eq!(root, *"root");
+        assert_eq!(*"root", root);
+        assert_eq!(root, string);
+        assert_eq!(string, root);
+        assert_eq!(root, c_string);
+        assert_eq!(c_string, root);
+        assert_eq!(root, c_str);
+        assert_eq!(c_str, root);
+        assert_eq!(root, *c_str);
+        assert_eq!(*c_str, root);
+        assert_eq!(root, bytes);
+        assert_eq!(bytes, root);
+        assert_eq!(root, *bytes);
+        assert_eq!(*bytes, root);
+        assert_ne!(root, "roo");
+        assert_ne!(root, "root\0");
+        assert_ne!("rooted", root);
+
+        assert_eq!(root.partial_cmp("rooted"), Some(Ordering::Less));
+        assert_eq!("rooted".partial_cmp(&root), Some(Ordering::Greater));
+        assert_eq!(root.partial_cmp(&string), Some(Ordering::Equal));
+        assert_eq!(c_str.partial_cmp(&root), Some(Ordering::Equal));
+        let invalid = ArcCStr::try_from(&b"\xff"[..]).unwrap();
+        assert_eq!(invalid.partial_cmp("z"), Some(Ordering::Greater));
+        assert_eq!(invalid, &b"\xff"[..]);
+    }
+
+    #[test]
+    fn test_byte_accessors() {
+        let s = ArcCStr::try_from("a\u{e9}").unwrap();
+        assert_eq!(s.as_bytes(), s.to_bytes());
+        assert_eq!(s.as_bytes_with_nul(), s.to_bytes_with_nul());
+        assert_eq!(AsRef::<[u8]>::as_ref(&s), s.to_bytes());
+        assert_eq!(s.bytes().collect::<Vec<_>>(), [b'a', 0xc3, 0xa9]);
+        assert_eq!(ArcCStr::try_from("").unwrap().as_bytes_with_nul(), b"\0");
+
+        let s = ArcCStr::try_from("ab").unwrap();
+        assert_eq!((s.first_byte(), s.last_byte()), (Some(b'a'), Some(b'b')));
+        assert_eq!(s.prefix(0), b"");
+        assert_eq!(s.prefix(1), b"a");
+        assert_eq!(s.prefix(2), b"ab");
+        assert_eq!(s.prefix(3), b"ab");
+        let empty = ArcCStr::try_from("").unwrap();
+        assert_eq!((empty.first_byte(), empty.last_byte()), (None, None));
+        assert_eq!(empty.prefix(1), b"");
+    }
+
+    #[test]
+    fn test_std_pointers() {
+        use std::ffi::{CStr, CString};
+        use std::sync::Arc;
+
+        let foo = ArcCStr::try_from("foo").unwrap();
+        let arc: Arc<CStr> = Arc::from(&foo);
+        assert_eq!(&*arc, &*foo);
+        assert_eq!(ArcCStr::from(arc), foo);
+        let arc: Arc<CStr> = foo.clone().into();
+        assert_eq!(arc.to_bytes(), b"foo");
+        let boxed: Box<CStr> = CString::new("foo").unwrap().into_boxed_c_str();
+        assert_eq!(ArcCStr::from(boxed), foo);
+    }
+
+    #[test]
+    fn test_concat() {
+        let parts: Vec<ArcCStr> = ["a", "", "bc"]
+            .iter()
+            .map(|&p| ArcCStr::try_from(p).unwrap())
+            .collect();
+        let empty: &[ArcCStr] = &[];
+        let sep = std::ffi::CStr::from_bytes_with_nul(b", \0").unwrap();
+        assert_eq!(ArcCStr::concat(&parts).to_bytes(), b"abc");
+        assert_eq!(ArcCStr::join(&parts, sep).to_bytes(), b"a, , bc");
+        assert_eq!(ArcCStr::join(&parts[..1], sep).to_bytes(), b"a");
+        assert!(ArcCStr::concat(empty).is_empty());
+        assert!(ArcCStr::join(empty, sep).is_empty());
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut s = ArcCStr::try_from("Hello").unwrap();
+        assert_eq!(s.to_str(), Ok("Hello"));
+        {
+            let mut m = ArcCStr::get_mut(&mut s).unwrap();
+            m.make_ascii_uppercase();
+            m[0] = std::num::NonZeroU8::new(0xff).unwrap();
+            assert_eq!(m.as_bytes(), b"\xffELLO");
+        }
+        // the cached UTF-8 check was forgotten
+        assert!(s.to_str().is_err());
+        assert_eq!(s, ArcCStr::try_from(&b"\xffELLO"[..]).unwrap());
+
+        let weak = ArcCStr::downgrade(&s);
+        assert!(ArcCStr::get_mut(&mut s).is_none());
+        drop(weak);
+        let other = ArcCStr::clone(&s);
+        assert!(ArcCStr::get_mut(&mut s).is_none());
+        drop(other);
+        assert!(ArcCStr::get_mut(&mut s).is_some());
+        assert_eq!(ArcCStr::weak_count(&s), 0);
+    }
+
+    #[test]
+    fn test_chunks() {
+        let s = ArcCStr::try_from("abcdef").unwrap();
+        assert_eq!(s.chunks(2).collect::<Vec<_>>(), [b"ab", b"cd", b"ef"]);
+        assert_eq!(s.chunks(10).collect::<Vec<_>>(), [b"abcdef"]);
+        assert_eq!(ArcCStr::try_from("").unwrap().chunks(1).count(), 0);
+        #[cfg(feature = "std")]
+        {
+            let slices: Vec<_> = s.io_slices(4).collect();
+            assert_eq!(slices.len(), 2);
+            assert_eq!(&*slices[1], b"ef");
+        }
+    }
+
+    #[test]
+    fn test_lossy() {
+        for &(input, expected) in &[
+            (&b""[..], ""),
+            (b"\0", "\u{FFFD}"),
+            (b"a\xe2\x82", "a\u{FFFD}"),
+            (b"\xe2\x82a", "\u{FFFD}a"),
+            (b"\xffb\0\xc3\xa9\0", "\u{FFFD}b\u{FFFD}\u{e9}\u{FFFD}"),
+        ] {
+            let s = ArcCStr::from_utf8_lossy(input);
+            assert_eq!(s.as_str(), Some(expected));
+            if !input.contains(&0) {
+                assert_eq!(s.as_str(), Some(&*String::from_utf8_lossy(input)));
+            }
+        }
+
+        let s = ArcCStr::from_bytes_lossy(b"\0a\xff\0\0", b"<nul>");
+        assert_eq!(s.to_bytes(), b"<nul>a\xff<nul><nul>");
+        assert_eq!(s.len(), 17);
+        assert_eq!(ArcCStr::from_bytes_lossy(b"\0\0", b"").len(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_lossy_nul_marker() {
+        ArcCStr::from_bytes_lossy(b"a", b"\0");
+    }
+
+    #[test]
+    fn test_truncated() {
+        for max in 0..8 {
+            let s = ArcCStr::from_str_truncated("aé€😀", max).unwrap();
+            assert!(s.to_bytes().len() <= max);
+            assert!("aé€😀".starts_with(s.to_str().unwrap()));
+        }
+        let s = ArcCStr::from_str_truncated_with("abcdef", 4, "...").unwrap();
+        assert_eq!(s.to_str(), Ok("a..."));
+        let s = ArcCStr::from_str_truncated_with("abcdef", 2, "...").unwrap();
+        assert_eq!(s.to_str(), Ok(".."));
+        let s = ArcCStr::from_str_truncated_with("abcdef", 5, "…").unwrap();
+        assert_eq!(s.to_str(), Ok("ab…"));
+        // only the part that is kept has to be free of \0s
+        assert!(ArcCStr::from_str_truncated("ab\0", 2).is_ok());
+        assert_eq!(
+            ArcCStr::from_str_truncated_with("abcdef", 4, ".\0."),
+            Err(crate::ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+
+    #[test]
+    fn test_into_raw() {
+        let foo = ArcCStr::try_from("foo").unwrap();
+        let ptr = ArcCStr::into_raw(ArcCStr::clone(&foo));
+        assert_eq!(ptr, foo.as_ptr());
+        unsafe {
+            ArcCStr::increment_strong_count(ptr);
+            assert_eq!(ArcCStr::strong_count(&foo), 3);
+            let foo2 = ArcCStr::from_raw(ptr);
+            assert!(ArcCStr::ptr_eq(&foo, &foo2));
+            drop(foo2);
+            ArcCStr::decrement_strong_count(ptr);
+        }
+        assert_eq!(ArcCStr::strong_count(&foo), 1);
+    }
+
+    #[test]
+    fn test_try_unwrap() {
+        let foo = ArcCStr::try_from("foo").unwrap();
+        let weak = ArcCStr::downgrade(&foo);
+        let foo2 = ArcCStr::clone(&foo);
+        let foo = ArcCStr::try_unwrap(foo).unwrap_err();
+        drop(foo2);
+        assert_eq!(
+            ArcCStr::try_unwrap(foo),
+            Ok(std::ffi::CString::new("foo").unwrap())
+        );
+        // the string is gone, but the allocation lives on until the weak pointer is dropped
+        assert!(weak.upgrade().is_none());
+        assert_eq!(weak.strong_count(), 0);
+    }
+
+    #[test]
+    fn test_display() {
+        let foo = ArcCStr::try_from("foo").unwrap();
+        assert_eq!(format!("{}", foo), "foo");
+        assert_eq!(format!("[{:>5}]", foo), "[  foo]");
+        for bytes in [&b"f\xffo"[..], b"\xff\xfe", b"a\xe2\x82", b"\xe2\x82a\xf0"] {
+            let s = ArcCStr::try_from(bytes).unwrap();
+            assert_eq!(s.to_string(), String::from_utf8_lossy(bytes));
+        }
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "std"))]
+    fn test_to_os_string() {
+        use std::ffi::OsString;
+        use std::os::unix::ffi::OsStrExt;
+        let invalid = ArcCStr::try_from(&b"f\xffo"[..]).unwrap();
+        assert_eq!(invalid.to_os_string().as_bytes(), b"f\xffo");
+        assert_eq!(
+            OsString::from(invalid),
+            OsString::from(std::ffi::OsStr::from_bytes(b"f\xffo"))
+        );
+    }
+
+    #[test]
+    fn test_cmp_bytes() {
+        let strs: [&[u8]; 6] = [b"", b"a", b"ab", b"abc", b"b", b"\xff"];
+        for &a in &strs {
+            let arc = ArcCStr::try_from(a).unwrap();
+            for &b in strs.iter().chain(&[&b"a\0"[..], &b"\0"[..]]) {
+                assert_eq!(arc.cmp_bytes(b), a.cmp(b), "{:?} vs {:?}", a, b);
+                assert_eq!(arc.eq_bytes(b), a == b);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_json() {
+        use serde_json;
+        let original = ArcCStr::try_from("hello").unwrap();
+        let serialized = serde_json::to_string(&original).unwrap();
+        assert_eq!(serialized, r#""hello""#);
+        let deserialized: ArcCStr = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(original, deserialized);
+
+        let invalid = ArcCStr::try_from(&b"h\xffi"[..]).unwrap();
+        let serialized = serde_json::to_string(&invalid).unwrap();
+        assert_eq!(serialized, "[104,255,105]");
+        let deserialized: ArcCStr = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(invalid, deserialized);
+        // bytes are accepted for valid UTF-8 too
+        let deserialized: ArcCStr = serde_json::from_str("[104,105]").unwrap();
+        assert_eq!(deserialized, "hi");
+    }
+
+    mod round_trip {
+        use crate::{ArcCStr, SmallCStr};
+        use proptest::prelude::*;
+        use std::convert::TryFrom;
+        use std::ffi::{CStr, CString};
+
+        fn nul_free() -> impl Strategy<Value = Vec<u8>> {
+            proptest::collection::vec(1u8..=255, 0..64)
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(if cfg!(miri) { 4 } else { 256 }))]
+
+            #[test]
+            fn from_every_source(bytes in nul_free()) {
+                let cstring = CString::new(bytes.clone()).unwrap();
+                let mut arcs = vec![
+                    ArcCStr::try_from(&bytes[..]).unwrap(),
+                    ArcCStr::from(cstring.clone()),
+                    ArcCStr::from(&*cstring),
+                    SmallCStr::<64>::try_from(&bytes[..]).unwrap().to_arc(),
+                ];
+                if let Ok(s) = std::str::from_utf8(&bytes) {
+                    arcs.push(ArcCStr::try_from(s).unwrap());
+                    arcs.push(ArcCStr::try_from(s.to_string()).unwrap());
+                }
+                for arc in arcs {
+                    prop_assert_eq!(arc.to_bytes(), &bytes[..]);
+                    prop_assert_eq!(&*arc, &*cstring);
+                    prop_assert!(arc.eq_bytes(&bytes));
+                }
+            }
+
+            #[test]
+            #[cfg(feature = "serde")]
+            fn through_serde(bytes in nul_free()) {
+                let arc = ArcCStr::try_from(&bytes[..]).unwrap();
+                let json = serde_json::to_string(&arc).unwrap();
+                let back: ArcCStr = serde_json::from_str(&json).unwrap();
+                prop_assert_eq!(back.to_bytes(), &bytes[..]);
+            }
+
+            #[test]
+            fn through_raw(bytes in nul_free()) {
+                let arc = ArcCStr::try_from(&bytes[..]).unwrap();
+                let ptr = arc.as_ptr();
+                prop_assert_eq!(unsafe { CStr::from_ptr(ptr) }.to_bytes(), &bytes[..]);
+
+                let raw = ArcCStr::into_raw_arc(arc);
+                let arc = unsafe { ArcCStr::from_raw_arc(raw) };
+                prop_assert_eq!(arc.as_ptr(), ptr);
+                prop_assert_eq!(arc.to_bytes(), &bytes[..]);
+
+                let restored = unsafe { ArcCStr::from_allocation(arc.to_allocation()) };
+                prop_assert_eq!(restored.to_bytes(), &bytes[..]);
+            }
+        }
+    }
+}
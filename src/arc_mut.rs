@@ -0,0 +1,81 @@
+use crate::ArcCStr;
+use core::fmt;
+use core::num::NonZeroU8;
+use core::ops::{Deref, DerefMut};
+use core::slice;
+
+/// Mutable access to the bytes of an [`ArcCStr`] that has no other pointers to it.
+///
+/// This is returned by [`ArcCStr::get_mut`]. It dereferences to the string's bytes, excluding
+/// the null terminator, as a slice of [`NonZeroU8`], which keeps a `\0` from being written into
+/// the middle of the string. [`as_bytes`](ArcCStrMut::as_bytes) gives a plain byte view, and the
+/// most common in-place fix-ups have methods of their own.
+///
+/// The string's cached facts about its contents, such as whether it is UTF-8, are updated when
+/// the `ArcCStrMut` is dropped. Leaking it with [`mem::forget`](core::mem::forget) is safe, but
+/// with the `fingerprint` feature enabled, the string may then compare unequal to strings with
+/// the same contents.
+pub struct ArcCStrMut<'a> {
+    s: &'a mut ArcCStr,
+}
+
+impl<'a> ArcCStrMut<'a> {
+    /// The caller must have checked that `s` is the only reference to its string.
+    pub(crate) unsafe fn new(s: &'a mut ArcCStr) -> Self {
+        // forget that the contents are UTF-8 right away, in case we are leaked
+        ArcCStr::as_raw_arc(s).contents_changed();
+        ArcCStrMut { s }
+    }
+
+    /// Returns the string's bytes, excluding the null terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.s.to_bytes()
+    }
+
+    /// Converts the string to ASCII lower case in place.
+    pub fn make_ascii_lowercase(&mut self) {
+        for b in self.iter_mut() {
+            // lowercasing never produces a \0
+            *b = NonZeroU8::new(b.get().to_ascii_lowercase()).expect("non-zero");
+        }
+    }
+
+    /// Converts the string to ASCII upper case in place.
+    pub fn make_ascii_uppercase(&mut self) {
+        for b in self.iter_mut() {
+            // uppercasing never produces a \0
+            *b = NonZeroU8::new(b.get().to_ascii_uppercase()).expect("non-zero");
+        }
+    }
+}
+
+impl Deref for ArcCStrMut<'_> {
+    type Target = [NonZeroU8];
+
+    fn deref(&self) -> &[NonZeroU8] {
+        let bytes = self.s.to_bytes();
+        // the string bytes contain no \0, and NonZeroU8 has the same layout as u8
+        unsafe { slice::from_raw_parts(bytes.as_ptr() as *const NonZeroU8, bytes.len()) }
+    }
+}
+
+impl DerefMut for ArcCStrMut<'_> {
+    fn deref_mut(&mut self) -> &mut [NonZeroU8] {
+        let raw = ArcCStr::as_raw_arc(self.s);
+        // we hold the only reference, so nobody else can be reading the bytes, and only non-zero
+        // bytes can be written through the slice
+        unsafe { slice::from_raw_parts_mut(raw.data().as_ptr() as *mut NonZeroU8, self.s.len()) }
+    }
+}
+
+impl Drop for ArcCStrMut<'_> {
+    fn drop(&mut self) {
+        unsafe { ArcCStr::as_raw_arc(self.s).contents_changed() };
+    }
+}
+
+impl fmt::Debug for ArcCStrMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self.s, f)
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{ArcCStr, ArcCStrError};
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// A thread-safe reference-counted string that is known to be valid UTF-8.
+///
+/// `ArcStr` is an [`ArcCStr`] whose contents have been checked to be UTF-8 once, when it was
+/// created, so that it can dereference to [`str`] without checking again. It uses the same
+/// single-pointer representation and allocation as `ArcCStr`, so it is still null-terminated and
+/// cannot contain an internal `\0`, and converting between the two never copies the string.
+///
+/// Like `ArcCStr`, the methods that manage the reference count are associated functions, to avoid
+/// clashing with the methods of `str`.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, ArcStr};
+///
+/// let hello = ArcStr::try_from("héllo").unwrap();
+/// assert_eq!(hello, "héllo");
+/// assert_eq!(hello.chars().count(), 5);
+/// assert_eq!(format!("{}!", hello), "héllo!");
+///
+/// // converting back and forth reuses the allocation
+/// let c = ArcCStr::from(hello.clone());
+/// assert_eq!(c.as_ptr(), hello.as_c_str().as_ptr());
+/// let again = ArcStr::try_from(c).unwrap();
+/// assert!(ArcStr::ptr_eq(&again, &hello));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct ArcStr {
+    inner: ArcCStr,
+}
+
+impl ArcStr {
+    /// Returns the string as a `&str`.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self
+    }
+
+    /// Returns the string as a `&CStr`, including its null terminator.
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        &self.inner
+    }
+
+    /// Returns the string as an [`ArcCStr`] pointing to the same allocation.
+    #[inline]
+    pub fn as_arc_cstr(this: &Self) -> &ArcCStr {
+        &this.inner
+    }
+
+    /// Gets the number of pointers to this string, counting both `ArcStr`s and `ArcCStr`s.
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        ArcCStr::strong_count(&this.inner)
+    }
+
+    /// Returns true if the two `ArcStr`s point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        ArcCStr::ptr_eq(&this.inner, &other.inner)
+    }
+}
+
+impl Deref for ArcStr {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        // the contents were checked to be UTF-8 when the ArcStr was created, and never change
+        unsafe { core::str::from_utf8_unchecked(self.inner.to_bytes()) }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ArcStr {
+    type Error = ArcCStrError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        Ok(ArcStr {
+            inner: ArcCStr::try_from(s)?,
+        })
+    }
+}
+
+impl TryFrom<String> for ArcStr {
+    type Error = ArcCStrError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        ArcStr::try_from(&*s)
+    }
+}
+
+impl TryFrom<ArcCStr> for ArcStr {
+    type Error = ArcCStrError;
+
+    /// Checks that the string is UTF-8, and converts it into an `ArcStr` without copying it.
+    fn try_from(s: ArcCStr) -> Result<Self, Self::Error> {
+        s.to_str()?;
+        Ok(ArcStr { inner: s })
+    }
+}
+
+impl From<ArcStr> for ArcCStr {
+    /// Converts the string into an `ArcCStr` without copying it.
+    #[inline]
+    fn from(s: ArcStr) -> Self {
+        s.inner
+    }
+}
+
+impl<'a> From<&'a ArcStr> for String {
+    fn from(s: &'a ArcStr) -> Self {
+        String::from(&**s)
+    }
+}
+
+impl PartialOrd for ArcStr {
+    fn partial_cmp(&self, other: &ArcStr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArcStr {
+    fn cmp(&self, other: &ArcStr) -> Ordering {
+        self.inner.cmp(&other.inner)
+    }
+}
+
+impl PartialEq<str> for ArcStr {
+    fn eq(&self, other: &str) -> bool {
+        &**self == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for ArcStr {
+    fn eq(&self, other: &&'a str) -> bool {
+        &**self == *other
+    }
+}
+
+impl PartialEq<ArcStr> for str {
+    fn eq(&self, other: &ArcStr) -> bool {
+        self == &**other
+    }
+}
+
+impl PartialEq<ArcStr> for &str {
+    fn eq(&self, other: &ArcStr) -> bool {
+        *self == &**other
+    }
+}
+
+impl Hash for ArcStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // like str, so that Borrow<str> is sound
+        (**self).hash(state)
+    }
+}
+
+impl Borrow<str> for ArcStr {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
+
+impl AsRef<str> for ArcStr {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl AsRef<CStr> for ArcStr {
+    fn as_ref(&self) -> &CStr {
+        &self.inner
+    }
+}
+
+impl AsRef<[u8]> for ArcStr {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl fmt::Display for ArcStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+impl fmt::Debug for ArcStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Pointer for ArcStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.inner, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcStr;
+    use crate::{ArcCStr, ArcCStrError};
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn conversions() {
+        let c = ArcCStr::try_from("foo").unwrap();
+        let s = ArcStr::try_from(ArcCStr::clone(&c)).unwrap();
+        assert_eq!(ArcStr::strong_count(&s), 2);
+        assert!(ArcCStr::ptr_eq(ArcStr::as_arc_cstr(&s), &c));
+        assert_eq!(ArcCStr::from(s), c);
+
+        let invalid = ArcCStr::try_from(&b"f\xffo"[..]).unwrap();
+        assert!(matches!(
+            ArcStr::try_from(invalid),
+            Err(ArcCStrError::Utf8(_))
+        ));
+        assert_eq!(
+            ArcStr::try_from("f\0o"),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+
+    #[test]
+    fn as_str() {
+        let s = ArcStr::try_from(String::from("héllo")).unwrap();
+        assert_eq!(s.as_str(), "héllo");
+        assert_eq!(s.as_c_str().to_bytes(), "héllo".as_bytes());
+        assert_eq!(String::from(&s), "héllo");
+        assert_eq!(format!("{:?}", s), "\"héllo\"");
+        assert!("héllo" == s);
+    }
+
+    #[test]
+    fn borrow_str() {
+        let mut set = HashSet::new();
+        set.insert(ArcStr::try_from("a").unwrap());
+        assert!(set.contains("a"));
+        assert!(!set.contains("b"));
+    }
+}
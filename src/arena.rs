@@ -0,0 +1,261 @@
+use crate::ArcCStrError;
+use std::alloc::{self, Layout};
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ptr::{self, NonNull};
+use std::slice;
+
+/// The size of the first chunk an arena allocates.
+const FIRST_CHUNK: usize = 4096;
+/// The size beyond which chunks stop doubling.
+const MAX_CHUNK: usize = 1 << 20;
+
+/// A string interner that stores its strings in a bump arena, and frees them all at once.
+///
+/// Unlike [`Interner`](crate::Interner), which hands out reference-counted [`ArcCStr`]s that are
+/// each allocated and freed on their own, an `ArenaInterner` copies every distinct string into a
+/// few large chunks of memory, and hands out plain `&CStr`s that borrow from it. Interning a new
+/// string is then little more than a copy, there are no reference counts to update, and dropping
+/// the arena frees every string in one go. The borrow checker makes sure that no string outlives
+/// the arena.
+///
+/// This suits phases of a program, like a compiler pass, that create a great many strings that
+/// all live exactly as long as the phase. The arena is meant for a single thread: it can be sent
+/// to another thread, but not shared between threads.
+///
+/// [`ArcCStr`]: crate::ArcCStr
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::ArenaInterner;
+///
+/// let arena = ArenaInterner::new();
+/// let a = arena.intern("ident").unwrap();
+/// let b = arena.intern(String::from("ident")).unwrap();
+/// assert!(std::ptr::eq(a, b));
+/// assert_eq!(a.to_bytes(), b"ident");
+/// assert_eq!(arena.len(), 1);
+/// ```
+pub struct ArenaInterner {
+    inner: RefCell<Inner>,
+}
+
+struct Inner {
+    chunks: Vec<Chunk>,
+    // the unused part of the last chunk
+    next: *mut u8,
+    left: usize,
+    strings: HashSet<Key>,
+}
+
+/// A block of memory that strings are bump-allocated from.
+struct Chunk {
+    ptr: NonNull<u8>,
+    size: usize,
+}
+
+impl Chunk {
+    fn layout(size: usize) -> Layout {
+        Layout::array::<u8>(size).expect("chunk too large")
+    }
+}
+
+/// A string in the arena, including its terminator.
+///
+/// The `'static` is a lie: the bytes live only as long as the arena, which never hands them out
+/// for longer than it is borrowed.
+struct Key(&'static [u8]);
+
+impl Key {
+    fn to_bytes(&self) -> &[u8] {
+        &self.0[..self.0.len() - 1]
+    }
+
+    fn as_c_str(&self) -> &'static CStr {
+        // the bytes were checked for \0 before they were copied in, and end with the terminator
+        unsafe { CStr::from_bytes_with_nul_unchecked(self.0) }
+    }
+}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bytes().hash(state)
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for Key {}
+
+impl Borrow<[u8]> for Key {
+    fn borrow(&self) -> &[u8] {
+        self.to_bytes()
+    }
+}
+
+// the arena owns its chunks, and the keys only point into them
+unsafe impl Send for ArenaInterner {}
+
+impl ArenaInterner {
+    /// Constructs an empty arena.
+    ///
+    /// Nothing is allocated until the first string is interned.
+    pub fn new() -> Self {
+        ArenaInterner {
+            inner: RefCell::new(Inner {
+                chunks: Vec::new(),
+                next: ptr::null_mut(),
+                left: 0,
+                strings: HashSet::new(),
+            }),
+        }
+    }
+
+    /// Returns the string equal to `s` in the arena, copying it in first if there is none.
+    ///
+    /// Fails with [`ArcCStrError::InteriorNul`] if `s` contains a `\0`, and with
+    /// [`ArcCStrError::Alloc`] if the arena could not grow.
+    pub fn intern<B: AsRef<[u8]>>(&self, s: B) -> Result<&CStr, ArcCStrError> {
+        let s = s.as_ref();
+        ArcCStrError::check_no_nul(s)?;
+        let mut inner = self.inner.borrow_mut();
+        if let Some(key) = inner.strings.get(s) {
+            return Ok(key.as_c_str());
+        }
+        let ptr = inner.alloc(s.len() + 1)?;
+        let key = unsafe {
+            ptr::copy_nonoverlapping(s.as_ptr(), ptr, s.len());
+            *ptr.add(s.len()) = 0;
+            Key(slice::from_raw_parts(ptr, s.len() + 1))
+        };
+        let c = key.as_c_str();
+        inner.strings.insert(key);
+        Ok(c)
+    }
+
+    /// Returns the string equal to `s` in the arena, if there is one.
+    pub fn get<B: AsRef<[u8]>>(&self, s: B) -> Option<&CStr> {
+        let inner = self.inner.borrow();
+        inner.strings.get(s.as_ref()).map(Key::as_c_str)
+    }
+
+    /// Returns the number of distinct strings in the arena.
+    pub fn len(&self) -> usize {
+        self.inner.borrow().strings.len()
+    }
+
+    /// Returns true if the arena holds no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the total size of the chunks the arena has allocated, in bytes.
+    pub fn allocated_bytes(&self) -> usize {
+        self.inner.borrow().chunks.iter().map(|c| c.size).sum()
+    }
+}
+
+impl Inner {
+    /// Returns a pointer to `n` unused bytes.
+    fn alloc(&mut self, n: usize) -> Result<*mut u8, ArcCStrError> {
+        if n > self.left {
+            let size = match self.chunks.last() {
+                Some(last) => (last.size * 2).min(MAX_CHUNK),
+                None => FIRST_CHUNK,
+            };
+            // a string too long for a chunk gets a chunk of its own
+            let size = size.max(n);
+            let ptr = NonNull::new(unsafe { alloc::alloc(Chunk::layout(size)) })
+                .ok_or(ArcCStrError::Alloc)?;
+            self.chunks.push(Chunk { ptr, size });
+            self.next = ptr.as_ptr();
+            self.left = size;
+        }
+        let ptr = self.next;
+        self.next = unsafe { self.next.add(n) };
+        self.left -= n;
+        Ok(ptr)
+    }
+}
+
+impl Drop for ArenaInterner {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut();
+        inner.strings.clear();
+        for chunk in inner.chunks.drain(..) {
+            unsafe { alloc::dealloc(chunk.ptr.as_ptr(), Chunk::layout(chunk.size)) };
+        }
+    }
+}
+
+impl Default for ArenaInterner {
+    fn default() -> Self {
+        ArenaInterner::new()
+    }
+}
+
+impl fmt::Debug for ArenaInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaInterner")
+            .field("len", &self.len())
+            .field("allocated_bytes", &self.allocated_bytes())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArenaInterner, FIRST_CHUNK};
+    use crate::ArcCStrError;
+
+    #[test]
+    fn shares() {
+        let arena = ArenaInterner::new();
+        assert_eq!(arena.allocated_bytes(), 0);
+        let strings: Vec<_> = (0..10_000)
+            .map(|i| arena.intern((i % 100).to_string()).unwrap())
+            .collect();
+        assert_eq!(arena.len(), 100);
+        assert_eq!(arena.allocated_bytes(), FIRST_CHUNK);
+        for (i, s) in strings.iter().enumerate() {
+            assert_eq!(s.to_str(), Ok(&*(i % 100).to_string()));
+            assert!(std::ptr::eq(*s, strings[i % 100]));
+        }
+        assert!(std::ptr::eq(arena.get("42").unwrap(), strings[42]));
+        assert!(arena.get("100").is_none());
+    }
+
+    #[test]
+    fn chunks() {
+        let arena = ArenaInterner::new();
+        let big = "x".repeat(FIRST_CHUNK * 3);
+        let a = arena.intern("a").unwrap();
+        let b = arena.intern(&big).unwrap();
+        let c = arena.intern("c").unwrap();
+        assert_eq!(a.to_bytes(), b"a");
+        assert_eq!(b.to_bytes(), big.as_bytes());
+        assert_eq!(c.to_bytes(), b"c");
+        // the big string gets a chunk of its own, and the next chunk is twice that size
+        assert_eq!(arena.allocated_bytes(), FIRST_CHUNK + (big.len() + 1) * 3);
+        assert!(arena.intern("").unwrap().to_bytes().is_empty());
+    }
+
+    #[test]
+    fn nul() {
+        let arena = ArenaInterner::new();
+        assert_eq!(
+            arena.intern("a\0"),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+        assert!(arena.is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::pool::ArcCStrPool;
+use crate::{ArcCStr, ArcCStrError};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec::{self, Vec};
+use core::ffi::c_char;
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::Deref;
+use core::slice;
+
+/// A batch of [`ArcCStr`]s that all live in one shared allocation.
+///
+/// Collecting strings into an array copies them next to each other into a single allocation
+/// that is sized to fit them exactly, and drops the originals. This is meant for batches of
+/// strings that stay around long after they were ingested. Compacting such a batch replaces
+/// the allocator's bookkeeping and fragmentation for each string with one allocation for all of
+/// them, and keeps strings that are read together close in memory. Strings that shared an
+/// allocation before, like clones of one interned string, still share one afterwards.
+///
+/// The strings are allocated the way an [`ArcCStrPool`] allocates them. They are ordinary
+/// `ArcCStr`s that can be cloned out of the array and outlive it. The shared allocation is only
+/// freed once every one of them is dropped, and each string takes two more words than one
+/// allocated on its own.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, ArcCStrArray};
+///
+/// let ingested: Vec<_> = ["alpha", "beta", "alpha"]
+///     .iter()
+///     .map(|&s| ArcCStr::try_from(s).unwrap())
+///     .collect();
+/// let batch: ArcCStrArray = ingested.into_iter().collect();
+/// assert_eq!(batch.len(), 3);
+/// assert_eq!(batch[1], "beta");
+/// // the strings were copied next to each other
+/// assert!(batch[1].as_ptr() > batch[0].as_ptr());
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArcCStrArray {
+    strings: Box<[ArcCStr]>,
+}
+
+impl ArcCStrArray {
+    /// Compacts `strings` into one new allocation, and drops the originals.
+    ///
+    /// Fails with [`ArcCStrError::Alloc`] if the allocation could not be made.
+    pub fn try_from_iter<I: IntoIterator<Item = ArcCStr>>(
+        strings: I,
+    ) -> Result<Self, ArcCStrError> {
+        let originals: Vec<ArcCStr> = strings.into_iter().collect();
+
+        // copy each allocation only once, in the order they first appear
+        let mut seen: BTreeMap<*const c_char, usize> = BTreeMap::new();
+        let mut distinct = Vec::new();
+        let slots: Vec<usize> = originals
+            .iter()
+            .map(|s| {
+                *seen.entry(s.as_ptr()).or_insert_with(|| {
+                    distinct.push(s);
+                    distinct.len() - 1
+                })
+            })
+            .collect();
+
+        let mut pool = ArcCStrPool::for_lengths(distinct.iter().map(|s| s.to_bytes().len()))
+            .ok_or(ArcCStrError::Alloc)?;
+        let copies = distinct
+            .iter()
+            .map(|s| pool.alloc(s.to_bytes()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let strings = slots.iter().map(|&i| ArcCStr::clone(&copies[i])).collect();
+        Ok(ArcCStrArray { strings })
+    }
+
+    /// Returns the strings in the array.
+    #[inline]
+    pub fn as_slice(&self) -> &[ArcCStr] {
+        &self.strings
+    }
+
+    /// Converts the array into a vector of its strings, without copying them.
+    pub fn into_vec(self) -> Vec<ArcCStr> {
+        self.strings.into_vec()
+    }
+}
+
+/// Compacts the strings into one new allocation, and drops the originals.
+///
+/// # Panics
+///
+/// Panics if the allocation could not be made. See [`ArcCStrArray::try_from_iter`] for a
+/// version that fails instead.
+impl FromIterator<ArcCStr> for ArcCStrArray {
+    fn from_iter<I: IntoIterator<Item = ArcCStr>>(strings: I) -> Self {
+        Self::try_from_iter(strings).expect("could not allocate memory")
+    }
+}
+
+impl Deref for ArcCStrArray {
+    type Target = [ArcCStr];
+
+    #[inline]
+    fn deref(&self) -> &[ArcCStr] {
+        &self.strings
+    }
+}
+
+impl AsRef<[ArcCStr]> for ArcCStrArray {
+    #[inline]
+    fn as_ref(&self) -> &[ArcCStr] {
+        &self.strings
+    }
+}
+
+impl From<ArcCStrArray> for Vec<ArcCStr> {
+    fn from(array: ArcCStrArray) -> Self {
+        array.into_vec()
+    }
+}
+
+impl IntoIterator for ArcCStrArray {
+    type Item = ArcCStr;
+    type IntoIter = vec::IntoIter<ArcCStr>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ArcCStrArray {
+    type Item = &'a ArcCStr;
+    type IntoIter = slice::Iter<'a, ArcCStr>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.strings.iter()
+    }
+}
+
+impl fmt::Debug for ArcCStrArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.strings.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCStrArray;
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn compacts() {
+        let shared = ArcCStr::try_from("shared").unwrap();
+        let long = "x".repeat(100_000);
+        let strings = vec![
+            ArcCStr::clone(&shared),
+            ArcCStr::try_from("").unwrap(),
+            ArcCStr::try_from(&*long).unwrap(),
+            ArcCStr::clone(&shared),
+            ArcCStr::try_from("shared").unwrap(),
+        ];
+        let weak = ArcCStr::downgrade(&strings[2]);
+        let array: ArcCStrArray = strings.iter().cloned().collect();
+        assert_eq!(&*array, &*strings);
+
+        // the originals are no longer referenced
+        drop(strings);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(ArcCStr::strong_count(&shared), 1);
+
+        // clones of one string stay shared, and equal strings in different allocations do not
+        assert!(ArcCStr::ptr_eq(&array[0], &array[3]));
+        assert!(!ArcCStr::ptr_eq(&array[0], &array[4]));
+
+        // even strings too long for an ordinary pool are in the same allocation
+        let start = array[0].as_ptr() as usize;
+        let end = array[4].as_ptr() as usize;
+        assert!(start < end && end - start < 2 * long.len());
+        assert_eq!(array[2].to_bytes(), long.as_bytes());
+
+        // the strings outlive the array
+        let first = array.into_vec().swap_remove(0);
+        assert_eq!(first, "shared");
+    }
+
+    #[test]
+    fn empty() {
+        let array: ArcCStrArray = Vec::new().into_iter().collect();
+        assert!(array.is_empty());
+        assert_eq!(array, ArcCStrArray::default());
+        assert_eq!(format!("{:?}", array), "[]");
+    }
+}
@@ -0,0 +1,62 @@
+//! Compile-time checks of the layout and auto traits that the rest of the crate relies on.
+//!
+//! None of these produce any code; a failing assertion is a build error.
+
+use crate::raw::{Header, RawArcCStr};
+use crate::{
+    ArcCStr, ArcCStrArray, ArcCStrBuilder, ArcCStrGuard, ArcCStrPair, ArcCStrPool, ArcCStrSlice,
+    ArcCStrSuffix, ArcCWStr, ArcStr, ByBytes, CompactCStr, CowCStr, FmtArg, OrdByPtr,
+    OrdByPtrThenContent, StaticCStr, WeakCStr,
+};
+use core::mem::{align_of, size_of};
+
+// an ArcCStr is a single pointer
+const _: () = assert!(size_of::<ArcCStr>() == size_of::<usize>());
+const _: () = assert!(size_of::<RawArcCStr>() == size_of::<usize>());
+const _: () = assert!(size_of::<ArcStr>() == size_of::<usize>());
+const _: () = assert!(size_of::<OrdByPtr>() == size_of::<usize>());
+const _: () = assert!(size_of::<ByBytes>() == size_of::<usize>());
+const _: () = assert!(size_of::<WeakCStr>() == size_of::<usize>());
+const _: () = assert!(size_of::<ArcCWStr>() == size_of::<usize>());
+
+// and that pointer is non-null, so Option<ArcCStr> is free
+const _: () = assert!(size_of::<Option<ArcCStr>>() == size_of::<ArcCStr>());
+
+// a CompactCStr is as wide as a String
+const _: () = assert!(size_of::<CompactCStr>() == 3 * size_of::<usize>());
+
+// a FmtArg is passed to C exactly like a pointer
+const _: () = assert!(size_of::<FmtArg<'static>>() == size_of::<usize>());
+
+// the header only holds counters, and the string follows it directly
+const _: () = assert!(align_of::<Header>() == align_of::<usize>());
+const _: () = assert!(RawArcCStr::DATA_OFFSET == size_of::<Header>());
+
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<ArcCStr>();
+    assert_send_sync::<RawArcCStr>();
+    assert_send_sync::<ArcStr>();
+    assert_send_sync::<OrdByPtr>();
+    assert_send_sync::<OrdByPtrThenContent>();
+    assert_send_sync::<ByBytes>();
+    assert_send_sync::<WeakCStr>();
+    assert_send_sync::<ArcCWStr>();
+    assert_send_sync::<ArcCStrPair>();
+    assert_send_sync::<ArcCStrSlice>();
+    assert_send_sync::<ArcCStrSuffix>();
+    assert_send_sync::<ArcCStrBuilder>();
+    assert_send_sync::<ArcCStrGuard>();
+    assert_send_sync::<ArcCStrPool>();
+    assert_send_sync::<ArcCStrArray>();
+    assert_send_sync::<CompactCStr>();
+    assert_send_sync::<CowCStr<'static>>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<crate::AtomicArcCStr>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<crate::NamedStrings>();
+    #[cfg(feature = "std")]
+    assert_send_sync::<crate::WeakCStrMap<()>>();
+    assert_send_sync::<StaticCStr<1>>();
+    assert_send_sync::<Header>();
+};
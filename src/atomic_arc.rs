@@ -0,0 +1,244 @@
+use crate::raw::RawArcCStr;
+use crate::ArcCStr;
+use core::mem;
+use core::ptr::NonNull;
+use core::sync::atomic::Ordering::{Relaxed, SeqCst};
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+#[cfg(loom)]
+use loom::sync::Mutex;
+use std::fmt;
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize};
+#[cfg(not(loom))]
+use std::sync::Mutex;
+
+/// An [`ArcCStr`] that can be replaced while other threads read it, without locking.
+///
+/// [`load`](AtomicArcCStr::load) returns a new reference to the current string, and
+/// [`store`](AtomicArcCStr::store) replaces it. Loads never block, and never wait for a store:
+/// a load only increments two counters and decrements one of them again, however many other
+/// threads are loading or storing at the same time. Stores take a lock, so they wait for each
+/// other, but never for a load.
+///
+/// A string that has been replaced is not released right away, since a load that started before
+/// the store may still be about to increment its reference count. Instead, the slot holds on to
+/// it until a store or a load finds that no load is in progress. So the last reference to a
+/// replaced string may be released a little later than it would be with a lock.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use std::sync::Arc;
+/// use arccstr::{ArcCStr, AtomicArcCStr};
+///
+/// let endpoint = Arc::new(AtomicArcCStr::new(ArcCStr::try_from("db-1:5432").unwrap()));
+/// let worker = {
+///     let endpoint = Arc::clone(&endpoint);
+///     std::thread::spawn(move || {
+///         let current = endpoint.load();
+///         assert!(current == "db-1:5432" || current == "db-2:5432");
+///     })
+/// };
+/// endpoint.store(ArcCStr::try_from("db-2:5432").unwrap());
+/// worker.join().unwrap();
+/// assert_eq!(endpoint.load(), "db-2:5432");
+/// ```
+pub struct AtomicArcCStr {
+    // holds one reference, as a pointer to the header (see `into_ptr`)
+    ptr: AtomicPtr<u8>,
+    // the number of loads in progress
+    readers: AtomicUsize,
+    // replaced strings that a load in progress may still be about to clone
+    retired: Mutex<Vec<ArcCStr>>,
+    // whether `retired` may be non-empty, so that loads only take the lock if it is
+    has_retired: AtomicBool,
+}
+
+impl AtomicArcCStr {
+    /// Constructs a new `AtomicArcCStr` holding `s`.
+    pub fn new(s: ArcCStr) -> Self {
+        AtomicArcCStr {
+            ptr: AtomicPtr::new(into_ptr(s)),
+            readers: AtomicUsize::new(0),
+            retired: Mutex::new(Vec::new()),
+            has_retired: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the current string.
+    pub fn load(&self) -> ArcCStr {
+        self.readers.fetch_add(1, SeqCst);
+        let ptr = self.ptr.load(SeqCst);
+        // if a store has replaced ptr since, it has retired its reference rather than releasing
+        // it, and it will not be released until after we stop counting as a reader below
+        let s = ArcCStr::clone(&mem::ManuallyDrop::new(unsafe { from_ptr(ptr) }));
+        if self.readers.fetch_sub(1, SeqCst) == 1 && self.has_retired.load(SeqCst) {
+            // if a store holds the lock, the strings stay retired until a later store or load
+            let released = match self.retired.try_lock() {
+                Ok(mut retired) => self.release_retired(&mut retired),
+                Err(_) => Vec::new(),
+            };
+            drop(released);
+        }
+        s
+    }
+
+    /// Replaces the current string with `s`.
+    pub fn store(&self, s: ArcCStr) {
+        let prev = self.ptr.swap(into_ptr(s), SeqCst);
+        self.retire(unsafe { from_ptr(prev) });
+    }
+
+    /// Replaces the current string with `s`, and returns the string it replaced.
+    pub fn swap(&self, s: ArcCStr) -> ArcCStr {
+        let prev = self.ptr.swap(into_ptr(s), SeqCst);
+        let prev = unsafe { from_ptr(prev) };
+        // a load in progress may still clone the reference held by the slot, so that one is
+        // retired, and the caller gets a new one
+        let out = ArcCStr::clone(&prev);
+        self.retire(prev);
+        out
+    }
+
+    /// Consumes the `AtomicArcCStr`, returning the current string.
+    pub fn into_inner(self) -> ArcCStr {
+        self.load()
+    }
+
+    /// Holds on to the reference to `prev`, which was just swapped out of `ptr`, until no load
+    /// can still be cloning it.
+    fn retire(&self, prev: ArcCStr) {
+        let released = {
+            let mut retired = self.retired.lock().unwrap_or_else(|e| e.into_inner());
+            retired.push(prev);
+            self.has_retired.store(true, SeqCst);
+            self.release_retired(&mut retired)
+        };
+        // releasing the strings may run release hooks, which should not hold up other stores
+        drop(released);
+    }
+
+    /// Takes the retired strings out of `retired`, if no load is in progress.
+    ///
+    /// The caller must hold the lock on `retired`, and every retired string must have been
+    /// swapped out of `ptr` before it was pushed.
+    fn release_retired(&self, retired: &mut Vec<ArcCStr>) -> Vec<ArcCStr> {
+        // a load that starts after this will see a string that has not been retired, and one
+        // that has finished has already cloned its string. this has to be a read-modify-write,
+        // which always sees the latest count: a plain load could miss a load that has started,
+        // and already read the pointer from before the swap.
+        if self.readers.compare_exchange(0, 0, SeqCst, SeqCst).is_err() {
+            return Vec::new();
+        }
+        self.has_retired.store(false, Relaxed);
+        mem::take(retired)
+    }
+}
+
+/// Turns `s` into a pointer to its header, which holds the reference that `s` held.
+///
+/// Unlike [`ArcCStr::into_raw`], this works the same for every string, so the slot never has to
+/// copy one.
+fn into_ptr(s: ArcCStr) -> *mut u8 {
+    ArcCStr::into_raw_arc(s).as_ptr().as_ptr()
+}
+
+/// Takes over the reference held by a pointer from [`into_ptr`].
+///
+/// # Safety
+///
+/// `ptr` must have come from `into_ptr`, and still hold its reference.
+unsafe fn from_ptr(ptr: *mut u8) -> ArcCStr {
+    ArcCStr::from_raw_arc(RawArcCStr::from_ptr(NonNull::new_unchecked(ptr)))
+}
+
+impl From<ArcCStr> for AtomicArcCStr {
+    fn from(s: ArcCStr) -> Self {
+        AtomicArcCStr::new(s)
+    }
+}
+
+impl Drop for AtomicArcCStr {
+    fn drop(&mut self) {
+        // nothing else can load from the slot anymore, and the retired strings are dropped
+        // along with it
+        drop(unsafe { from_ptr(self.ptr.load(Relaxed)) });
+    }
+}
+
+impl fmt::Debug for AtomicArcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.load(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AtomicArcCStr;
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn load_store_swap() {
+        let a = ArcCStr::try_from("a").unwrap();
+        let slot = AtomicArcCStr::new(ArcCStr::clone(&a));
+        assert!(ArcCStr::ptr_eq(&slot.load(), &a));
+        assert_eq!(ArcCStr::strong_count(&a), 2);
+
+        let b = ArcCStr::try_from("b").unwrap();
+        let prev = slot.swap(ArcCStr::clone(&b));
+        assert!(ArcCStr::ptr_eq(&prev, &a));
+        drop(prev);
+        // no load was in progress, so the slot let go of the old string right away
+        assert_eq!(ArcCStr::strong_count(&a), 1);
+
+        slot.store(ArcCStr::try_from("c").unwrap());
+        assert_eq!(ArcCStr::strong_count(&b), 1);
+        assert_eq!(format!("{:?}", slot), "\"c\"");
+        let c = slot.into_inner();
+        assert_eq!(c, "c");
+        assert_eq!(ArcCStr::strong_count(&c), 1);
+    }
+
+    #[test]
+    fn external_strings_are_not_copied() {
+        let data = b"external\0".as_ptr() as *const std::os::raw::c_char;
+        let s = unsafe { ArcCStr::from_external(data, 8, Arc::new(())) };
+        let slot = AtomicArcCStr::new(ArcCStr::clone(&s));
+        assert!(ArcCStr::ptr_eq(&slot.load(), &s));
+        assert_eq!(slot.swap(ArcCStr::try_from("b").unwrap()).as_ptr(), data);
+    }
+
+    #[test]
+    fn concurrent_loads_and_stores() {
+        let strs: Vec<_> = (0..8)
+            .map(|i| ArcCStr::try_from(i.to_string()).unwrap())
+            .collect();
+        let slot = Arc::new(AtomicArcCStr::new(ArcCStr::clone(&strs[0])));
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let slot = Arc::clone(&slot);
+                let strs = strs.clone();
+                thread::spawn(move || {
+                    for i in 0..2000 {
+                        if (i + t) % 8 == 0 {
+                            slot.store(ArcCStr::clone(&strs[i % 8]));
+                        } else {
+                            let s = slot.load();
+                            assert!(strs.contains(&s));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        drop(slot);
+        assert!(strs.iter().all(|s| ArcCStr::strong_count(s) == 1));
+    }
+}
@@ -0,0 +1,478 @@
+use crate::raw::RawArcCStr;
+use crate::{ArcCStr, ArcCStrError};
+use core::fmt;
+use core::ptr::{self, NonNull};
+use core::slice;
+
+/// Builds an [`ArcCStr`] piece by piece, writing directly into the string's final allocation.
+///
+/// Constructing an `ArcCStr` from a `String` or `Vec<u8>` means building that buffer first, and
+/// then copying it into a new allocation that has room for the header. A builder instead writes
+/// each piece straight into an allocation laid out like an `ArcCStr`'s, and
+/// [`build`](ArcCStrBuilder::build) then turns it into the string in place. If the builder was
+/// created [`with_capacity`](ArcCStrBuilder::with_capacity) for the whole string, that is the
+/// only allocation made; otherwise it grows the way a `Vec` does, with `realloc`.
+///
+/// Every write is checked for `\0` bytes, and a write that contains one fails without writing
+/// anything, so the error is reported where the bad input came from rather than when the string
+/// is built.
+///
+/// The builder implements [`fmt::Write`], so it works with `write!`, and with the `std` feature
+/// it also implements [`std::io::Write`].
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::Write;
+/// use arccstr::ArcCStrBuilder;
+///
+/// let mut b = ArcCStrBuilder::with_capacity(16);
+/// b.push_str("user-").unwrap();
+/// write!(b, "{}@{}", 42, "example.org").unwrap();
+/// let s = b.build().unwrap();
+/// assert_eq!(s.to_str(), Ok("user-42@example.org"));
+/// ```
+pub struct ArcCStrBuilder {
+    // an uninitialized allocation from RawArcCStr::allocate_uninit with room for `cap` bytes, or
+    // None if nothing has been allocated yet
+    ptr: Option<NonNull<u8>>,
+    cap: usize,
+    len: usize,
+    // whether everything written so far was a str, in which case the string is valid UTF-8
+    utf8: bool,
+}
+
+// the builder exclusively owns its allocation, and only ever hands out shared references to it
+// through &self
+unsafe impl Send for ArcCStrBuilder {}
+unsafe impl Sync for ArcCStrBuilder {}
+
+impl ArcCStrBuilder {
+    /// Constructs an empty builder.
+    ///
+    /// Nothing is allocated until the first write.
+    pub fn new() -> Self {
+        ArcCStrBuilder {
+            ptr: None,
+            cap: 0,
+            len: 0,
+            utf8: true,
+        }
+    }
+
+    /// Constructs an empty builder with room for a string of `cap` bytes.
+    ///
+    /// If the finished string is exactly `cap` bytes long, building it makes no further
+    /// allocations.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the memory cannot be allocated.
+    pub fn with_capacity(cap: usize) -> Self {
+        let ptr = RawArcCStr::allocate_uninit(cap).expect("could not allocate memory");
+        ArcCStrBuilder {
+            ptr: Some(ptr),
+            cap,
+            len: 0,
+            utf8: true,
+        }
+    }
+
+    /// Returns the length of the string written so far, in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if nothing has been written yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes the builder can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns the bytes written so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self.ptr {
+            // the first `len` bytes of the string data have been written
+            Some(ptr) => unsafe {
+                slice::from_raw_parts(ptr.as_ptr().add(RawArcCStr::DATA_OFFSET), self.len)
+            },
+            None => &[],
+        }
+    }
+
+    /// Appends `buf` to the string.
+    ///
+    /// Fails with [`ArcCStrError::InteriorNul`] if `buf` contains a `\0`, giving its position
+    /// within the whole string, with [`ArcCStrError::TooLong`] if the string would be longer
+    /// than [`RawArcCStr::MAX_LEN`], and with [`ArcCStrError::Alloc`] if the builder could not
+    /// grow. Either way, nothing is appended.
+    pub fn push_bytes(&mut self, buf: &[u8]) -> Result<(), ArcCStrError> {
+        self.push(buf)?;
+        self.utf8 = false;
+        Ok(())
+    }
+
+    /// Appends `s` to the string.
+    ///
+    /// Fails like [`push_bytes`](ArcCStrBuilder::push_bytes). A string built only from `str`s is
+    /// known to be valid UTF-8, so [`ArcCStr::to_str`] will not have to check it again.
+    pub fn push_str(&mut self, s: &str) -> Result<(), ArcCStrError> {
+        self.push(s.as_bytes())
+    }
+
+    /// Like [`push_bytes`](ArcCStrBuilder::push_bytes), but without checking `buf` for `\0`s.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must not contain any `\0` bytes.
+    pub(crate) unsafe fn push_bytes_unchecked(&mut self, buf: &[u8]) -> Result<(), ArcCStrError> {
+        self.push_unchecked(buf)?;
+        self.utf8 = false;
+        Ok(())
+    }
+
+    fn push(&mut self, buf: &[u8]) -> Result<(), ArcCStrError> {
+        ArcCStrError::check_no_nul(buf).map_err(|e| match e {
+            ArcCStrError::InteriorNul { pos } => ArcCStrError::InteriorNul {
+                pos: self.len + pos,
+            },
+            e => e,
+        })?;
+        unsafe { self.push_unchecked(buf) }
+    }
+
+    unsafe fn push_unchecked(&mut self, buf: &[u8]) -> Result<(), ArcCStrError> {
+        self.reserve(buf.len())?;
+        let ptr = self.ptr.expect("reserve allocates");
+        let at = ptr.as_ptr().add(RawArcCStr::DATA_OFFSET + self.len);
+        ptr::copy_nonoverlapping(buf.as_ptr(), at, buf.len());
+        self.len += buf.len();
+        Ok(())
+    }
+
+    /// Makes sure there is room for `additional` more bytes.
+    #[allow(clippy::unnecessary_min_or_max)] // `MAX_LEN` is `usize::MAX` on some 32-bit targets
+    fn reserve(&mut self, additional: usize) -> Result<(), ArcCStrError> {
+        let needed = self.needed(additional)?;
+        if self.ptr.is_some() && needed <= self.cap {
+            return Ok(());
+        }
+        // grow geometrically, like Vec, so that many small writes stay cheap, but never past what
+        // the header can hold, even if a smaller request would have fit
+        let doubled = self.cap.saturating_mul(2).min(RawArcCStr::MAX_LEN);
+        self.grow(needed.max(doubled).max(8))
+    }
+
+    /// Makes sure there is room for `additional` more bytes, without reserving any more than
+    /// that.
+    ///
+    /// Unlike the growth that writes trigger, this does not round the capacity up, so if the
+    /// builder has to grow, it ends up with room for exactly `additional` more bytes. Use this
+    /// when the final length is known, so that [`build`](ArcCStrBuilder::build) has nothing to
+    /// shrink. Fails with [`ArcCStrError::TooLong`] if the string would be longer than
+    /// [`RawArcCStr::MAX_LEN`], and with [`ArcCStrError::Alloc`] if the builder could not grow.
+    /// Either way, the builder is left as it was.
+    pub fn reserve_exact(&mut self, additional: usize) -> Result<(), ArcCStrError> {
+        let needed = self.needed(additional)?;
+        if self.ptr.is_some() && needed <= self.cap {
+            return Ok(());
+        }
+        self.grow(needed)
+    }
+
+    /// Returns the length the string would have with `additional` more bytes, if the header can
+    /// hold it.
+    #[allow(clippy::absurd_extreme_comparisons)] // `MAX_LEN` is `usize::MAX` on some 32-bit targets
+    fn needed(&self, additional: usize) -> Result<usize, ArcCStrError> {
+        match self.len.checked_add(additional) {
+            Some(needed) if needed <= RawArcCStr::MAX_LEN => Ok(needed),
+            needed => Err(ArcCStrError::TooLong {
+                len: needed.unwrap_or(usize::MAX),
+                max_len: RawArcCStr::MAX_LEN,
+            }),
+        }
+    }
+
+    /// Moves the string written so far into an allocation with room for `cap` bytes.
+    fn grow(&mut self, cap: usize) -> Result<(), ArcCStrError> {
+        let ptr = match self.ptr {
+            Some(ptr) => unsafe { RawArcCStr::reallocate_uninit(ptr, self.cap, cap) },
+            None => RawArcCStr::allocate_uninit(cap),
+        };
+        self.ptr = Some(ptr.ok_or(ArcCStrError::Alloc)?);
+        self.cap = cap;
+        Ok(())
+    }
+
+    /// Finishes the string.
+    ///
+    /// If the builder has spare capacity, its allocation is shrunk to fit first, which usually
+    /// happens in place. Fails with [`ArcCStrError::Alloc`] if that, or allocating an empty
+    /// string, fails.
+    pub fn build(mut self) -> Result<ArcCStr, ArcCStrError> {
+        let ptr = match self.ptr.take() {
+            Some(ptr) if self.cap == self.len => ptr,
+            Some(ptr) => match unsafe { RawArcCStr::reallocate_uninit(ptr, self.cap, self.len) } {
+                Some(ptr) => ptr,
+                None => {
+                    // put it back so that it is freed on drop
+                    self.ptr = Some(ptr);
+                    return Err(ArcCStrError::Alloc);
+                }
+            },
+            None => RawArcCStr::allocate_uninit(0).ok_or(ArcCStrError::Alloc)?,
+        };
+        // the allocation now has room for exactly `len` bytes, all of which have been written
+        // and checked for \0
+        Ok(self.finish(unsafe { RawArcCStr::init(ptr, self.len) }))
+    }
+
+    /// Finishes the string without giving back the builder's spare capacity.
+    ///
+    /// [`build`](ArcCStrBuilder::build) shrinks the allocation to fit, which usually happens in
+    /// place, but may have to move the string. This skips that, and so never moves or copies the
+    /// string, at the cost of keeping the spare capacity allocated for as long as the string
+    /// lives. The string cannot grow into that capacity later; it is only there because giving
+    /// it back was not worth it.
+    ///
+    /// The capacity is recorded in the spare room itself, so if there are fewer spare bytes than
+    /// a `usize` has, the allocation is shrunk to fit after all, like `build` does. Fails with
+    /// [`ArcCStrError::Alloc`] if that, or allocating an empty string, fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStrBuilder;
+    ///
+    /// let mut b = ArcCStrBuilder::with_capacity(64);
+    /// b.push_str("short").unwrap();
+    /// let data = b.as_bytes().as_ptr();
+    /// let s = b.build_keep_capacity().unwrap();
+    /// assert_eq!(s, "short");
+    /// // the string was finished where it was written
+    /// assert_eq!(s.as_ptr() as *const u8, data);
+    /// ```
+    pub fn build_keep_capacity(mut self) -> Result<ArcCStr, ArcCStrError> {
+        let raw = match self.ptr.take() {
+            Some(ptr) => match unsafe { RawArcCStr::init_with_slack(ptr, self.len, self.cap) } {
+                Some(raw) => raw,
+                None => {
+                    // put it back so that it is freed on drop
+                    self.ptr = Some(ptr);
+                    return Err(ArcCStrError::Alloc);
+                }
+            },
+            None => {
+                let ptr = RawArcCStr::allocate_uninit(0).ok_or(ArcCStrError::Alloc)?;
+                unsafe { RawArcCStr::init(ptr, 0) }
+            }
+        };
+        Ok(self.finish(raw))
+    }
+
+    /// Turns the initialized string into an `ArcCStr`, marking it as UTF-8 if it is.
+    fn finish(&self, raw: RawArcCStr) -> ArcCStr {
+        let s = unsafe { ArcCStr::from_raw_arc(raw) };
+        if self.utf8 {
+            unsafe { ArcCStr::as_raw_arc(&s).header().set_utf8(true) };
+        }
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::construct(&s);
+        s
+    }
+}
+
+impl Drop for ArcCStrBuilder {
+    fn drop(&mut self) {
+        if let Some(ptr) = self.ptr {
+            unsafe { RawArcCStr::deallocate_uninit(ptr, self.cap) };
+        }
+    }
+}
+
+impl Default for ArcCStrBuilder {
+    fn default() -> Self {
+        ArcCStrBuilder::new()
+    }
+}
+
+impl fmt::Debug for ArcCStrBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArcCStrBuilder")
+            .field("len", &self.len)
+            .field("capacity", &self.cap)
+            .finish()
+    }
+}
+
+impl fmt::Write for ArcCStrBuilder {
+    /// Appends `s` to the string.
+    ///
+    /// `fmt::Error` carries no details, so use [`push_str`](ArcCStrBuilder::push_str) directly
+    /// to learn why a write failed. Note that `write!` may fail after some of its pieces have
+    /// already been written.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for ArcCStrBuilder {
+    /// Appends all of `buf` to the string, or nothing if it contains a `\0`.
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.push_bytes(buf).map_err(|e| {
+            let kind = match e {
+                ArcCStrError::Alloc => std::io::ErrorKind::OutOfMemory,
+                _ => std::io::ErrorKind::InvalidInput,
+            };
+            std::io::Error::new(kind, e)
+        })?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCStrBuilder;
+    use crate::raw::RawArcCStr;
+    use crate::{ArcCStr, ArcCStrError};
+    use std::fmt::Write as _;
+
+    #[test]
+    fn grows() {
+        let mut b = ArcCStrBuilder::new();
+        assert_eq!(b.capacity(), 0);
+        for i in 0..100 {
+            write!(b, "{},", i).unwrap();
+        }
+        assert!(b.capacity() >= b.len());
+        let expected: String = (0..100).map(|i| format!("{},", i)).collect();
+        assert_eq!(b.as_bytes(), expected.as_bytes());
+        let s = b.build().unwrap();
+        assert_eq!(s.to_str(), Ok(&*expected));
+        assert_eq!(s.as_str(), Some(&*expected));
+    }
+
+    #[test]
+    fn exact_capacity() {
+        let mut b = ArcCStrBuilder::with_capacity(6);
+        b.push_str("foo").unwrap();
+        b.push_bytes(b"\xffar").unwrap();
+        assert_eq!(b.capacity(), 6);
+        let s = b.build().unwrap();
+        assert_eq!(s.to_bytes(), b"foo\xffar");
+        assert!(s.to_str().is_err());
+    }
+
+    #[test]
+    fn reserve_exact() {
+        let mut b = ArcCStrBuilder::new();
+        b.reserve_exact(5).unwrap();
+        assert_eq!(b.capacity(), 5);
+        b.push_str("abc").unwrap();
+        // there is already room
+        b.reserve_exact(2).unwrap();
+        assert_eq!(b.capacity(), 5);
+        b.reserve_exact(4).unwrap();
+        assert_eq!(b.capacity(), 7);
+        assert_eq!(b.as_bytes(), b"abc");
+        assert_eq!(b.capacity(), 7);
+        b.push_str("defg").unwrap();
+        assert_eq!(b.capacity(), 7);
+        assert_eq!(b.build().unwrap(), "abcdefg");
+    }
+
+    #[test]
+    fn huge_reservations_fail() {
+        let mut b = ArcCStrBuilder::new();
+        assert!(b.reserve_exact(usize::MAX / 2).is_err());
+        assert!(b.reserve(usize::MAX / 2).is_err());
+        b.push_str("a").unwrap();
+        assert!(matches!(
+            b.reserve_exact(usize::MAX),
+            Err(ArcCStrError::TooLong {
+                len: usize::MAX,
+                ..
+            })
+        ));
+        assert!(b.reserve_exact(usize::MAX - 5).is_err());
+        assert!(b.reserve(usize::MAX - 5).is_err());
+        assert!(b.reserve_exact(RawArcCStr::MAX_LEN).is_err());
+        // nothing changed
+        assert_eq!(b.as_bytes(), b"a");
+        b.push_str("b").unwrap();
+        assert_eq!(b.build().unwrap(), "ab");
+    }
+
+    #[test]
+    fn build_keep_capacity() {
+        let mut b = ArcCStrBuilder::with_capacity(100);
+        b.push_str("kept").unwrap();
+        let data = b.as_bytes().as_ptr();
+        let s = b.build_keep_capacity().unwrap();
+        assert_eq!(s.as_ptr() as *const u8, data);
+        assert_eq!(s.to_bytes_with_nul(), b"kept\0");
+        assert_eq!(s.as_str(), Some("kept"));
+        let t = ArcCStr::clone(&s);
+        drop(s);
+        assert_eq!(t, "kept");
+        drop(t);
+
+        // too little spare room to record the capacity in, so it is shrunk like by build
+        let mut b = ArcCStrBuilder::with_capacity(5);
+        b.push_bytes(b"tiny").unwrap();
+        assert_eq!(b.build_keep_capacity().unwrap(), "tiny");
+        assert!(ArcCStrBuilder::new()
+            .build_keep_capacity()
+            .unwrap()
+            .is_empty());
+        assert!(ArcCStrBuilder::with_capacity(64)
+            .build_keep_capacity()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn empty() {
+        assert!(ArcCStrBuilder::new().build().unwrap().is_empty());
+        let b = ArcCStrBuilder::with_capacity(10);
+        assert!(b.build().unwrap().is_empty());
+        // never built
+        let mut b = ArcCStrBuilder::new();
+        b.push_str("dropped").unwrap();
+    }
+
+    #[test]
+    fn nul() {
+        let mut b = ArcCStrBuilder::new();
+        b.push_str("abc").unwrap();
+        assert_eq!(
+            b.push_bytes(b"d\0"),
+            Err(ArcCStrError::InteriorNul { pos: 4 })
+        );
+        let nul = '\0';
+        assert!(write!(b, "{}", nul).is_err());
+        b.push_str("!").unwrap();
+        assert_eq!(b.build().unwrap().to_bytes(), b"abc!");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn io_write() {
+        use std::io::{self, Write};
+
+        let mut b = ArcCStrBuilder::new();
+        let e = b.write(b"ab\0").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        io::copy(&mut &b"line\n"[..], &mut b).unwrap();
+        assert_eq!(b.build().unwrap().to_bytes(), b"line\n");
+    }
+}
@@ -0,0 +1,111 @@
+use crate::ArcCStr;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// An [`ArcCStr`] that is compared, ordered, and hashed as the bytes of the string, without the
+/// null terminator, so that maps keyed by it can be looked up with a `&[u8]`.
+///
+/// An `ArcCStr` key can only be looked up with a `&CStr`, since an `ArcCStr` hashes like a
+/// `CStr` does, which includes the terminator, while a `&[u8]` received from the network
+/// usually has none. Wrapping the keys in `ByBytes` instead implements [`Borrow<[u8]>`], which
+/// lets `HashMap`, `BTreeMap`, and other maps that accept borrowed keys (including `hashbrown`
+/// and `indexmap`, through their `Equivalent` traits) be searched with a plain `&[u8]`, without
+/// allocating a key to search with.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, ByBytes};
+///
+/// let mut handlers = HashMap::new();
+/// handlers.insert(ByBytes(ArcCStr::try_from("PING").unwrap()), 1);
+///
+/// let from_wire: &[u8] = b"PING\r\n";
+/// assert_eq!(handlers.get(&from_wire[..4]), Some(&1));
+/// ```
+#[derive(Clone)]
+pub struct ByBytes(pub ArcCStr);
+
+impl Deref for ByBytes {
+    type Target = ArcCStr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<ArcCStr> for ByBytes {
+    fn from(s: ArcCStr) -> Self {
+        ByBytes(s)
+    }
+}
+
+impl Borrow<[u8]> for ByBytes {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl PartialEq for ByBytes {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl Eq for ByBytes {}
+
+impl PartialOrd for ByBytes {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByBytes {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.as_bytes().cmp(other.0.as_bytes())
+    }
+}
+
+impl Hash for ByBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // must match the hash of the borrowed [u8]
+        self.0.as_bytes().hash(state)
+    }
+}
+
+impl fmt::Debug for ByBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByBytes;
+    use crate::ArcCStr;
+    use std::collections::{BTreeSet, HashMap};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn lookup_by_bytes() {
+        let key = |s: &str| ByBytes(ArcCStr::try_from(s).unwrap());
+        let map: HashMap<_, _> = vec![(key("a"), 1), (key("ab"), 2)].into_iter().collect();
+        assert_eq!(map.get(&b"a"[..]), Some(&1));
+        assert_eq!(map.get(&b"ab"[..]), Some(&2));
+        assert_eq!(map.get(&b"a\0"[..]), None);
+
+        let set: BTreeSet<_> = vec![key("b"), key("a"), key("ab")].into_iter().collect();
+        assert!(set.contains(&b"ab"[..]));
+        let order: Vec<&[u8]> = set.iter().map(|k| k.as_bytes()).collect();
+        assert_eq!(order, [&b"a"[..], b"ab", b"b"]);
+    }
+}
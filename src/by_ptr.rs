@@ -0,0 +1,245 @@
+use crate::ArcCStr;
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// An [`ArcCStr`] that is compared, ordered, and hashed by the address of its allocation rather
+/// than by its contents.
+///
+/// Comparing two `OrdByPtr`s never reads the strings, which makes sorting and searching large
+/// collections of strings very cheap. When every string in a collection is interned (that is,
+/// equal strings always share one allocation), two `OrdByPtr`s are equal exactly when their
+/// strings are. Otherwise, equal strings held in different allocations are treated as distinct.
+///
+/// The order is stable for as long as the strings are alive, but is otherwise arbitrary, and will
+/// differ between runs of a program.
+///
+/// See also [`ArcCStr::sort_key`], and [`OrdByPtrThenContent`] for collections in which only some
+/// of the strings are interned.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeSet;
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, OrdByPtr};
+///
+/// let foo = ArcCStr::try_from("foo").unwrap();
+/// let mut set = BTreeSet::new();
+/// set.insert(OrdByPtr(ArcCStr::clone(&foo)));
+/// assert!(set.contains(&OrdByPtr(ArcCStr::clone(&foo))));
+///
+/// // a separate allocation with the same contents is a different key
+/// assert!(!set.contains(&OrdByPtr(ArcCStr::try_from("foo").unwrap())));
+/// ```
+#[derive(Clone)]
+pub struct OrdByPtr(pub ArcCStr);
+
+impl Deref for OrdByPtr {
+    type Target = ArcCStr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl PartialEq for OrdByPtr {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ArcCStr::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for OrdByPtr {}
+
+impl PartialOrd for OrdByPtr {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrdByPtr {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        ArcCStr::sort_key(&self.0).cmp(&ArcCStr::sort_key(&other.0))
+    }
+}
+
+impl Hash for OrdByPtr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        ArcCStr::sort_key(&self.0).hash(state)
+    }
+}
+
+impl fmt::Debug for OrdByPtr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+/// An [`ArcCStr`] that is ordered by address if it is interned, and by content otherwise.
+///
+/// This is for large indexes in which most strings come from an [`Interner`](crate::Interner),
+/// but some were made on their own. Interned strings are compared as cheaply as by [`OrdByPtr`],
+/// which is correct because equal strings from one interner share an allocation. The other
+/// strings are compared by content, so equal strings in separate allocations still end up next
+/// to each other.
+///
+/// All `Interned` strings order before all `Owned` ones. An `Interned` string is never equal to
+/// an `Owned` one, even with the same contents, and strings interned through different interners
+/// are only equal if they share an allocation.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, OrdByPtrThenContent};
+///
+/// // as an interner would hand it out, every time "x" is interned
+/// let x = ArcCStr::try_from("x").unwrap();
+/// let mut index = vec![
+///     OrdByPtrThenContent::Owned(ArcCStr::try_from("b").unwrap()),
+///     OrdByPtrThenContent::Interned(ArcCStr::clone(&x)),
+///     OrdByPtrThenContent::Owned(ArcCStr::try_from("a").unwrap()),
+///     OrdByPtrThenContent::Owned(ArcCStr::try_from("b").unwrap()),
+///     OrdByPtrThenContent::Interned(ArcCStr::clone(&x)),
+/// ];
+/// index.sort();
+/// index.dedup();
+/// let strs: Vec<_> = index.iter().map(|s| s.to_str().unwrap()).collect();
+/// assert_eq!(strs, ["x", "a", "b"]);
+/// ```
+#[derive(Clone)]
+pub enum OrdByPtrThenContent {
+    /// A string from an interner, ordered by the address of its allocation.
+    Interned(ArcCStr),
+    /// Any other string, ordered by its contents.
+    Owned(ArcCStr),
+}
+
+impl OrdByPtrThenContent {
+    /// Returns the string, whichever variant it is.
+    pub fn into_inner(self) -> ArcCStr {
+        match self {
+            OrdByPtrThenContent::Interned(s) | OrdByPtrThenContent::Owned(s) => s,
+        }
+    }
+}
+
+impl Deref for OrdByPtrThenContent {
+    type Target = ArcCStr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            OrdByPtrThenContent::Interned(s) | OrdByPtrThenContent::Owned(s) => s,
+        }
+    }
+}
+
+impl PartialEq for OrdByPtrThenContent {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl Eq for OrdByPtrThenContent {}
+
+impl PartialOrd for OrdByPtrThenContent {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrdByPtrThenContent {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        use OrdByPtrThenContent::{Interned, Owned};
+        match (self, other) {
+            (Interned(a), Interned(b)) => ArcCStr::sort_key(a).cmp(&ArcCStr::sort_key(b)),
+            (Owned(a), Owned(b)) => a.cmp(b),
+            (Interned(_), Owned(_)) => Ordering::Less,
+            (Owned(_), Interned(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl Hash for OrdByPtrThenContent {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            OrdByPtrThenContent::Interned(s) => {
+                state.write_u8(0);
+                ArcCStr::sort_key(s).hash(state)
+            }
+            OrdByPtrThenContent::Owned(s) => {
+                state.write_u8(1);
+                s.hash(state)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for OrdByPtrThenContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrdByPtr, OrdByPtrThenContent};
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn sorts_by_address() {
+        let strs: Vec<_> = ["c", "a", "b", "a"]
+            .iter()
+            .map(|&s| OrdByPtr(ArcCStr::try_from(s).unwrap()))
+            .collect();
+        let mut sorted = strs.clone();
+        sorted.push(strs[0].clone());
+        sorted.sort();
+        sorted.dedup();
+
+        // both "a"s survive, since they are separate allocations
+        assert_eq!(sorted.len(), 4);
+        assert!(sorted
+            .windows(2)
+            .all(|w| ArcCStr::sort_key(&w[0]) < ArcCStr::sort_key(&w[1])));
+    }
+
+    #[test]
+    fn interned_by_address_others_by_content() {
+        use std::collections::HashSet;
+        use OrdByPtrThenContent::{Interned, Owned};
+
+        let x = ArcCStr::try_from("x").unwrap();
+        let a = ArcCStr::try_from("a").unwrap();
+        let mut index = vec![
+            Owned(ArcCStr::try_from("b").unwrap()),
+            Interned(x.clone()),
+            Owned(a.clone()),
+            Owned(ArcCStr::try_from("a").unwrap()),
+            Interned(ArcCStr::try_from("a").unwrap()),
+            Interned(x.clone()),
+        ];
+        let set: HashSet<_> = index.iter().cloned().collect();
+        index.sort();
+        index.dedup();
+        assert_eq!(set.len(), index.len());
+
+        // the two interned strings are separate allocations, so both survive, ahead of the rest
+        assert_eq!(index.len(), 4);
+        assert!(matches!(index[0], Interned(_)));
+        assert!(matches!(index[1], Interned(_)));
+        assert!(ArcCStr::sort_key(&index[0]) < ArcCStr::sort_key(&index[1]));
+        // the two owned "a"s are one key
+        assert_eq!(index[2].as_str(), Some("a"));
+        assert_eq!(index[3].as_str(), Some("b"));
+        assert!(ArcCStr::ptr_eq(&index.remove(2).into_inner(), &a));
+    }
+}
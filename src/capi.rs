@@ -0,0 +1,163 @@
+//! Functions for sharing strings with C code.
+//!
+//! These let C code hold references to the same allocations as Rust's [`ArcCStr`]s, so that
+//! strings can cross a plugin boundary in either direction without being copied. A string is
+//! handed to C as the `const char *` returned by [`ArcCStr::into_raw`], which C can read like any
+//! other C string, and which it keeps alive with [`arccstr_clone`] and [`arccstr_release`]. Going
+//! the other way, [`ArcCStr::from_raw`] takes over a reference that C hands back.
+//!
+//! The declarations for C are in `include/arccstr.h`:
+//!
+//! ```c
+//! const char *arccstr_new(const char *s);
+//! const char *arccstr_clone(const char *s);
+//! void arccstr_release(const char *s);
+//! const char *arccstr_data(const char *s);
+//! size_t arccstr_len(const char *s);
+//! ```
+//!
+//! `include/staticlib` builds them as a static library, and `include/test.c` calls each of them
+//! from C, linked against that library.
+//!
+//! Every function other than `arccstr_new` must only be given pointers that came from
+//! `arccstr_new`, `arccstr_clone`, or `ArcCStr::into_raw`, and that still hold a reference.
+//!
+//! Only available with the `capi` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::convert::TryFrom;
+//! use arccstr::{capi, ArcCStr};
+//!
+//! let s = ArcCStr::try_from("shared").unwrap();
+//! // what a C plugin would do with a string it is given
+//! let handle = ArcCStr::into_raw(ArcCStr::clone(&s));
+//! unsafe {
+//!     assert_eq!(capi::arccstr_len(handle), 6);
+//!     capi::arccstr_release(handle);
+//! }
+//! assert_eq!(ArcCStr::strong_count(&s), 1);
+//! ```
+//!
+//! [`ArcCStr`]: crate::ArcCStr
+
+use crate::ArcCStr;
+use core::convert::TryFrom;
+use core::ffi::{c_char, CStr};
+use core::mem::ManuallyDrop;
+use core::ptr;
+
+/// Constructs a new shared string holding a copy of the C string `s`.
+///
+/// Returns `NULL` if `s` is `NULL`, or if the memory cannot be allocated. The returned string
+/// holds one reference, to be released with [`arccstr_release`].
+///
+/// # Safety
+///
+/// `s` must be `NULL` or point to a valid C string.
+#[no_mangle]
+pub unsafe extern "C" fn arccstr_new(s: *const c_char) -> *const c_char {
+    if s.is_null() {
+        return ptr::null();
+    }
+    match ArcCStr::try_from(CStr::from_ptr(s).to_bytes()) {
+        Ok(s) => ArcCStr::into_raw(s),
+        Err(_) => ptr::null(),
+    }
+}
+
+/// Adds a reference to the shared string `s`, and returns `s`.
+///
+/// The new reference has to be released separately, with [`arccstr_release`]. Returns `NULL`
+/// if `s` is `NULL`.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a shared string that still holds a reference.
+#[no_mangle]
+pub unsafe extern "C" fn arccstr_clone(s: *const c_char) -> *const c_char {
+    if !s.is_null() {
+        ArcCStr::increment_strong_count(s);
+    }
+    s
+}
+
+/// Releases one reference to the shared string `s`, freeing it if that was the last one.
+///
+/// Does nothing if `s` is `NULL`.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a shared string that still holds a reference, and the caller must not
+/// use that reference again.
+#[no_mangle]
+pub unsafe extern "C" fn arccstr_release(s: *const c_char) {
+    if !s.is_null() {
+        ArcCStr::decrement_strong_count(s);
+    }
+}
+
+/// Returns the null-terminated bytes of the shared string `s`.
+///
+/// A shared string already points to its bytes, so this returns `s` itself. It is provided so
+/// that C code can treat shared strings as opaque handles if it prefers.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a shared string that still holds a reference.
+#[no_mangle]
+pub unsafe extern "C" fn arccstr_data(s: *const c_char) -> *const c_char {
+    s
+}
+
+/// Returns the length of the shared string `s` in bytes, excluding the null terminator.
+///
+/// Unlike `strlen`, this reads the stored length rather than searching for the terminator.
+/// Returns 0 if `s` is `NULL`.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a shared string that still holds a reference.
+#[no_mangle]
+pub unsafe extern "C" fn arccstr_len(s: *const c_char) -> usize {
+    if s.is_null() {
+        return 0;
+    }
+    ManuallyDrop::new(ArcCStr::from_raw(s)).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arccstr_clone, arccstr_data, arccstr_len, arccstr_new, arccstr_release};
+    use crate::ArcCStr;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    #[test]
+    fn lifecycle() {
+        unsafe {
+            let s = arccstr_new(b"hello\0".as_ptr() as *const c_char);
+            assert_eq!(CStr::from_ptr(arccstr_data(s)).to_bytes(), b"hello");
+            assert_eq!(arccstr_len(s), 5);
+
+            assert_eq!(arccstr_clone(s), s);
+            let rust = ArcCStr::from_raw(s);
+            assert_eq!(ArcCStr::strong_count(&rust), 2);
+            arccstr_release(s);
+            assert_eq!(ArcCStr::strong_count(&rust), 1);
+        }
+    }
+
+    #[test]
+    fn null() {
+        unsafe {
+            assert!(arccstr_new(ptr::null()).is_null());
+            assert!(arccstr_clone(ptr::null()).is_null());
+            assert!(arccstr_data(ptr::null()).is_null());
+            assert_eq!(arccstr_len(ptr::null()), 0);
+            arccstr_release(ptr::null());
+        }
+    }
+}
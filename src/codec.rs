@@ -0,0 +1,282 @@
+use crate::{ArcCStr, ArcCStrError};
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// How the length of a string is encoded by [`ArcCStr::encode_length_prefixed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthPrefix {
+    /// A 4-byte big-endian length.
+    U32,
+    /// A variable-length LEB128 length: seven bits per byte, least significant group first, with
+    /// the high bit set on all but the last byte.
+    Varint,
+}
+
+/// The bytes that start every string written by [`ArcCStr::to_stable_bytes`].
+const STABLE_MAGIC: [u8; 4] = *b"ACST";
+/// The version of the layout written by [`ArcCStr::to_stable_bytes`].
+const STABLE_VERSION: u8 = 1;
+/// The length of everything before the string bytes in the stable layout.
+const STABLE_HEADER_LEN: usize = STABLE_MAGIC.len() + 1 + 8;
+
+pub(crate) fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+/// Reads a varint from the start of `bytes`, returning it and the number of bytes it took up.
+pub(crate) fn read_varint(bytes: &[u8]) -> Result<(usize, usize), ArcCStrError> {
+    let mut n: usize = 0;
+    for (i, &b) in bytes.iter().enumerate() {
+        let shift = 7 * i as u32;
+        let group = usize::from(b & 0x7f);
+        if shift >= usize::BITS || (group << shift) >> shift != group {
+            return Err(ArcCStrError::InvalidPrefix);
+        }
+        n |= group << shift;
+        if b & 0x80 == 0 {
+            return Ok((n, i + 1));
+        }
+    }
+    Err(ArcCStrError::Truncated)
+}
+
+impl ArcCStr {
+    /// Appends the string to `out`, preceded by its length (excluding the null terminator).
+    ///
+    /// The null terminator is not written. Fails only if the string is too long for a
+    /// [`LengthPrefix::U32`] prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::{ArcCStr, LengthPrefix};
+    ///
+    /// let mut out = Vec::new();
+    /// ArcCStr::try_from("foo").unwrap().encode_length_prefixed(LengthPrefix::U32, &mut out).unwrap();
+    /// ArcCStr::try_from("bar").unwrap().encode_length_prefixed(LengthPrefix::Varint, &mut out).unwrap();
+    /// assert_eq!(out, b"\0\0\0\x03foo\x03bar");
+    /// ```
+    pub fn encode_length_prefixed(
+        &self,
+        prefix: LengthPrefix,
+        out: &mut Vec<u8>,
+    ) -> Result<(), ArcCStrError> {
+        let bytes = self.to_bytes();
+        match prefix {
+            LengthPrefix::U32 => {
+                let len = u32::try_from(bytes.len()).map_err(|_| ArcCStrError::TooLong {
+                    len: bytes.len(),
+                    max_len: u32::MAX as usize,
+                })?;
+                out.extend_from_slice(&len.to_be_bytes());
+            }
+            LengthPrefix::Varint => write_varint(out, bytes.len()),
+        }
+        out.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Decodes a string written by [`encode_length_prefixed`](ArcCStr::encode_length_prefixed)
+    /// from the start of `input`, returning it along with the rest of `input`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::{ArcCStr, ArcCStrError, LengthPrefix};
+    ///
+    /// let input = b"\x03foo\x03bar\x04baz";
+    /// let (foo, rest) = ArcCStr::decode_length_prefixed(LengthPrefix::Varint, input).unwrap();
+    /// let (bar, rest) = ArcCStr::decode_length_prefixed(LengthPrefix::Varint, rest).unwrap();
+    /// assert_eq!(foo, b"foo");
+    /// assert_eq!(bar, b"bar");
+    /// assert_eq!(
+    ///     ArcCStr::decode_length_prefixed(LengthPrefix::Varint, rest),
+    ///     Err(ArcCStrError::Truncated)
+    /// );
+    /// ```
+    pub fn decode_length_prefixed(
+        prefix: LengthPrefix,
+        input: &[u8],
+    ) -> Result<(ArcCStr, &[u8]), ArcCStrError> {
+        let (len, header) = match prefix {
+            LengthPrefix::U32 => {
+                let len = input.get(..4).ok_or(ArcCStrError::Truncated)?;
+                let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]);
+                let len = usize::try_from(len).map_err(|_| ArcCStrError::InvalidPrefix)?;
+                (len, 4)
+            }
+            LengthPrefix::Varint => read_varint(input)?,
+        };
+        let rest = &input[header..];
+        if rest.len() < len {
+            return Err(ArcCStrError::Truncated);
+        }
+        let (bytes, rest) = rest.split_at(len);
+        let s = ArcCStr::try_from(bytes).map_err(|e| match e {
+            // report the position in the input, not in the string
+            ArcCStrError::InteriorNul { pos } => ArcCStrError::InteriorNul { pos: header + pos },
+            e => e,
+        })?;
+        Ok((s, rest))
+    }
+
+    /// Encodes the string in a versioned binary layout that stays readable by later versions of
+    /// this crate.
+    ///
+    /// Unlike [`to_allocation`](ArcCStr::to_allocation), whose layout follows the in-memory
+    /// header and may change between releases or targets, this layout is fixed, so it is suitable
+    /// for strings persisted by embedded databases and similar storage. It does not depend on
+    /// `serde`. Version 1, which this writes, consists of:
+    ///
+    ///  1. the four magic bytes `ACST`;
+    ///  2. a version byte, which is `1`;
+    ///  3. the length of the string in bytes, as a little-endian `u64`;
+    ///  4. the string bytes, without the null terminator.
+    ///
+    /// Later versions of this crate will keep reading every version that was ever written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("foo").unwrap();
+    /// let stable = s.to_stable_bytes();
+    /// assert_eq!(stable, b"ACST\x01\x03\0\0\0\0\0\0\0foo");
+    /// assert_eq!(ArcCStr::from_stable_bytes(&stable), Ok(s));
+    /// ```
+    pub fn to_stable_bytes(&self) -> Vec<u8> {
+        let bytes = self.to_bytes();
+        let mut out = Vec::with_capacity(STABLE_HEADER_LEN + bytes.len());
+        out.extend_from_slice(&STABLE_MAGIC);
+        out.push(STABLE_VERSION);
+        out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+        out.extend_from_slice(bytes);
+        out
+    }
+
+    /// Decodes a string written by [`to_stable_bytes`](ArcCStr::to_stable_bytes), by this or
+    /// any earlier version of this crate.
+    ///
+    /// `input` must hold exactly one encoded string. Fails with
+    /// [`InvalidMagic`](ArcCStrError::InvalidMagic) if it does not start with the magic bytes,
+    /// with [`UnsupportedVersion`](ArcCStrError::UnsupportedVersion) if it was written in a newer
+    /// layout than this version of the crate understands, with
+    /// [`Truncated`](ArcCStrError::Truncated) if it is shorter than its length says, and with
+    /// [`InvalidPrefix`](ArcCStrError::InvalidPrefix) if it is longer.
+    pub fn from_stable_bytes(input: &[u8]) -> Result<ArcCStr, ArcCStrError> {
+        if input.len() < STABLE_MAGIC.len() {
+            return Err(ArcCStrError::Truncated);
+        }
+        if input[..STABLE_MAGIC.len()] != STABLE_MAGIC {
+            return Err(ArcCStrError::InvalidMagic);
+        }
+        let header = input
+            .get(..STABLE_HEADER_LEN)
+            .ok_or(ArcCStrError::Truncated)?;
+        match header[STABLE_MAGIC.len()] {
+            STABLE_VERSION => {}
+            version => return Err(ArcCStrError::UnsupportedVersion { version }),
+        }
+        let mut len = [0; 8];
+        len.copy_from_slice(&header[STABLE_MAGIC.len() + 1..]);
+        let len =
+            usize::try_from(u64::from_le_bytes(len)).map_err(|_| ArcCStrError::InvalidPrefix)?;
+        let bytes = &input[STABLE_HEADER_LEN..];
+        if bytes.len() < len {
+            return Err(ArcCStrError::Truncated);
+        } else if bytes.len() > len {
+            return Err(ArcCStrError::InvalidPrefix);
+        }
+        ArcCStr::try_from(bytes).map_err(|e| match e {
+            // report the position in the input, not in the string
+            ArcCStrError::InteriorNul { pos } => ArcCStrError::InteriorNul {
+                pos: STABLE_HEADER_LEN + pos,
+            },
+            e => e,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_varint, write_varint, LengthPrefix};
+    use crate::{ArcCStr, ArcCStrError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn varint() {
+        for &n in &[0, 1, 127, 128, 300, 1 << 20, usize::MAX] {
+            let mut out = Vec::new();
+            write_varint(&mut out, n);
+            assert_eq!(read_varint(&out), Ok((n, out.len())));
+            assert_eq!(
+                read_varint(&out[..out.len() - 1]),
+                Err(ArcCStrError::Truncated)
+            );
+        }
+        assert_eq!(read_varint(&[0xff; 11]), Err(ArcCStrError::InvalidPrefix));
+    }
+
+    #[test]
+    fn round_trip() {
+        for &prefix in &[LengthPrefix::U32, LengthPrefix::Varint] {
+            let strs: Vec<_> = ["", "a", &"x".repeat(200)]
+                .iter()
+                .map(|s| ArcCStr::try_from(*s).unwrap())
+                .collect();
+            let mut out = Vec::new();
+            for s in &strs {
+                s.encode_length_prefixed(prefix, &mut out).unwrap();
+            }
+            let mut rest = &out[..];
+            for s in &strs {
+                let (decoded, r) = ArcCStr::decode_length_prefixed(prefix, rest).unwrap();
+                assert_eq!(&decoded, s);
+                rest = r;
+            }
+            assert!(rest.is_empty());
+        }
+    }
+
+    #[test]
+    fn stable() {
+        for s in ["", "a", &"x".repeat(300)] {
+            let s = ArcCStr::try_from(s).unwrap();
+            let stable = s.to_stable_bytes();
+            assert_eq!(stable.len(), 13 + s.len());
+            assert_eq!(ArcCStr::from_stable_bytes(&stable), Ok(s));
+        }
+
+        let stable = ArcCStr::try_from("ab").unwrap().to_stable_bytes();
+        let err = |input: &[u8]| ArcCStr::from_stable_bytes(input).unwrap_err();
+        assert_eq!(err(b"AC"), ArcCStrError::Truncated);
+        assert_eq!(err(b"ACS2\x01"), ArcCStrError::InvalidMagic);
+        assert_eq!(err(&stable[..12]), ArcCStrError::Truncated);
+        assert_eq!(err(&stable[..14]), ArcCStrError::Truncated);
+        assert_eq!(
+            err(&[&stable[..], b"c"].concat()),
+            ArcCStrError::InvalidPrefix
+        );
+        let mut newer = stable.clone();
+        newer[4] = 2;
+        assert_eq!(err(&newer), ArcCStrError::UnsupportedVersion { version: 2 });
+        let mut nul = stable;
+        nul[14] = 0;
+        assert_eq!(err(&nul), ArcCStrError::InteriorNul { pos: 14 });
+    }
+
+    #[test]
+    fn rejects_nul() {
+        assert_eq!(
+            ArcCStr::decode_length_prefixed(LengthPrefix::U32, b"\0\0\0\x02a\0"),
+            Err(ArcCStrError::InteriorNul { pos: 5 })
+        );
+    }
+}
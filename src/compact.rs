@@ -0,0 +1,245 @@
+use crate::{ArcCStr, ArcCStrError};
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::mem::size_of;
+use core::ops::Deref;
+
+// The inline buffer plus the length byte and the enum tag take up three words, like a `String`.
+const INLINE_BUF: usize = 3 * size_of::<usize>() - 2;
+
+/// A null-terminated string that stores short strings inline, and shares long ones.
+///
+/// Strings of up to `3 * size_of::<usize>() - 3` bytes (21 bytes on 64-bit targets) are stored
+/// directly in the `CompactCStr`, so creating, cloning, and dropping them never allocates and
+/// never touches an atomic counter. Longer strings are kept in an [`ArcCStr`] and shared like
+/// one. In exchange, a `CompactCStr` is three words wide rather than one.
+///
+/// Converting an existing `ArcCStr` into a `CompactCStr` keeps sharing its allocation, however
+/// short the string is, and [`to_arc`](CompactCStr::to_arc) only allocates for inline strings.
+///
+/// `CompactCStr` dereferences to [`CStr`], and is compared, ordered, and hashed by its contents,
+/// just like `ArcCStr`.
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::CompactCStr;
+/// use std::convert::TryFrom;
+///
+/// let short = CompactCStr::try_from("user_id").unwrap();
+/// assert!(short.is_inline());
+/// assert_eq!(short.to_bytes(), b"user_id");
+///
+/// let long = CompactCStr::try_from("a string that does not fit inline").unwrap();
+/// assert!(!long.is_inline());
+/// // cloning a long string only increments its reference count
+/// let shared = long.clone();
+/// assert_eq!(shared, long);
+/// ```
+#[derive(Clone)]
+pub struct CompactCStr(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    // buf[len] is always \0, and buf[..len] never contains a \0
+    Inline { len: u8, buf: [u8; INLINE_BUF] },
+    Shared(ArcCStr),
+}
+
+impl CompactCStr {
+    /// The longest string, in bytes, that is stored inline.
+    pub const INLINE_CAPACITY: usize = INLINE_BUF - 1;
+
+    /// Constructs a new, empty `CompactCStr`.
+    pub const fn new() -> Self {
+        CompactCStr(Repr::Inline {
+            len: 0,
+            buf: [0; INLINE_BUF],
+        })
+    }
+
+    /// Constructs a `CompactCStr` holding a copy of `bytes`.
+    ///
+    /// Fails if `bytes` contains an internal `\0`.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, ArcCStrError> {
+        ArcCStrError::check_no_nul(bytes)?;
+        // we just checked for \0
+        Ok(unsafe { Self::from_bytes_unchecked(bytes) })
+    }
+
+    /// The caller must have checked that `bytes` contains no `\0`.
+    unsafe fn from_bytes_unchecked(bytes: &[u8]) -> Self {
+        if bytes.len() > Self::INLINE_CAPACITY {
+            return CompactCStr(Repr::Shared(ArcCStr::from_bytes_unchecked(bytes)));
+        }
+        let mut buf = [0; INLINE_BUF];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        CompactCStr(Repr::Inline {
+            len: bytes.len() as u8,
+            buf,
+        })
+    }
+
+    /// Returns `true` if the string is stored inline rather than in a shared allocation.
+    #[inline]
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    /// Returns the string as an [`ArcCStr`].
+    ///
+    /// A shared string is returned with its reference count incremented, while an inline string
+    /// is copied into a new allocation.
+    pub fn to_arc(&self) -> ArcCStr {
+        match &self.0 {
+            // the inline bytes contain no \0
+            Repr::Inline { .. } => unsafe { ArcCStr::from_bytes_unchecked(self.to_bytes()) },
+            Repr::Shared(s) => ArcCStr::clone(s),
+        }
+    }
+}
+
+impl Default for CompactCStr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for CompactCStr {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        match &self.0 {
+            Repr::Inline { len, buf } => {
+                // we always keep a \0 at buf[len], and never let a \0 into buf[..len]
+                unsafe { CStr::from_bytes_with_nul_unchecked(&buf[..=usize::from(*len)]) }
+            }
+            Repr::Shared(s) => s,
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for CompactCStr {
+    type Error = ArcCStrError;
+    fn try_from(b: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(b)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for CompactCStr {
+    type Error = ArcCStrError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(s.as_bytes())
+    }
+}
+
+impl<'a> From<&'a CStr> for CompactCStr {
+    fn from(s: &'a CStr) -> Self {
+        // a CStr contains no \0 before its terminator
+        unsafe { Self::from_bytes_unchecked(s.to_bytes()) }
+    }
+}
+
+impl From<ArcCStr> for CompactCStr {
+    fn from(s: ArcCStr) -> Self {
+        CompactCStr(Repr::Shared(s))
+    }
+}
+
+impl From<CompactCStr> for ArcCStr {
+    fn from(s: CompactCStr) -> Self {
+        match s.0 {
+            Repr::Shared(s) => s,
+            Repr::Inline { .. } => s.to_arc(),
+        }
+    }
+}
+
+impl fmt::Debug for CompactCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl PartialEq for CompactCStr {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+impl Eq for CompactCStr {}
+
+impl PartialOrd for CompactCStr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CompactCStr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl Hash for CompactCStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl Borrow<CStr> for CompactCStr {
+    fn borrow(&self) -> &CStr {
+        self
+    }
+}
+
+impl AsRef<CStr> for CompactCStr {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompactCStr;
+    use crate::{ArcCStr, ArcCStrError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn inline_or_shared() {
+        let fits = "x".repeat(CompactCStr::INLINE_CAPACITY);
+        let s = CompactCStr::try_from(&*fits).unwrap();
+        assert!(s.is_inline());
+        assert_eq!(s.to_bytes_with_nul().len(), fits.len() + 1);
+
+        let long = fits + "x";
+        let s = CompactCStr::try_from(&*long).unwrap();
+        assert!(!s.is_inline());
+        assert_eq!(s.to_bytes(), long.as_bytes());
+        assert_eq!(s, CompactCStr::from(&*ArcCStr::try_from(&*long).unwrap()));
+
+        assert!(CompactCStr::new().is_inline());
+        assert!(CompactCStr::new().to_bytes().is_empty());
+        assert_eq!(
+            CompactCStr::try_from(&b"a\0b"[..]),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+
+    #[test]
+    fn arc_conversions() {
+        let a = ArcCStr::try_from("abc").unwrap();
+        let s = CompactCStr::from(ArcCStr::clone(&a));
+        assert!(!s.is_inline());
+        assert!(ArcCStr::ptr_eq(&s.to_arc(), &a));
+        assert!(ArcCStr::ptr_eq(&ArcCStr::from(s), &a));
+
+        let s = CompactCStr::try_from("abc").unwrap();
+        assert_eq!(s, CompactCStr::from(ArcCStr::clone(&a)));
+        assert_eq!(ArcCStr::from(s), a);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::ArcCStr;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::ffi::CStr;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// A null-terminated string that is either borrowed, or shared through an [`ArcCStr`].
+///
+/// This is for APIs that usually hand out strings known ahead of time, such as `&'static CStr`
+/// literals, but sometimes have to make one at runtime. Unlike returning an `ArcCStr`, returning
+/// a borrowed string allocates nothing; unlike [`Cow<CStr>`](alloc::borrow::Cow), cloning a
+/// runtime string only increments its reference count.
+///
+/// `CowCStr` dereferences to [`CStr`], and is compared, ordered, and hashed by its contents,
+/// whichever variant it is.
+///
+/// # Examples
+///
+/// ```
+/// use std::ffi::CStr;
+/// use arccstr::{ArcCStr, CowCStr};
+///
+/// fn describe(code: u32) -> CowCStr<'static> {
+///     match code {
+///         0 => CowCStr::Borrowed(CStr::from_bytes_with_nul(b"ok\0").unwrap()),
+///         n => CowCStr::Shared(format!("error {}", n).parse().unwrap()),
+///     }
+/// }
+///
+/// assert!(describe(0).is_borrowed());
+/// assert_eq!(describe(0).to_bytes(), b"ok");
+/// let err = describe(7);
+/// assert_eq!(err.to_bytes(), b"error 7");
+/// // the runtime string is handed over as is
+/// let shared: ArcCStr = err.clone().into_arc();
+/// assert_eq!(shared.as_ptr(), err.as_ptr());
+/// ```
+#[derive(Clone)]
+pub enum CowCStr<'a> {
+    /// A string borrowed for `'a`, often a `&'static CStr` literal.
+    Borrowed(&'a CStr),
+    /// A reference-counted string.
+    Shared(ArcCStr),
+}
+
+impl<'a> CowCStr<'a> {
+    /// Returns `true` if the string is borrowed rather than shared.
+    #[inline]
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self, CowCStr::Borrowed(_))
+    }
+
+    /// Returns the string as an [`ArcCStr`].
+    ///
+    /// A shared string is returned with its reference count incremented, while a borrowed string
+    /// is copied into a new allocation.
+    pub fn to_arc(&self) -> ArcCStr {
+        match self {
+            CowCStr::Borrowed(s) => ArcCStr::from(*s),
+            CowCStr::Shared(s) => ArcCStr::clone(s),
+        }
+    }
+
+    /// Converts the string into an [`ArcCStr`], copying it only if it is borrowed.
+    pub fn into_arc(self) -> ArcCStr {
+        match self {
+            CowCStr::Borrowed(s) => ArcCStr::from(s),
+            CowCStr::Shared(s) => s,
+        }
+    }
+
+    /// Converts the string into a `CowCStr` that borrows nothing, copying it only if it is
+    /// borrowed.
+    pub fn into_owned(self) -> CowCStr<'static> {
+        CowCStr::Shared(self.into_arc())
+    }
+}
+
+impl Default for CowCStr<'_> {
+    /// Returns an empty, borrowed string.
+    fn default() -> Self {
+        // the only byte is the terminator
+        CowCStr::Borrowed(unsafe { CStr::from_bytes_with_nul_unchecked(b"\0") })
+    }
+}
+
+impl Deref for CowCStr<'_> {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        match self {
+            CowCStr::Borrowed(s) => s,
+            CowCStr::Shared(s) => s,
+        }
+    }
+}
+
+impl<'a> From<&'a CStr> for CowCStr<'a> {
+    fn from(s: &'a CStr) -> Self {
+        CowCStr::Borrowed(s)
+    }
+}
+
+impl From<ArcCStr> for CowCStr<'_> {
+    fn from(s: ArcCStr) -> Self {
+        CowCStr::Shared(s)
+    }
+}
+
+impl<'a> From<&'a ArcCStr> for CowCStr<'a> {
+    /// Borrows the string, without touching its reference count.
+    fn from(s: &'a ArcCStr) -> Self {
+        CowCStr::Borrowed(s)
+    }
+}
+
+impl From<CowCStr<'_>> for ArcCStr {
+    fn from(s: CowCStr<'_>) -> Self {
+        s.into_arc()
+    }
+}
+
+impl fmt::Debug for CowCStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl PartialEq for CowCStr<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+impl Eq for CowCStr<'_> {}
+
+impl PartialOrd for CowCStr<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CowCStr<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl Hash for CowCStr<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl Borrow<CStr> for CowCStr<'_> {
+    fn borrow(&self) -> &CStr {
+        self
+    }
+}
+
+impl AsRef<CStr> for CowCStr<'_> {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CowCStr;
+    use crate::ArcCStr;
+    use std::collections::HashSet;
+    use std::convert::TryFrom;
+    use std::ffi::CStr;
+
+    const LITERAL: &[u8] = b"literal\0";
+
+    #[test]
+    fn borrowed_or_shared() {
+        let lit = CStr::from_bytes_with_nul(LITERAL).unwrap();
+        let borrowed = CowCStr::from(lit);
+        assert!(borrowed.is_borrowed());
+        assert_eq!(borrowed.as_ptr(), lit.as_ptr());
+
+        let arc = ArcCStr::try_from("literal").unwrap();
+        let shared = CowCStr::from(ArcCStr::clone(&arc));
+        assert!(!shared.is_borrowed());
+        assert_eq!(shared, borrowed);
+        let set: HashSet<_> = vec![shared.clone(), borrowed.clone()].into_iter().collect();
+        assert_eq!(set.len(), 1);
+
+        assert!(ArcCStr::ptr_eq(&shared.to_arc(), &arc));
+        assert!(ArcCStr::ptr_eq(&ArcCStr::from(shared), &arc));
+        let copy = borrowed.to_arc();
+        assert_eq!(copy, "literal");
+        assert_ne!(copy.as_ptr(), lit.as_ptr());
+        assert!(!borrowed.into_owned().is_borrowed());
+
+        assert!(CowCStr::from(&arc).is_borrowed());
+        assert!(CowCStr::default().is_borrowed());
+        assert!(CowCStr::default().to_bytes().is_empty());
+        assert_eq!(format!("{:?}", CowCStr::from(&arc)), "\"literal\"");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::ArcCStr;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A decoder for streams of `\0`-terminated records, such as the output of `find -print0` or the
+/// contents of `/proc/<pid>/environ`.
+///
+/// Feed the decoder chunks of the stream as they arrive with [`decode`](NulDecoder::decode),
+/// and it produces one [`ArcCStr`] per record. Records that lie entirely within one chunk are
+/// copied straight from the chunk into their `ArcCStr`. Only a record that is split across chunks
+/// is buffered until its end arrives.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Read;
+/// use arccstr::NulDecoder;
+///
+/// let mut input: &[u8] = b"./a\0./b/c\0./d";
+/// let mut decoder = NulDecoder::new();
+/// let mut paths = Vec::new();
+/// let mut chunk = [0; 4];
+/// loop {
+///     let n = input.read(&mut chunk).unwrap();
+///     if n == 0 {
+///         break;
+///     }
+///     decoder.decode(&chunk[..n], &mut paths);
+/// }
+/// // the stream did not end with a \0
+/// paths.extend(decoder.finish());
+///
+/// assert_eq!(paths.len(), 3);
+/// assert_eq!(paths[0], b"./a");
+/// assert_eq!(paths[1], b"./b/c");
+/// assert_eq!(paths[2], b"./d");
+/// ```
+#[derive(Default)]
+pub struct NulDecoder {
+    partial: Vec<u8>,
+}
+
+impl NulDecoder {
+    /// Constructs a new decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes the records that end in `chunk`, and adds them to `out`.
+    ///
+    /// Any bytes after the last `\0` in `chunk` are kept until a later chunk completes the
+    /// record.
+    pub fn decode<E: Extend<ArcCStr>>(&mut self, chunk: &[u8], out: &mut E) {
+        let mut records = chunk.split(|&b| b == 0);
+        // split always yields at least one piece, and the last one is never terminated
+        let last = records.next_back().expect("split is never empty");
+        let mut first = true;
+        out.extend(records.map(|record| {
+            // none of the records contain a \0, since we split on them
+            if core::mem::replace(&mut first, false) && !self.partial.is_empty() {
+                self.partial.extend_from_slice(record);
+                let s = unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(&self.partial) };
+                self.partial.clear();
+                s
+            } else {
+                unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(record) }
+            }
+        }));
+        self.partial.extend_from_slice(last);
+    }
+
+    /// Returns the bytes of the record that has been started but not yet terminated.
+    pub fn partial(&self) -> &[u8] {
+        &self.partial
+    }
+
+    /// Ends the stream, returning the final record if the stream did not end with a `\0`.
+    pub fn finish(self) -> Option<ArcCStr> {
+        if self.partial.is_empty() {
+            None
+        } else {
+            Some(unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(&self.partial) })
+        }
+    }
+}
+
+impl fmt::Debug for NulDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NulDecoder")
+            .field("partial", &self.partial.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NulDecoder;
+
+    #[test]
+    fn any_chunking() {
+        let input = b"HOME=/root\0\0PATH=/bin:/usr/bin\0X=\xff\0";
+        let expected: [&[u8]; 4] = [b"HOME=/root", b"", b"PATH=/bin:/usr/bin", b"X=\xff"];
+        for size in 1..=input.len() {
+            let mut decoder = NulDecoder::new();
+            let mut out = Vec::new();
+            for chunk in input.chunks(size) {
+                decoder.decode(chunk, &mut out);
+            }
+            assert_eq!(decoder.partial(), b"");
+            assert!(decoder.finish().is_none());
+            let out: Vec<_> = out.iter().map(|s| s.to_bytes()).collect();
+            assert_eq!(out, expected, "chunks of {}", size);
+        }
+    }
+
+    #[test]
+    fn unterminated() {
+        let mut decoder = NulDecoder::new();
+        let mut out = Vec::new();
+        decoder.decode(b"a\0b", &mut out);
+        decoder.decode(b"c", &mut out);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0], b"a");
+        assert_eq!(decoder.partial(), b"bc");
+        assert_eq!(decoder.finish().unwrap(), b"bc");
+    }
+}
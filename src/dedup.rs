@@ -0,0 +1,100 @@
+use crate::ArcCStr;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+
+/// Sorts `strings` and removes duplicates.
+///
+/// Strings are considered duplicates if they have the same contents, whether or not they share an
+/// allocation. Comparing two handles to the same allocation is cheap, so this is fastest on
+/// vectors that are already mostly deduplicated.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCStr;
+///
+/// let mut v: Vec<_> = ["b", "a", "b"].iter().map(|&s| ArcCStr::try_from(s).unwrap()).collect();
+/// arccstr::dedup_in_place(&mut v);
+/// assert_eq!(v, [ArcCStr::try_from("a").unwrap(), ArcCStr::try_from("b").unwrap()]);
+/// ```
+pub fn dedup_in_place(strings: &mut Vec<ArcCStr>) {
+    strings.sort_unstable();
+    strings.dedup();
+}
+
+/// Makes all equal strings in `strings` share a single allocation, without changing their order.
+///
+/// Each string is replaced with a handle to the first string in `strings` with the same contents.
+/// Allocations that are no longer referenced are freed. This is useful for compacting collections
+/// that were built up before the strings were interned.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCStr;
+///
+/// let mut v: Vec<_> = ["a", "b", "a"].iter().map(|&s| ArcCStr::try_from(s).unwrap()).collect();
+/// arccstr::share_duplicates(&mut v);
+/// assert!(ArcCStr::ptr_eq(&v[0], &v[2]));
+/// assert_eq!(ArcCStr::strong_count(&v[0]), 2);
+/// ```
+#[cfg(feature = "std")]
+pub fn share_duplicates(strings: &mut [ArcCStr]) {
+    let mut seen: HashSet<ArcCStr> = HashSet::with_capacity(strings.len());
+    for s in strings {
+        match seen.get(&**s) {
+            Some(first) => {
+                if !ArcCStr::ptr_eq(first, s) {
+                    *s = ArcCStr::clone(first);
+                }
+            }
+            None => {
+                seen.insert(ArcCStr::clone(s));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dedup_in_place;
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn dedup_shared_and_separate() {
+        let a = ArcCStr::try_from("a").unwrap();
+        let mut v = vec![
+            ArcCStr::clone(&a),
+            ArcCStr::try_from("b").unwrap(),
+            ArcCStr::try_from("a").unwrap(),
+            ArcCStr::clone(&a),
+        ];
+        dedup_in_place(&mut v);
+        assert_eq!(v.len(), 2);
+        assert_eq!(v[0], a);
+        assert_eq!(v[1].to_bytes(), b"b");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn share() {
+        use super::share_duplicates;
+
+        let mut v = vec![
+            ArcCStr::try_from("a").unwrap(),
+            ArcCStr::try_from("b").unwrap(),
+            ArcCStr::try_from("a").unwrap(),
+            ArcCStr::try_from("a").unwrap(),
+        ];
+        share_duplicates(&mut v);
+        assert_eq!(v.len(), 4);
+        assert!(ArcCStr::ptr_eq(&v[0], &v[2]));
+        assert!(ArcCStr::ptr_eq(&v[0], &v[3]));
+        assert_eq!(ArcCStr::strong_count(&v[0]), 3);
+        assert_eq!(ArcCStr::strong_count(&v[1]), 1);
+    }
+}
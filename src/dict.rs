@@ -0,0 +1,380 @@
+use crate::codec::{read_varint, write_varint};
+use crate::ArcCStr;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ffi::CStr;
+use core::fmt;
+use core::ops::Range;
+
+/// Every `BLOCK`th string is stored in full, so lookups can binary search over block heads.
+const BLOCK: usize = 16;
+
+/// A read-only, sorted set of strings stored with prefix compression.
+///
+/// Sorted strings often share long prefixes with their neighbours (think routing tables, or
+/// symbol names). `ArcCStrDict` stores each string as the length of the prefix it shares with
+/// the previous string plus the remaining suffix, in a single buffer. Every sixteenth string is
+/// stored in full so that lookups can binary search to the right place and only decode a few
+/// strings from there.
+///
+/// Strings are decoded into fresh [`ArcCStr`]s as they are retrieved. Cloning an `ArcCStrDict`
+/// is cheap, as clones share the underlying buffer.
+///
+/// # Examples
+///
+/// ```
+/// use std::ffi::CStr;
+/// use arccstr::ArcCStrDict;
+///
+/// let routes = ["/api/users", "/api/users/new", "/api/widgets", "/static"];
+/// let routes: Vec<_> = routes.iter().map(|r| std::ffi::CString::new(*r).unwrap()).collect();
+/// let dict = ArcCStrDict::from_sorted(&routes);
+///
+/// assert_eq!(dict.len(), 4);
+/// assert_eq!(dict.find(b"/api/widgets"), Ok(2));
+/// assert_eq!(dict.find(b"/api/x"), Err(3));
+/// assert_eq!(dict.prefix_range(b"/api/users"), 0..2);
+///
+/// let users: Vec<_> = dict.iter_prefix(b"/api/").map(|s| s.to_bytes().to_vec()).collect();
+/// assert_eq!(users.len(), 3);
+/// assert_eq!(dict.get(3).unwrap(), b"/static");
+/// ```
+#[derive(Clone)]
+pub struct ArcCStrDict {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// The encoded strings, each terminated by a `\0`.
+    bytes: Box<[u8]>,
+    /// The offset in `bytes` of the first (full) string of each block.
+    blocks: Box<[usize]>,
+    len: usize,
+}
+
+impl ArcCStrDict {
+    /// Builds a dictionary from `strings`, which must be sorted and free of duplicates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `strings` is not in strictly increasing order.
+    pub fn from_sorted<I, T>(strings: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<CStr>,
+    {
+        let mut bytes = Vec::new();
+        let mut blocks = Vec::new();
+        let mut prev: Vec<u8> = Vec::new();
+        let mut len = 0;
+        for s in strings {
+            let s = s.as_ref().to_bytes();
+            if len != 0 {
+                assert!(
+                    prev.as_slice() < s,
+                    "ArcCStrDict::from_sorted requires sorted, unique strings"
+                );
+            }
+            if len == blocks.len() * BLOCK {
+                blocks.push(bytes.len());
+                bytes.extend_from_slice(s);
+            } else {
+                let shared = prev.iter().zip(s).take_while(|(a, b)| a == b).count();
+                write_varint(&mut bytes, shared);
+                bytes.extend_from_slice(&s[shared..]);
+            }
+            bytes.push(0);
+            prev.clear();
+            prev.extend_from_slice(s);
+            len += 1;
+        }
+        ArcCStrDict {
+            inner: Arc::new(Inner {
+                bytes: bytes.into_boxed_slice(),
+                blocks: blocks.into_boxed_slice(),
+                len,
+            }),
+        }
+    }
+
+    /// The number of strings in the dictionary.
+    pub fn len(&self) -> usize {
+        self.inner.len
+    }
+
+    /// Returns true if the dictionary holds no strings.
+    pub fn is_empty(&self) -> bool {
+        self.inner.len == 0
+    }
+
+    /// Returns the `i`th string in sorted order, or `None` if `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<ArcCStr> {
+        self.iter_range(i..self.len()).next()
+    }
+
+    /// Returns true if the dictionary contains `key`.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.find(key).is_ok()
+    }
+
+    /// Searches for `key`, like [`slice::binary_search`].
+    ///
+    /// Returns `Ok` with the index of `key` if it is present, or `Err` with the index at which it
+    /// would be inserted otherwise.
+    pub fn find(&self, key: &[u8]) -> Result<usize, usize> {
+        let inner = &*self.inner;
+        // the last block whose head is <= key
+        let block = match inner
+            .blocks
+            .binary_search_by(|&offset| self.head(offset).cmp(key))
+        {
+            Ok(b) => return Ok(b * BLOCK),
+            Err(0) => return Err(0),
+            Err(b) => b - 1,
+        };
+
+        let start = block * BLOCK;
+        let end = usize::min(start + BLOCK, inner.len);
+        let mut cursor = self.cursor_at(start);
+        for i in start..end {
+            match cursor.advance(inner).as_slice().cmp(key) {
+                Ordering::Less => {}
+                Ordering::Equal => return Ok(i),
+                Ordering::Greater => return Err(i),
+            }
+        }
+        Err(end)
+    }
+
+    /// Returns the range of indices of the strings that start with `prefix`.
+    pub fn prefix_range(&self, prefix: &[u8]) -> Range<usize> {
+        let start = self.find(prefix).unwrap_or_else(|i| i);
+        // the smallest string greater than everything starting with prefix
+        let mut succ = prefix.to_vec();
+        while succ.last() == Some(&0xff) {
+            succ.pop();
+        }
+        let end = match succ.last_mut() {
+            Some(b) => {
+                *b += 1;
+                self.find(&succ).unwrap_or_else(|i| i)
+            }
+            None => self.len(),
+        };
+        start..end
+    }
+
+    /// Iterates over all the strings in sorted order.
+    pub fn iter(&self) -> DictIter<'_> {
+        self.iter_range(0..self.len())
+    }
+
+    /// Iterates over the strings that start with `prefix`, in sorted order.
+    pub fn iter_prefix(&self, prefix: &[u8]) -> DictIter<'_> {
+        self.iter_range(self.prefix_range(prefix))
+    }
+
+    fn iter_range(&self, range: Range<usize>) -> DictIter<'_> {
+        let end = usize::min(range.end, self.len());
+        let start = usize::min(range.start, end);
+        let mut cursor = self.cursor_at(start - start % BLOCK);
+        for _ in start - start % BLOCK..start {
+            cursor.advance(&self.inner);
+        }
+        DictIter {
+            inner: &self.inner,
+            cursor,
+            remaining: end - start,
+        }
+    }
+
+    fn cursor_at(&self, i: usize) -> Cursor {
+        debug_assert_eq!(i % BLOCK, 0);
+        Cursor {
+            offset: self.inner.blocks.get(i / BLOCK).copied().unwrap_or(0),
+            index: i,
+            buf: Vec::new(),
+        }
+    }
+
+    fn head(&self, offset: usize) -> &[u8] {
+        let bytes = &self.inner.bytes[offset..];
+        let len = bytes
+            .iter()
+            .position(|&b| b == 0)
+            .expect("strings are terminated");
+        &bytes[..len]
+    }
+}
+
+/// A position in the encoded strings, along with the previous decoded string.
+struct Cursor {
+    offset: usize,
+    index: usize,
+    buf: Vec<u8>,
+}
+
+impl Cursor {
+    /// Decodes the string at the cursor, and moves past it.
+    fn advance(&mut self, inner: &Inner) -> &Vec<u8> {
+        let in_block = self.index % BLOCK;
+        let shared = if in_block == 0 {
+            0
+        } else {
+            {
+                let (shared, len) = read_varint(&inner.bytes[self.offset..]).expect("valid varint");
+                self.offset += len;
+                shared
+            }
+        };
+        let suffix = &inner.bytes[self.offset..];
+        let suffix = &suffix[..suffix.iter().position(|&b| b == 0).expect("terminated")];
+        self.buf.truncate(shared);
+        self.buf.extend_from_slice(suffix);
+        self.offset += suffix.len() + 1;
+        self.index += 1;
+        &self.buf
+    }
+}
+
+/// An iterator over the strings of an [`ArcCStrDict`].
+///
+/// This is created by [`ArcCStrDict::iter`] and [`ArcCStrDict::iter_prefix`].
+pub struct DictIter<'a> {
+    inner: &'a Inner,
+    cursor: Cursor,
+    remaining: usize,
+}
+
+impl<'a> Iterator for DictIter<'a> {
+    type Item = ArcCStr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let s = self.cursor.advance(self.inner);
+        // the strings were NUL-free when the dictionary was built
+        Some(unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(s) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for DictIter<'a> {}
+
+impl<'a> fmt::Debug for DictIter<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DictIter")
+            .field("remaining", &self.remaining)
+            .finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a ArcCStrDict {
+    type Item = ArcCStr;
+    type IntoIter = DictIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for ArcCStrDict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCStrDict;
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    fn words() -> Vec<ArcCStr> {
+        let mut words: Vec<_> = (0..100u32)
+            .map(|i| ArcCStr::try_from(format!("key/{}/{}", i % 7, i)).unwrap())
+            .collect();
+        words.push(ArcCStr::try_from(&b"\xff\xff"[..]).unwrap());
+        words.push(ArcCStr::try_from(&b"\xff\xff\x01"[..]).unwrap());
+        words.sort();
+        words
+    }
+
+    #[test]
+    fn round_trip() {
+        let words = words();
+        let dict = ArcCStrDict::from_sorted(&words);
+        assert_eq!(dict.len(), words.len());
+        assert_eq!(dict.iter().collect::<Vec<_>>(), words);
+        for (i, w) in words.iter().enumerate() {
+            assert_eq!(dict.find(w.to_bytes()), Ok(i));
+            assert_eq!(dict.get(i).as_ref(), Some(w));
+        }
+        assert_eq!(dict.get(words.len()), None);
+    }
+
+    #[test]
+    fn missing() {
+        let words = words();
+        let dict = ArcCStrDict::from_sorted(&words);
+        for probe in [
+            &b""[..],
+            b"a",
+            b"key/3/",
+            b"key/3/999",
+            b"zzz",
+            b"\xff\xff\x00",
+        ] {
+            let expected = words.binary_search_by(|w| w.to_bytes().cmp(probe));
+            assert_eq!(dict.find(probe), expected, "{:?}", probe);
+        }
+    }
+
+    #[test]
+    fn prefixes() {
+        let words = words();
+        let dict = ArcCStrDict::from_sorted(&words);
+        for prefix in [
+            &b""[..],
+            b"key/",
+            b"key/3/",
+            b"key/3/1",
+            b"nope",
+            b"\xff",
+            b"\xff\xff",
+        ] {
+            let expected: Vec<_> = words
+                .iter()
+                .filter(|w| w.to_bytes().starts_with(prefix))
+                .cloned()
+                .collect();
+            assert_eq!(dict.iter_prefix(prefix).collect::<Vec<_>>(), expected);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let dict = ArcCStrDict::from_sorted(Vec::<ArcCStr>::new());
+        assert!(dict.is_empty());
+        assert_eq!(dict.find(b"x"), Err(0));
+        assert_eq!(dict.iter().count(), 0);
+        assert_eq!(dict.prefix_range(b""), 0..0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted() {
+        let words = [
+            ArcCStr::try_from("b").unwrap(),
+            ArcCStr::try_from("a").unwrap(),
+        ];
+        ArcCStrDict::from_sorted(&words);
+    }
+}
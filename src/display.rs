@@ -0,0 +1,164 @@
+use crate::ArcCStr;
+use core::fmt;
+
+/// Displays at most a fixed number of bytes of an [`ArcCStr`], for logging.
+///
+/// This is returned by [`ArcCStr::display_truncated`].
+#[derive(Clone, Copy)]
+pub struct DisplayTruncated<'a> {
+    bytes: &'a [u8],
+    truncated: bool,
+}
+
+impl ArcCStr {
+    /// Returns an adapter that displays at most the first `max` bytes of the string, for logging.
+    ///
+    /// If the string is longer, it is cut short, without splitting a character, and followed by
+    /// `…`. Control characters, such as newlines and terminal escape sequences, are escaped like
+    /// [`char::escape_default`] does, and invalid UTF-8 sequences are replaced with
+    /// `U+FFFD REPLACEMENT CHARACTER`. So however large or hostile the string, a log line
+    /// includes at most `max` bytes of it, and stays on one line. Escaping may make the output
+    /// itself longer than `max` bytes.
+    ///
+    /// Only the bytes that are displayed are ever read, so this is cheap even for huge strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::try_from("line one\nline two").unwrap();
+    /// assert_eq!(s.display_truncated(10).to_string(), "line one\\nl…");
+    /// assert_eq!(s.display_truncated(100).to_string(), "line one\\nline two");
+    /// ```
+    pub fn display_truncated(&self, max: usize) -> DisplayTruncated<'_> {
+        let bytes = self.to_bytes();
+        if bytes.len() <= max {
+            return DisplayTruncated {
+                bytes,
+                truncated: false,
+            };
+        }
+        DisplayTruncated {
+            bytes: &bytes[..floor_char_boundary(bytes, max)],
+            truncated: true,
+        }
+    }
+}
+
+/// Returns `max`, or the start of the UTF-8 character that `max` would cut in half.
+fn floor_char_boundary(bytes: &[u8], max: usize) -> usize {
+    // a character is at most four bytes long
+    for start in (max.saturating_sub(3)..max).rev() {
+        let b = bytes[start];
+        if b & 0xc0 == 0x80 {
+            // a continuation byte; keep looking for the first byte of the character
+            continue;
+        }
+        let width = match b {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        let end = (start + width).min(bytes.len());
+        // only back up for a real character; invalid bytes are replaced either way
+        if end > max && core::str::from_utf8(&bytes[start..end]).is_ok() {
+            return start;
+        }
+        break;
+    }
+    max
+}
+
+/// Writes `s`, escaping any control characters.
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    let mut run = 0;
+    for (i, c) in s.char_indices() {
+        if c.is_control() {
+            f.write_str(&s[run..i])?;
+            fmt::Display::fmt(&c.escape_default(), f)?;
+            run = i + c.len_utf8();
+        }
+    }
+    f.write_str(&s[run..])
+}
+
+impl fmt::Display for DisplayTruncated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = self.bytes;
+        loop {
+            match core::str::from_utf8(bytes) {
+                Ok(s) => {
+                    write_escaped(f, s)?;
+                    break;
+                }
+                Err(e) => {
+                    let (valid, rest) = bytes.split_at(e.valid_up_to());
+                    // from_utf8 just checked that this prefix is valid
+                    write_escaped(f, unsafe { core::str::from_utf8_unchecked(valid) })?;
+                    f.write_str("\u{FFFD}")?;
+                    match e.error_len() {
+                        Some(n) => bytes = &rest[n..],
+                        // the bytes end in the middle of a character
+                        None => break,
+                    }
+                }
+            }
+        }
+        if self.truncated {
+            f.write_str("…")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DisplayTruncated<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DisplayTruncated")
+            .field(&format_args!("{}", self))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    fn show(s: &[u8], max: usize) -> String {
+        ArcCStr::try_from(s)
+            .unwrap()
+            .display_truncated(max)
+            .to_string()
+    }
+
+    #[test]
+    fn truncates() {
+        assert_eq!(show(b"", 0), "");
+        assert_eq!(show(b"abc", 0), "…");
+        assert_eq!(show(b"abc", 3), "abc");
+        assert_eq!(show(b"abcd", 3), "abc…");
+        // never splits a character
+        assert_eq!(show("a€b".as_bytes(), 1), "a…");
+        assert_eq!(show("a€b".as_bytes(), 2), "a…");
+        assert_eq!(show("a€b".as_bytes(), 3), "a…");
+        assert_eq!(show("a€b".as_bytes(), 4), "a€…");
+        // but does cut invalid sequences
+        assert_eq!(show(b"a\xe2\x82z", 2), "a\u{FFFD}…");
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(show(b"\x1b[31mred\r\n", 100), "\\u{1b}[31mred\\r\\n");
+        assert_eq!(show(b"\xff\tx", 100), "\u{FFFD}\\tx");
+        assert_eq!(
+            format!(
+                "{:?}",
+                ArcCStr::try_from("a\"b").unwrap().display_truncated(2)
+            ),
+            "DisplayTruncated(a\"…)"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::raw::RawArcCStr;
+use core::fmt;
+use core::str::Utf8Error;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+/// An error returned by the fallible constructors in this crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ArcCStrError {
+    /// The input contained an internal `\0`.
+    InteriorNul {
+        /// The position of the first `\0` in the input.
+        pos: usize,
+    },
+    /// The input was required to be UTF-8, but was not.
+    Utf8(Utf8Error),
+    /// The input was an OS string that is not valid Unicode, on a platform where OS strings are
+    /// not arbitrary bytes.
+    NotUnicode,
+    /// The input was longer than the permitted maximum, which is at most the longest string a
+    /// header can hold ([`RawArcCStr::MAX_LEN`]).
+    TooLong {
+        /// The length of the input in bytes.
+        len: usize,
+        /// The maximum permitted length in bytes.
+        max_len: usize,
+    },
+    /// The input had no `\0` terminator within the permitted maximum length.
+    NotTerminated {
+        /// The maximum permitted length in bytes, excluding the terminator.
+        max_len: usize,
+    },
+    /// Memory for the string could not be allocated.
+    Alloc,
+    /// The input ended before the encoded string did.
+    Truncated,
+    /// The length prefix of an encoded string was malformed.
+    InvalidPrefix,
+    /// The input was not a `KEY=VALUE` pair with a non-empty key.
+    InvalidPair,
+    /// The input did not start with the magic bytes of its encoding.
+    InvalidMagic,
+    /// The input was encoded in a version of its layout that is not supported.
+    UnsupportedVersion {
+        /// The version the input was encoded in.
+        version: u8,
+    },
+}
+
+impl ArcCStrError {
+    /// Returns [`InteriorNul`](ArcCStrError::InteriorNul) if `buf` contains a `\0`.
+    pub(crate) fn check_no_nul(buf: &[u8]) -> Result<(), Self> {
+        match buf.iter().position(|&b| b == 0) {
+            Some(pos) => Err(ArcCStrError::InteriorNul { pos }),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns [`TooLong`](ArcCStrError::TooLong) if a string of `len` bytes is longer than the
+    /// header can hold (see [`RawArcCStr::MAX_LEN`]).
+    pub(crate) fn check_len(len: usize) -> Result<(), Self> {
+        Self::check_len_at_most(len, RawArcCStr::MAX_LEN)
+    }
+
+    /// Returns [`TooLong`](ArcCStrError::TooLong) if `len` is longer than `max_len`.
+    pub(crate) fn check_len_at_most(len: usize, max_len: usize) -> Result<(), Self> {
+        if len > max_len {
+            Err(ArcCStrError::TooLong { len, max_len })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for ArcCStrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ArcCStrError::InteriorNul { pos } => {
+                write!(
+                    f,
+                    "data provided contains an interior nul byte at pos {}",
+                    pos
+                )
+            }
+            ArcCStrError::Utf8(ref e) => write!(f, "data provided is not valid UTF-8: {}", e),
+            ArcCStrError::NotUnicode => f.write_str("OS string provided is not valid Unicode"),
+            ArcCStrError::TooLong { len, max_len } => write!(
+                f,
+                "string of {} bytes exceeds the maximum length of {} bytes",
+                len, max_len
+            ),
+            ArcCStrError::NotTerminated { max_len } => write!(
+                f,
+                "string is not terminated within the maximum length of {} bytes",
+                max_len
+            ),
+            ArcCStrError::Alloc => f.write_str("memory allocation failed"),
+            ArcCStrError::Truncated => f.write_str("input ended in the middle of a string"),
+            ArcCStrError::InvalidPrefix => f.write_str("invalid length prefix"),
+            ArcCStrError::InvalidPair => f.write_str("not a KEY=VALUE pair with a non-empty key"),
+            ArcCStrError::InvalidMagic => f.write_str("input does not start with the magic bytes"),
+            ArcCStrError::UnsupportedVersion { version } => {
+                write!(f, "unsupported encoding version {}", version)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ArcCStrError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ArcCStrError::Utf8(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Utf8Error> for ArcCStrError {
+    fn from(e: Utf8Error) -> Self {
+        ArcCStrError::Utf8(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCStrError;
+    use crate::raw::RawArcCStr;
+
+    #[test]
+    fn too_long() {
+        assert_eq!(ArcCStrError::check_len(RawArcCStr::MAX_LEN), Ok(()));
+        // there is no longer length to reject if the header can hold any length at all
+        if let Some(len) = RawArcCStr::MAX_LEN.checked_add(1) {
+            assert_eq!(
+                ArcCStrError::check_len(len),
+                Err(ArcCStrError::TooLong {
+                    len,
+                    max_len: RawArcCStr::MAX_LEN
+                })
+            );
+        }
+    }
+}
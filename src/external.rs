@@ -0,0 +1,161 @@
+use crate::raw::{External, RawArcCStr};
+use crate::ArcCStr;
+use alloc::sync::Arc;
+use core::ffi::c_char;
+use core::ptr::NonNull;
+
+/// The release hook of an external string, which drops its reference to the owner.
+unsafe extern "C" fn release_owner<O>(owner: *const ()) {
+    drop(Arc::from_raw(owner as *const O))
+}
+
+impl ArcCStr {
+    /// Constructs an `ArcCStr` from a string in memory that something else owns, such as a
+    /// memory-mapped file, without copying it.
+    ///
+    /// The string starts at `ptr` and is `len` bytes long, not counting its terminator. It is
+    /// never written to, so it may well be in read-only memory: the reference counts live in a
+    /// small allocation of their own, which points to the string. That makes reading an external
+    /// string go through one more pointer than reading any other string. It also means that
+    /// [`get_mut`](ArcCStr::get_mut) always returns `None` for it, and that
+    /// [`into_raw`](ArcCStr::into_raw) has to copy it.
+    ///
+    /// The returned `ArcCStr` holds a reference to `owner` until it and all of its clones and
+    /// weak references are gone, so that the memory stays valid while it is used. `owner` is
+    /// dropped on whichever thread drops the last of them, and must not panic when it is
+    /// dropped, or the process aborts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ptr` is null, if `len` is longer than the header can hold (see
+    /// [`RawArcCStr::MAX_LEN`](crate::raw::RawArcCStr::MAX_LEN)), or if the memory for the
+    /// reference counts cannot be allocated.
+    ///
+    /// # Safety
+    ///
+    ///  - `ptr[..len]` must contain no `\0`, and `ptr[len]` must be `\0`.
+    ///  - The string must stay valid and unchanged for as long as `owner` or a clone of it is
+    ///    alive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    /// use arccstr::ArcCStr;
+    ///
+    /// // a "file" of null-terminated words, which is only ever read
+    /// let file = Arc::new(b"apple\0banana\0".to_vec());
+    /// let base = file.as_ptr() as *const std::os::raw::c_char;
+    ///
+    /// let strings: Vec<ArcCStr> = [(0, 5), (6, 6)]
+    ///     .iter()
+    ///     .map(|&(at, len)| unsafe { ArcCStr::from_external(base.add(at), len, Arc::clone(&file)) })
+    ///     .collect();
+    /// drop(file);
+    /// assert_eq!(strings[1], "banana");
+    /// // the string was not copied
+    /// assert_eq!(strings[1].as_ptr(), unsafe { base.add(6) });
+    /// ```
+    pub unsafe fn from_external<O: Send + Sync + 'static>(
+        ptr: *const c_char,
+        len: usize,
+        owner: Arc<O>,
+    ) -> Self {
+        let data = NonNull::new(ptr as *mut u8).expect("null string");
+        assert!(len <= RawArcCStr::MAX_LEN, "string too long");
+        debug_assert!(!core::slice::from_raw_parts(data.as_ptr(), len).contains(&0));
+        debug_assert_eq!(*data.as_ptr().add(len), 0);
+        let owner = Arc::into_raw(owner);
+        let external = External {
+            data,
+            ctx: owner as *const (),
+            release: release_owner::<O>,
+        };
+        let raw = match RawArcCStr::try_allocate_external(external, len) {
+            Some(raw) => raw,
+            None => {
+                drop(Arc::from_raw(owner));
+                panic!("could not allocate memory");
+            }
+        };
+        let s = ArcCStr::from_raw_arc(raw);
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::construct(&s);
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+    use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+    use std::sync::Arc;
+
+    struct Mapping {
+        dropped: &'static AtomicBool,
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            self.dropped.store(true, SeqCst);
+        }
+    }
+
+    #[test]
+    fn released_with_last_reference() {
+        static DROPPED: AtomicBool = AtomicBool::new(false);
+        let owner = Arc::new(Mapping { dropped: &DROPPED });
+        let data = b"mapped\0".as_ptr() as *const c_char;
+
+        let s = unsafe { ArcCStr::from_external(data, 6, owner) };
+        assert_eq!(s, "mapped");
+        assert_eq!(s.as_ptr(), data);
+        let weak = ArcCStr::downgrade(&s);
+        let clone = s.clone();
+        drop(s);
+        assert!(!DROPPED.load(SeqCst));
+        drop(clone);
+        assert!(weak.upgrade().is_none());
+        assert!(!DROPPED.load(SeqCst));
+        drop(weak);
+        assert!(DROPPED.load(SeqCst));
+    }
+
+    #[test]
+    fn read_only() {
+        // a string literal lives in read-only memory, so writing to it would crash
+        static WORDS: &[u8] = b"read\0only\0";
+        let data = WORDS.as_ptr() as *const c_char;
+        let mut s = unsafe { ArcCStr::from_external(data.add(5), 4, Arc::new(())) };
+        assert_eq!(s.as_bytes_with_nul(), b"only\0");
+        assert_eq!(s.first_byte(), Some(b'o'));
+        assert_eq!(s.last_byte(), Some(b'y'));
+        assert_eq!(s.as_str(), Some("only"));
+        assert_eq!(&*s, CStr::from_bytes_with_nul(b"only\0").unwrap());
+        assert_eq!(s.as_ptr(), unsafe { data.add(5) });
+        // even the only reference cannot change the bytes
+        assert!(ArcCStr::get_mut(&mut s).is_none());
+        assert_eq!(WORDS, b"read\0only\0");
+    }
+
+    #[test]
+    fn copies_are_independent() {
+        let owner = Arc::new(b"abc\0".to_vec());
+        let data = owner.as_ptr() as *const c_char;
+        let s = unsafe { ArcCStr::from_external(data, 3, owner) };
+        let copy = unsafe { ArcCStr::from_allocation(s.to_allocation()) };
+        assert_eq!(copy, "abc");
+        assert_ne!(copy.as_ptr(), data);
+
+        // into_raw has to hand out a pointer to the bytes that leads back to the counts
+        let raw = ArcCStr::into_raw(ArcCStr::clone(&s));
+        assert_ne!(raw, data);
+        assert_eq!(unsafe { CStr::from_ptr(raw) }.to_bytes(), b"abc");
+        let back = unsafe { ArcCStr::from_raw(raw) };
+        assert_eq!(back, s);
+        drop(s);
+        assert_eq!(copy, "abc");
+    }
+}
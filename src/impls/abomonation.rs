@@ -0,0 +1,70 @@
+use crate::ArcCStr;
+use abomonation::Abomonation;
+use std::convert::TryFrom;
+use std::io::{Result, Write};
+use std::ptr;
+
+// An entombed string is its bytes and terminator, which is what the `extent` counts. Unlike a
+// `String`, an `ArcCStr` cannot point into the buffer it is decoded from, since its clones would
+// outlive the buffer, so it is exhumed into a fresh allocation instead.
+//
+// Note that abomonation only lends out the values it decodes, and never drops them, so that
+// allocation is leaked unless the decoded value is moved out of the buffer (with `ptr::read`) and
+// dropped. Clones of a decoded string do not depend on the buffer, and are freed as usual.
+impl Abomonation for ArcCStr {
+    #[inline]
+    unsafe fn entomb<W: Write>(&self, write: &mut W) -> Result<()> {
+        write.write_all(self.to_bytes_with_nul())
+    }
+
+    #[inline]
+    unsafe fn exhume<'b>(&mut self, bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+        let len = bytes.iter().position(|&b| b == 0)?;
+        let (mine, rest) = bytes.split_at_mut(len + 1);
+        let s = ArcCStr::try_from(&mine[..len]).ok()?;
+        // the pointer that was copied into the buffer is not ours to release
+        ptr::write(self, s);
+        Some(rest)
+    }
+
+    #[inline]
+    fn extent(&self) -> usize {
+        self.len() + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trip() {
+        let strings = vec![
+            ArcCStr::try_from("foo").unwrap(),
+            ArcCStr::try_from("").unwrap(),
+            ArcCStr::try_from(&b"\xffbar"[..]).unwrap(),
+        ];
+        let mut bytes = Vec::new();
+        unsafe { abomonation::encode(&strings, &mut bytes).unwrap() };
+        assert_eq!(abomonation::measure(&strings), bytes.len());
+
+        let (decoded, rest) = unsafe { abomonation::decode::<Vec<ArcCStr>>(&mut bytes) }.unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(decoded, &strings);
+        assert!(!ArcCStr::ptr_eq(&decoded[0], &strings[0]));
+        // decoded strings outlive the buffer
+        let kept = decoded[2].clone();
+        drop(bytes);
+        assert_eq!(kept, &b"\xffbar"[..]);
+    }
+
+    #[test]
+    fn truncated() {
+        let s = ArcCStr::try_from("foo").unwrap();
+        let mut bytes = Vec::new();
+        unsafe { abomonation::encode(&s, &mut bytes).unwrap() };
+        bytes.pop();
+        assert!(unsafe { abomonation::decode::<ArcCStr>(&mut bytes) }.is_none());
+    }
+}
@@ -0,0 +1,61 @@
+use crate::{ArcCStr, ArcCStrError};
+use bytes::Bytes;
+use std::convert::TryFrom;
+
+impl From<ArcCStr> for Bytes {
+    /// Converts the string into `Bytes` without copying it.
+    ///
+    /// The `Bytes` holds the string's bytes, excluding the null terminator, and keeps a
+    /// reference to the string until it and all of its clones and slices are dropped.
+    fn from(s: ArcCStr) -> Self {
+        Bytes::from_owner(s)
+    }
+}
+
+impl<'a> From<&'a ArcCStr> for Bytes {
+    /// Converts the string into `Bytes` without copying it, by taking another reference to it.
+    fn from(s: &'a ArcCStr) -> Self {
+        Bytes::from(ArcCStr::clone(s))
+    }
+}
+
+impl TryFrom<Bytes> for ArcCStr {
+    type Error = ArcCStrError;
+
+    /// Copies the bytes into a new string.
+    ///
+    /// The bytes have to be copied once, since an `ArcCStr` keeps its reference counts in the
+    /// same allocation as the string. This fails if the bytes contain a `\0`.
+    fn try_from(b: Bytes) -> Result<Self, Self::Error> {
+        ArcCStr::try_from(&*b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCStr, ArcCStrError};
+    use bytes::Bytes;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trip() {
+        let s = ArcCStr::try_from("GET /").unwrap();
+        let b = Bytes::from(&s);
+        assert_eq!(b, "GET /");
+        // points into the string rather than a copy
+        assert_eq!(b.as_ptr(), s.as_ptr() as *const u8);
+        assert_eq!(ArcCStr::strong_count(&s), 2);
+
+        let method = b.slice(..3);
+        drop(b);
+        assert_eq!(ArcCStr::strong_count(&s), 2);
+        drop(method);
+        assert_eq!(ArcCStr::strong_count(&s), 1);
+
+        assert_eq!(ArcCStr::try_from(Bytes::from(s.clone())).unwrap(), s);
+        assert_eq!(
+            ArcCStr::try_from(Bytes::from_static(b"a\0")),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+}
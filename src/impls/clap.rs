@@ -0,0 +1,90 @@
+use crate::ArcCStr;
+use clap::builder::{TypedValueParser, ValueParserFactory};
+use clap::error::ErrorKind;
+use std::convert::TryFrom;
+use std::ffi::OsStr;
+
+/// A [`clap`] value parser that produces [`ArcCStr`] arguments.
+///
+/// You normally won't need to name this type: since `ArcCStr` implements
+/// [`ValueParserFactory`], `clap::value_parser!(ArcCStr)` and `#[arg] name: ArcCStr` both pick
+/// it up automatically. On Unix the raw argument bytes are used as-is, so non-UTF-8 arguments are
+/// accepted; elsewhere, the argument must be valid UTF-8. Arguments containing an internal `\0`
+/// are always rejected.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ArcCStrValueParser;
+
+impl TypedValueParser for ArcCStrValueParser {
+    type Value = ArcCStr;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let arg = arg
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "...".to_owned());
+
+        #[cfg(unix)]
+        let bytes = {
+            use std::os::unix::ffi::OsStrExt;
+            value.as_bytes()
+        };
+        #[cfg(not(unix))]
+        let bytes = match value.to_str() {
+            Some(s) => s.as_bytes(),
+            None => {
+                let msg = format!("invalid UTF-8 was detected in the value for '{}'", arg);
+                return Err(clap::Error::raw(ErrorKind::InvalidUtf8, msg).with_cmd(cmd));
+            }
+        };
+
+        ArcCStr::try_from(bytes).map_err(|e| {
+            let msg = format!(
+                "invalid value '{}' for '{}': {}",
+                value.to_string_lossy(),
+                arg,
+                e
+            );
+            clap::Error::raw(ErrorKind::InvalidValue, msg).with_cmd(cmd)
+        })
+    }
+}
+
+impl ValueParserFactory for ArcCStr {
+    type Parser = ArcCStrValueParser;
+
+    fn value_parser() -> Self::Parser {
+        ArcCStrValueParser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use clap::error::ErrorKind;
+    use clap::{Arg, Command};
+    use std::convert::TryFrom;
+    use std::ffi::OsString;
+
+    fn cmd() -> Command {
+        Command::new("test").arg(Arg::new("name").value_parser(clap::value_parser!(ArcCStr)))
+    }
+
+    #[test]
+    fn parses() {
+        let m = cmd().try_get_matches_from(["test", "foo"]).unwrap();
+        let name: &ArcCStr = m.get_one("name").unwrap();
+        assert_eq!(name, &ArcCStr::try_from("foo").unwrap());
+    }
+
+    #[test]
+    fn rejects_nul() {
+        let err = cmd()
+            .try_get_matches_from([OsString::from("test"), OsString::from("a\0b")])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::ArcCStr;
+
+impl ArcCStr {
+    /// Returns the position of the first byte of the string that is one of `needles`.
+    ///
+    /// This uses the vectorized searches of the [`memchr`] crate when there are at most three
+    /// needles, which covers most delimiter scans. Since the string cannot contain a `\0`, a `\0`
+    /// needle never matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let route = ArcCStr::try_from("/users/42?fields=name#top").unwrap();
+    /// assert_eq!(route.find_any(b"?#"), Some(9));
+    /// assert_eq!(route.find_any(b"&"), None);
+    /// ```
+    pub fn find_any(&self, needles: &[u8]) -> Option<usize> {
+        let haystack = self.to_bytes();
+        match *needles {
+            [] => None,
+            [a] => memchr::memchr(a, haystack),
+            [a, b] => memchr::memchr2(a, b, haystack),
+            [a, b, c] => memchr::memchr3(a, b, c, haystack),
+            _ => {
+                let mut set = [false; 256];
+                for &n in needles {
+                    set[usize::from(n)] = true;
+                }
+                haystack.iter().position(|&b| set[usize::from(b)])
+            }
+        }
+    }
+
+    /// Returns the position of the first occurrence of `needle` in the string.
+    ///
+    /// This uses [`memchr::memmem`]. An empty needle is found at position 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let header = ArcCStr::try_from("text/html; charset=utf-8").unwrap();
+    /// assert_eq!(header.find(b"charset="), Some(11));
+    /// assert_eq!(header.find(b"boundary="), None);
+    /// ```
+    pub fn find(&self, needle: &[u8]) -> Option<usize> {
+        memchr::memmem::find(self.to_bytes(), needle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn find_any() {
+        let s = ArcCStr::try_from("a,b;c d|e").unwrap();
+        assert_eq!(s.find_any(b""), None);
+        assert_eq!(s.find_any(b";"), Some(3));
+        assert_eq!(s.find_any(b"|;"), Some(3));
+        assert_eq!(s.find_any(b" |;"), Some(3));
+        assert_eq!(s.find_any(b"| ;,"), Some(1));
+        assert_eq!(s.find_any(b"\0xyz"), None);
+    }
+
+    #[test]
+    fn find() {
+        let s = ArcCStr::try_from("abcabd").unwrap();
+        assert_eq!(s.find(b"abd"), Some(3));
+        assert_eq!(s.find(b""), Some(0));
+        assert_eq!(s.find(b"abcabdx"), None);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::ArcCStr;
+use mlua::{Error, FromLua, IntoLua, Lua, Result, Value};
+use std::convert::TryFrom;
+
+// Note that mlua requires the final binary to pick a Lua version (e.g., `mlua/lua54`), so we
+// don't pick one here.
+
+impl IntoLua for ArcCStr {
+    #[inline]
+    fn into_lua(self, lua: &Lua) -> Result<Value> {
+        (&self).into_lua(lua)
+    }
+}
+
+impl IntoLua for &ArcCStr {
+    #[inline]
+    fn into_lua(self, lua: &Lua) -> Result<Value> {
+        Ok(Value::String(lua.create_string(self.to_bytes())?))
+    }
+}
+
+impl FromLua for ArcCStr {
+    /// Converts a Lua string (or number, following Lua's coercion rules) into an `ArcCStr`.
+    ///
+    /// Lua strings may contain `\0`, so this fails for strings with an internal `\0`.
+    fn from_lua(value: Value, lua: &Lua) -> Result<Self> {
+        let from = value.type_name();
+        let conversion_error = |message: &str| Error::FromLuaConversionError {
+            from,
+            to: "ArcCStr".to_owned(),
+            message: Some(message.to_owned()),
+        };
+
+        let s = lua
+            .coerce_string(value)?
+            .ok_or_else(|| conversion_error("expected string or number"))?;
+        ArcCStr::try_from(&*s.as_bytes()).map_err(|_| conversion_error("invalid C-style string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use mlua::Lua;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trip() {
+        let lua = Lua::new();
+        let foo = ArcCStr::try_from("foo").unwrap();
+        lua.globals().set("foo", foo.clone()).unwrap();
+        let back: ArcCStr = lua.load("return foo .. 'bar'").eval().unwrap();
+        assert_eq!(back, ArcCStr::try_from("foobar").unwrap());
+
+        let n: ArcCStr = lua.load("return 42").eval().unwrap();
+        assert_eq!(n, ArcCStr::try_from("42").unwrap());
+    }
+
+    #[test]
+    fn rejects() {
+        let lua = Lua::new();
+        assert!(lua.load("return 'f\\0o'").eval::<ArcCStr>().is_err());
+        assert!(lua.load("return {}").eval::<ArcCStr>().is_err());
+    }
+}
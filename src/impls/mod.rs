@@ -0,0 +1,30 @@
+//! Trait implementations for third-party crates, each behind its own feature.
+
+#[cfg(feature = "abomonation")]
+mod abomonation;
+#[cfg(feature = "bytes")]
+mod bytes;
+#[cfg(feature = "clap")]
+mod clap;
+#[cfg(feature = "clap")]
+pub use self::clap::ArcCStrValueParser;
+#[cfg(feature = "memchr")]
+mod memchr;
+#[cfg(feature = "mlua")]
+mod mlua;
+#[cfg(feature = "napi")]
+mod napi;
+#[cfg(feature = "postgres-types")]
+mod postgres_types;
+#[cfg(feature = "rmp-serde")]
+mod rmp_serde;
+#[cfg(feature = "rusqlite")]
+mod rusqlite;
+#[cfg(feature = "serde_json")]
+mod serde_json;
+#[cfg(feature = "tokio-util")]
+mod tokio_util;
+#[cfg(feature = "tokio-util")]
+pub use self::tokio_util::{LengthPrefixedCodec, NulCodec};
+#[cfg(feature = "utoipa")]
+mod utoipa;
@@ -0,0 +1,53 @@
+use crate::ArcCStr;
+use napi::bindgen_prelude::{FromNapiValue, ToNapiValue, TypeName, ValidateNapiValue, ValueType};
+use napi::{sys, Error, Result, Status};
+use std::convert::TryFrom;
+
+// JavaScript strings are converted through UTF-8, so only strings that are valid UTF-8 can cross
+// the boundary.
+
+impl TypeName for ArcCStr {
+    fn type_name() -> &'static str {
+        "ArcCStr"
+    }
+
+    fn value_type() -> ValueType {
+        ValueType::String
+    }
+}
+
+impl ValidateNapiValue for ArcCStr {}
+
+impl ToNapiValue for &ArcCStr {
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+        let s = val.to_str().map_err(|e| {
+            Error::new(
+                Status::InvalidArg,
+                format!("Failed to convert rust `ArcCStr` into napi `string`: {}", e),
+            )
+        })?;
+        unsafe { ToNapiValue::to_napi_value(env, s) }
+    }
+}
+
+impl ToNapiValue for ArcCStr {
+    #[inline]
+    unsafe fn to_napi_value(env: sys::napi_env, val: Self) -> Result<sys::napi_value> {
+        unsafe { ToNapiValue::to_napi_value(env, &val) }
+    }
+}
+
+impl FromNapiValue for ArcCStr {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+        let s = unsafe { String::from_napi_value(env, napi_val)? };
+        ArcCStr::try_from(s).map_err(|e| {
+            Error::new(
+                Status::InvalidArg,
+                format!(
+                    "Failed to convert napi `string` into rust type `ArcCStr`: {}",
+                    e
+                ),
+            )
+        })
+    }
+}
@@ -0,0 +1,72 @@
+use crate::ArcCStr;
+use bytes::BytesMut;
+use postgres_types::{FromSql, IsNull, ToSql, Type};
+use std::convert::TryFrom;
+use std::error::Error;
+
+// PostgreSQL sends text over the wire without a terminator, so there is always a copy in each
+// direction. Both go through the `&str` impls, which know about every text-like type (including
+// the ones with a version prefix, like `ltree`).
+
+impl ToSql for ArcCStr {
+    /// Writes the string as text, failing if it is not UTF-8, which PostgreSQL requires.
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        self.to_str()?.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as ToSql>::accepts(ty)
+    }
+
+    postgres_types::to_sql_checked!();
+}
+
+impl<'a> FromSql<'a> for ArcCStr {
+    /// Reads a text column, copying it straight into a new string.
+    ///
+    /// PostgreSQL text cannot contain `\0`, so this only fails if the column is not text.
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn Error + Sync + Send>> {
+        let s = <&str as FromSql>::from_sql(ty, raw)?;
+        Ok(ArcCStr::try_from(s)?)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <&str as FromSql>::accepts(ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use bytes::BytesMut;
+    use postgres_types::{FromSql, IsNull, ToSql, Type};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trip() {
+        let s = ArcCStr::try_from("héllo").unwrap();
+        let mut out = BytesMut::new();
+        assert!(matches!(s.to_sql(&Type::TEXT, &mut out), Ok(IsNull::No)));
+        assert_eq!(&out[..], "héllo".as_bytes());
+        let back = ArcCStr::from_sql(&Type::VARCHAR, &out).unwrap();
+        assert_eq!(back, s);
+        assert_eq!(back.as_str(), Some("héllo"));
+    }
+
+    #[test]
+    fn rejects() {
+        assert!(<ArcCStr as ToSql>::accepts(&Type::TEXT));
+        assert!(!<ArcCStr as ToSql>::accepts(&Type::INT4));
+        assert!(!<ArcCStr as FromSql<'_>>::accepts(&Type::BYTEA));
+        let invalid = ArcCStr::try_from(&b"\xff"[..]).unwrap();
+        assert!(invalid.to_sql(&Type::TEXT, &mut BytesMut::new()).is_err());
+        assert!(invalid
+            .to_sql_checked(&Type::INT4, &mut BytesMut::new())
+            .is_err());
+        assert!(ArcCStr::from_sql(&Type::TEXT, b"\xff").is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::ArcCStr;
+
+impl ArcCStr {
+    /// Encodes the string as a MessagePack value.
+    ///
+    /// The string is always written in the `bin` format, even if it is valid UTF-8, since its
+    /// contents are arbitrary bytes. This matches how `ArcCStr` is serialized by
+    /// [`rmp_serde`] as part of a larger structure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::ArcCStr;
+    ///
+    /// let foo = ArcCStr::try_from("foo").unwrap();
+    /// assert_eq!(foo.to_msgpack_bytes(), b"\xc4\x03foo");
+    /// ```
+    pub fn to_msgpack_bytes(&self) -> Vec<u8> {
+        rmp_serde::to_vec(self).expect("writing to a Vec cannot fail")
+    }
+
+    /// Decodes a string from a MessagePack value.
+    ///
+    /// Both the `bin` and the `str` formats are accepted, so strings written by other MessagePack
+    /// implementations can be read too. Decoding fails for other kinds of values, and for strings
+    /// that contain an internal `\0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// assert_eq!(ArcCStr::from_msgpack_bytes(b"\xc4\x03foo").unwrap(), b"foo");
+    /// assert_eq!(ArcCStr::from_msgpack_bytes(b"\xa3foo").unwrap(), b"foo");
+    /// ```
+    pub fn from_msgpack_bytes(bytes: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn non_utf8() {
+        let s = ArcCStr::try_from(&b"f\xffo"[..]).unwrap();
+        let bytes = s.to_msgpack_bytes();
+        assert_eq!(bytes, b"\xc4\x03f\xffo");
+        assert_eq!(ArcCStr::from_msgpack_bytes(&bytes).unwrap(), s);
+    }
+
+    #[test]
+    fn rejects() {
+        assert!(ArcCStr::from_msgpack_bytes(b"\xc4\x03f\0o").is_err());
+        assert!(ArcCStr::from_msgpack_bytes(b"\x2a").is_err());
+        assert!(ArcCStr::from_msgpack_bytes(b"\xc4\x03fo").is_err());
+    }
+
+    #[test]
+    fn nested() {
+        let record = (
+            7u32,
+            ArcCStr::try_from(&b"\xfe\xff"[..]).unwrap(),
+            vec![
+                ArcCStr::try_from("a").unwrap(),
+                ArcCStr::try_from("").unwrap(),
+            ],
+        );
+        let bytes = rmp_serde::to_vec(&record).unwrap();
+        let back: (u32, ArcCStr, Vec<ArcCStr>) = rmp_serde::from_slice(&bytes).unwrap();
+        assert_eq!(back, record);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::ArcCStr;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::ToSql;
+use std::convert::TryFrom;
+
+impl ToSql for ArcCStr {
+    /// Binds the string as text, by reference, so the string is not copied until SQLite copies
+    /// it.
+    ///
+    /// SQLite does not check that text is UTF-8, so neither does this.
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Borrowed(ValueRef::Text(self.to_bytes())))
+    }
+}
+
+impl FromSql for ArcCStr {
+    /// Reads a text or blob column, copying it straight into a new string.
+    ///
+    /// Fails if the value contains a `\0`, which SQLite allows.
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value {
+            ValueRef::Text(bytes) | ValueRef::Blob(bytes) => {
+                ArcCStr::try_from(bytes).map_err(|e| FromSqlError::Other(Box::new(e)))
+            }
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use rusqlite::Connection;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trip() {
+        let db = Connection::open_in_memory().unwrap();
+        let s = ArcCStr::try_from("héllo").unwrap();
+        let back: ArcCStr = db
+            .query_row("SELECT ?1 || '!'", [&s], |row| row.get(0))
+            .unwrap();
+        assert_eq!(back, "héllo!");
+        let blob: ArcCStr = db
+            .query_row("SELECT x'616263'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(blob, "abc");
+    }
+
+    #[test]
+    fn rejects() {
+        let db = Connection::open_in_memory().unwrap();
+        let nul = db.query_row("SELECT x'610062'", [], |row| row.get::<_, ArcCStr>(0));
+        assert!(nul.is_err());
+        let int = db.query_row("SELECT 1", [], |row| row.get::<_, ArcCStr>(0));
+        assert!(int.is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::ArcCStr;
+use serde_json::Value;
+use std::convert::TryFrom;
+
+impl<'a> From<&'a ArcCStr> for Value {
+    /// Converts the string into a JSON value.
+    ///
+    /// Valid UTF-8 strings become [`Value::String`]. Other strings become a [`Value::Array`] of
+    /// their bytes, which is also how they are represented when serialized with `serde_json`.
+    fn from(s: &'a ArcCStr) -> Self {
+        match s.to_str() {
+            Ok(s) => Value::String(s.to_owned()),
+            Err(_) => Value::Array(s.to_bytes().iter().map(|&b| Value::from(b)).collect()),
+        }
+    }
+}
+
+impl From<ArcCStr> for Value {
+    /// Converts the string into a JSON value.
+    ///
+    /// See the implementation for `&ArcCStr` for details.
+    fn from(s: ArcCStr) -> Self {
+        Value::from(&s)
+    }
+}
+
+impl TryFrom<Value> for ArcCStr {
+    type Error = serde_json::Error;
+
+    /// Converts a JSON string, or a JSON array of byte values, into an `ArcCStr`.
+    ///
+    /// This fails for any other kind of JSON value, and for strings that contain an internal
+    /// `\0`.
+    fn try_from(v: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use serde_json::{json, Value};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn round_trip() {
+        let foo = ArcCStr::try_from("foo").unwrap();
+        assert_eq!(Value::from(&foo), json!("foo"));
+        assert_eq!(ArcCStr::try_from(Value::from(foo.clone())).unwrap(), foo);
+
+        let invalid = ArcCStr::try_from(&b"\xff\x01"[..]).unwrap();
+        assert_eq!(Value::from(&invalid), json!([255, 1]));
+        assert_eq!(ArcCStr::try_from(Value::from(&invalid)).unwrap(), invalid);
+    }
+
+    #[test]
+    fn rejects() {
+        assert!(ArcCStr::try_from(json!("f\u{0}o")).is_err());
+        assert!(ArcCStr::try_from(json!(42)).is_err());
+        assert!(ArcCStr::try_from(json!([256])).is_err());
+    }
+}
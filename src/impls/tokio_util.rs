@@ -0,0 +1,221 @@
+use crate::codec::write_varint;
+use crate::{ArcCStr, ArcCStrError, LengthPrefix};
+use bytes::{Buf, BufMut, BytesMut};
+use std::io;
+use tokio_util::codec::{Decoder, Encoder};
+
+fn invalid_data(e: ArcCStrError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// A [`tokio_util`] codec for streams of `\0`-terminated [`ArcCStr`] frames.
+///
+/// Each frame is copied once, straight from the read buffer into its `ArcCStr`.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, NulCodec};
+/// use bytes::BytesMut;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// let mut buf = BytesMut::new();
+/// NulCodec::new().encode(ArcCStr::try_from("PING").unwrap(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"PING\0");
+///
+/// let frame = NulCodec::new().decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(frame, b"PING");
+/// assert!(buf.is_empty());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NulCodec {
+    // how far into the buffer we have already searched for a \0
+    searched: usize,
+}
+
+impl NulCodec {
+    /// Constructs a new `NulCodec`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Decoder for NulCodec {
+    type Item = ArcCStr;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match src[self.searched..].iter().position(|&b| b == 0) {
+            Some(i) => {
+                let end = self.searched + i;
+                self.searched = 0;
+                // the frame ends at the first \0, so contains no others
+                let s = unsafe { ArcCStr::from_raw_cstr_no_nul_unchecked(&src[..end]) };
+                src.advance(end + 1);
+                Ok(Some(s))
+            }
+            None => {
+                self.searched = src.len();
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl<'a> Encoder<&'a ArcCStr> for NulCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &'a ArcCStr, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(item.to_bytes_with_nul());
+        Ok(())
+    }
+}
+
+impl Encoder<ArcCStr> for NulCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: ArcCStr, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(&item, dst)
+    }
+}
+
+/// A [`tokio_util`] codec for [`ArcCStr`] frames preceded by their length.
+///
+/// Frames use the same format as [`ArcCStr::encode_length_prefixed`]. Frames that contain a `\0`
+/// or have a malformed length are rejected with [`io::ErrorKind::InvalidData`].
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, LengthPrefix, LengthPrefixedCodec};
+/// use bytes::BytesMut;
+/// use tokio_util::codec::{Decoder, Encoder};
+///
+/// let mut codec = LengthPrefixedCodec::new(LengthPrefix::Varint);
+/// let mut buf = BytesMut::new();
+/// codec.encode(ArcCStr::try_from("PING").unwrap(), &mut buf).unwrap();
+/// assert_eq!(&buf[..], b"\x04PING");
+///
+/// let frame = codec.decode(&mut buf).unwrap().unwrap();
+/// assert_eq!(frame, b"PING");
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LengthPrefixedCodec {
+    prefix: LengthPrefix,
+}
+
+impl LengthPrefixedCodec {
+    /// Constructs a codec whose frames carry the given kind of length prefix.
+    pub fn new(prefix: LengthPrefix) -> Self {
+        LengthPrefixedCodec { prefix }
+    }
+}
+
+impl Decoder for LengthPrefixedCodec {
+    type Item = ArcCStr;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match ArcCStr::decode_length_prefixed(self.prefix, src) {
+            Ok((s, rest)) => {
+                let used = src.len() - rest.len();
+                src.advance(used);
+                Ok(Some(s))
+            }
+            Err(ArcCStrError::Truncated) => Ok(None),
+            Err(e) => Err(invalid_data(e)),
+        }
+    }
+}
+
+impl<'a> Encoder<&'a ArcCStr> for LengthPrefixedCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: &'a ArcCStr, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let bytes = item.to_bytes();
+        match self.prefix {
+            LengthPrefix::U32 => {
+                if bytes.len() > u32::MAX as usize {
+                    return Err(invalid_data(ArcCStrError::TooLong {
+                        len: bytes.len(),
+                        max_len: u32::MAX as usize,
+                    }));
+                }
+                dst.reserve(4 + bytes.len());
+                dst.put_u32(bytes.len() as u32);
+            }
+            LengthPrefix::Varint => {
+                let mut prefix = Vec::with_capacity(10);
+                write_varint(&mut prefix, bytes.len());
+                dst.reserve(prefix.len() + bytes.len());
+                dst.extend_from_slice(&prefix);
+            }
+        }
+        dst.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+impl Encoder<ArcCStr> for LengthPrefixedCodec {
+    type Error = io::Error;
+
+    fn encode(&mut self, item: ArcCStr, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(&item, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LengthPrefixedCodec, NulCodec};
+    use crate::{ArcCStr, LengthPrefix};
+    use bytes::BytesMut;
+    use std::convert::TryFrom;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    #[test]
+    fn nul_split_frames() {
+        let mut codec = NulCodec::new();
+        let mut buf = BytesMut::from(&b"ab"[..]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(b"c\0d\0e");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"abc");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"d");
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(&buf[..], b"e");
+    }
+
+    #[test]
+    fn length_prefixed() {
+        for &prefix in &[LengthPrefix::U32, LengthPrefix::Varint] {
+            let mut codec = LengthPrefixedCodec::new(prefix);
+            let mut encoded = BytesMut::new();
+            for s in &["", "foo", "bar"] {
+                codec
+                    .encode(ArcCStr::try_from(*s).unwrap(), &mut encoded)
+                    .unwrap();
+            }
+
+            // feed it in one byte at a time
+            let mut buf = BytesMut::new();
+            let mut frames = Vec::new();
+            for &b in &encoded[..] {
+                buf.extend_from_slice(&[b]);
+                while let Some(s) = codec.decode(&mut buf).unwrap() {
+                    frames.push(s);
+                }
+            }
+            let frames: Vec<_> = frames.iter().map(|s| s.to_bytes()).collect();
+            assert_eq!(frames, [&b""[..], b"foo", b"bar"]);
+        }
+    }
+
+    #[test]
+    fn length_prefixed_rejects_nul() {
+        let mut codec = LengthPrefixedCodec::new(LengthPrefix::Varint);
+        let mut buf = BytesMut::from(&b"\x02a\0"[..]);
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::ArcCStr;
+use std::borrow::Cow;
+use utoipa::openapi::schema::{ArrayBuilder, ObjectBuilder, OneOfBuilder, Schema, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
+
+// This mirrors the `serde` representation. In human-readable formats like JSON, an `ArcCStr`
+// that is valid UTF-8 is serialized as a string, and any other one as an array of its raw bytes
+// (without the terminator), which are non-zero integers.
+impl PartialSchema for ArcCStr {
+    fn schema() -> RefOr<Schema> {
+        OneOfBuilder::new()
+            .item(
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .pattern(Some("^[^\\u0000]*$")),
+            )
+            .item(
+                ArrayBuilder::new().items(
+                    ObjectBuilder::new()
+                        .schema_type(Type::Integer)
+                        .minimum(Some(1))
+                        .maximum(Some(255)),
+                ),
+            )
+            .description(Some(
+                "A C-style string with no nulls, as a string or as an array of its bytes",
+            ))
+            .into()
+    }
+}
+
+impl ToSchema for ArcCStr {
+    fn name() -> Cow<'static, str> {
+        Cow::Borrowed("ArcCStr")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use utoipa::{PartialSchema, ToSchema};
+
+    #[test]
+    fn schema_is_string_or_byte_array() {
+        assert_eq!(ArcCStr::name(), "ArcCStr");
+        let schema = serde_json::to_value(ArcCStr::schema()).unwrap();
+        let one_of = schema["oneOf"].as_array().unwrap();
+        assert_eq!(one_of[0]["type"], "string");
+        assert_eq!(one_of[1]["type"], "array");
+        assert_eq!(one_of[1]["items"]["type"], "integer");
+        assert_eq!(one_of[1]["items"]["minimum"], 1);
+        assert_eq!(one_of[1]["items"]["maximum"], 255);
+    }
+}
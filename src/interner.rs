@@ -0,0 +1,625 @@
+use crate::{ArcCStr, ArcCStrError};
+use std::borrow::{Borrow, Cow};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, Once, Weak};
+
+/// The number of independently locked parts of an interner, to reduce contention.
+const SHARDS: usize = 16;
+
+type Shards = [Mutex<HashSet<Entry>>];
+
+/// The interners that have been added with [`Interner::register`], by name.
+///
+/// Only the sets of strings are referenced, weakly, so that interners can still be moved around
+/// and dropped as usual. Dropped interners are cleaned up by the next report.
+static REGISTRY: Mutex<Vec<(String, Weak<Shards>)>> = Mutex::new(Vec::new());
+
+/// How an [`Interner`] decides whether two strings are the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CaseFolding {
+    /// Strings are the same only if their bytes are equal.
+    None,
+    /// Strings are the same if they are equal after mapping ASCII letters to lowercase.
+    Ascii,
+    /// Strings are the same if they are equal after mapping all characters to lowercase.
+    ///
+    /// Strings that are not valid UTF-8 are only the same if their bytes are equal.
+    Unicode,
+}
+
+impl CaseFolding {
+    /// Returns the key under which `bytes` is interned.
+    fn key<'a>(&self, bytes: &'a [u8]) -> Cow<'a, [u8]> {
+        match *self {
+            CaseFolding::None => Cow::Borrowed(bytes),
+            CaseFolding::Ascii if !bytes.iter().any(u8::is_ascii_uppercase) => Cow::Borrowed(bytes),
+            CaseFolding::Ascii => Cow::Owned(bytes.to_ascii_lowercase()),
+            // a lowercased string is UTF-8, so it can't be mistaken for a non-UTF-8 one
+            CaseFolding::Unicode => match std::str::from_utf8(bytes) {
+                Ok(s) => match s.to_lowercase() {
+                    lower if lower == s => Cow::Borrowed(bytes),
+                    lower => Cow::Owned(lower.into_bytes()),
+                },
+                Err(_) => Cow::Borrowed(bytes),
+            },
+        }
+    }
+}
+
+/// An interned string, hashed and compared by its key so that it can be looked up by `&[u8]`.
+struct Entry {
+    // the folded key, if it differs from the string itself
+    key: Option<Box<[u8]>>,
+    s: ArcCStr,
+}
+
+impl Entry {
+    fn new(key: Cow<'_, [u8]>, s: ArcCStr) -> Self {
+        let key = match key {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(key) => Some(key.into_boxed_slice()),
+        };
+        Entry { key, s }
+    }
+}
+
+impl Hash for Entry {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Borrow::<[u8]>::borrow(self).hash(state)
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        Borrow::<[u8]>::borrow(self) == Borrow::<[u8]>::borrow(other)
+    }
+}
+impl Eq for Entry {}
+
+impl Borrow<[u8]> for Entry {
+    fn borrow(&self) -> &[u8] {
+        match self.key {
+            Some(ref key) => key,
+            None => self.s.to_bytes(),
+        }
+    }
+}
+
+/// A set of strings that hands out a single shared [`ArcCStr`] for all equal strings.
+///
+/// Interning a string returns the `ArcCStr` that the interner already holds for equal contents,
+/// if any, and otherwise stores a new one. Since all equal strings interned through the same
+/// interner share an allocation, [`ArcCStr::ptr_eq`] can be used to compare them, and storing
+/// many copies of the same string costs just a pointer each.
+///
+/// The interner keeps every string it has handed out alive. Use [`purge`](Interner::purge) to
+/// drop the strings that are no longer used anywhere else.
+///
+/// An interner can also be made to ignore case with [`with_case_folding`], for things like HTTP
+/// header names or SQL identifiers. Equal strings then share the spelling that was interned first.
+///
+/// For a process-wide interner, see [`ArcCStr::intern`]. To monitor several interners the same
+/// way, [`register`](Interner::register) them and collect their sizes with
+/// [`Interner::report`].
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::{ArcCStr, Interner};
+///
+/// let interner = Interner::new();
+/// let a = interner.intern("foo").unwrap();
+/// let b = interner.intern(String::from("foo")).unwrap();
+/// assert!(ArcCStr::ptr_eq(&a, &b));
+/// assert_eq!(interner.len(), 1);
+///
+/// drop((a, b));
+/// interner.purge();
+/// assert!(interner.is_empty());
+/// ```
+///
+/// [`with_case_folding`]: Interner::with_case_folding
+pub struct Interner {
+    folding: CaseFolding,
+    hasher: RandomState,
+    shards: Arc<Shards>,
+}
+
+impl Interner {
+    /// Constructs a new, empty interner.
+    pub fn new() -> Self {
+        Self::with_case_folding(CaseFolding::None)
+    }
+
+    /// Constructs a new, empty interner that considers strings that differ only in case to be the
+    /// same.
+    ///
+    /// Interning a string returns the spelling of it that was interned first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::{CaseFolding, Interner};
+    ///
+    /// let headers = Interner::with_case_folding(CaseFolding::Ascii);
+    /// let first = headers.intern("Content-Type").unwrap();
+    /// let second = headers.intern("content-type").unwrap();
+    /// assert_eq!(second, first);
+    /// assert_eq!(second.to_str(), Ok("Content-Type"));
+    /// ```
+    pub fn with_case_folding(folding: CaseFolding) -> Self {
+        Interner {
+            folding,
+            hasher: RandomState::new(),
+            shards: (0..SHARDS).map(|_| Mutex::new(HashSet::new())).collect(),
+        }
+    }
+
+    // BuildHasher::hash_one is newer than our minimum supported Rust version
+    #[allow(clippy::manual_hash_one)]
+    fn shard(&self, bytes: &[u8]) -> MutexGuard<'_, HashSet<Entry>> {
+        let mut h = self.hasher.build_hasher();
+        bytes.hash(&mut h);
+        let shard = &self.shards[h.finish() as usize % SHARDS];
+        // a panic while holding the lock cannot leave the set in an inconsistent state
+        shard.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the interned string equal to `s`, interning a copy of `s` if there is none.
+    ///
+    /// Fails if `s` contains an internal `\0`.
+    pub fn intern<B: AsRef<[u8]>>(&self, s: B) -> Result<ArcCStr, ArcCStrError> {
+        let bytes = s.as_ref();
+        let key = self.folding.key(bytes);
+        let mut shard = self.shard(&key);
+        if let Some(e) = shard.get(&*key) {
+            return Ok(ArcCStr::clone(&e.s));
+        }
+        let s = ArcCStr::try_from(bytes)?;
+        shard.insert(Entry::new(key, ArcCStr::clone(&s)));
+        Ok(s)
+    }
+
+    /// Returns the interned string equal to `s`, interning `s` itself if there is none.
+    ///
+    /// Unlike [`intern`](Interner::intern), this never copies the string.
+    pub fn intern_arc(&self, s: ArcCStr) -> ArcCStr {
+        let key = self.folding.key(s.to_bytes());
+        let mut shard = self.shard(&key);
+        if let Some(e) = shard.get(&*key) {
+            return ArcCStr::clone(&e.s);
+        }
+        shard.insert(Entry::new(key, ArcCStr::clone(&s)));
+        s
+    }
+
+    /// Returns the interned string equal to `s`, if any, without interning it.
+    pub fn get<B: AsRef<[u8]>>(&self, s: B) -> Option<ArcCStr> {
+        let key = self.folding.key(s.as_ref());
+        self.shard(&key).get(&*key).map(|e| ArcCStr::clone(&e.s))
+    }
+
+    /// Drops the interned strings that are not referenced from anywhere outside the interner.
+    ///
+    /// Returns the number of strings dropped.
+    pub fn purge(&self) -> usize {
+        let mut purged = 0;
+        for shard in self.shards.iter() {
+            let mut shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            let before = shard.len();
+            // new handles to these strings come from the interner, which is locked; a string that
+            // is only reachable through a `WeakCStr` is dropped, and just won't be shared anymore
+            shard.retain(|e| ArcCStr::strong_count(&e.s) > 1);
+            purged += before - shard.len();
+        }
+        purged
+    }
+
+    /// The number of strings in the interner.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|s| s.lock().unwrap_or_else(|e| e.into_inner()).len())
+            .sum()
+    }
+
+    /// Returns true if the interner holds no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds the interner to a process-wide registry under `name`, so that it is included in
+    /// [`Interner::report`].
+    ///
+    /// The interner stays registered until it is dropped. Registering it again adds it under
+    /// another name as well.
+    pub fn register<S: Into<String>>(&self, name: S) {
+        registry().push((name.into(), Arc::downgrade(&self.shards)));
+    }
+
+    /// Returns the name, number of strings, and size of every registered interner that is still
+    /// alive, in the order they were registered.
+    ///
+    /// This is meant to be polled periodically to feed a metrics system.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::Interner;
+    ///
+    /// let headers = Interner::new();
+    /// headers.register("headers");
+    /// headers.intern("Host").unwrap();
+    ///
+    /// let report = Interner::report();
+    /// let stats = report.iter().find(|s| s.name == "headers").unwrap();
+    /// assert_eq!(stats.entries, 1);
+    /// assert_eq!(stats.bytes, 5);
+    /// ```
+    pub fn report() -> Vec<InternerStats> {
+        let mut registry = registry();
+        registry.retain(|(_, shards)| shards.strong_count() > 0);
+        registry
+            .iter()
+            .filter_map(|(name, shards)| {
+                let shards = shards.upgrade()?;
+                let mut stats = InternerStats {
+                    name: name.clone(),
+                    entries: 0,
+                    bytes: 0,
+                };
+                for shard in shards.iter() {
+                    let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+                    stats.entries += shard.len();
+                    stats.bytes += shard
+                        .iter()
+                        .map(|e| e.s.to_bytes_with_nul().len())
+                        .sum::<usize>();
+                }
+                Some(stats)
+            })
+            .collect()
+    }
+}
+
+fn registry() -> MutexGuard<'static, Vec<(String, Weak<Shards>)>> {
+    // a panic while holding the lock cannot leave the list in an inconsistent state
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The size of one registered [`Interner`], as returned by [`Interner::report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct InternerStats {
+    /// The name the interner was registered under.
+    pub name: String,
+    /// The number of strings in the interner.
+    pub entries: usize,
+    /// The total length of the strings in the interner, including their null terminators.
+    ///
+    /// Strings that are also referenced from outside the interner are counted in full.
+    pub bytes: usize,
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Interner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interner")
+            .field("folding", &self.folding)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// An interner is written as its [`CaseFolding`] followed by the strings it holds, sorted by their
+/// bytes, in the encoding of [`serde_dedup`](crate::serde_dedup). Since the strings are distinct,
+/// that is just the table of strings, which can be shipped once and read back into an interner
+/// that hands out the same strings again.
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::{ArcCStr, Interner};
+///
+/// let interner = Interner::new();
+/// let get = interner.intern("GET").unwrap();
+/// interner.intern("POST").unwrap();
+///
+/// let json = serde_json::to_string(&interner).unwrap();
+/// assert_eq!(json, r#"["None",[["GET","POST"],null]]"#);
+///
+/// let back: Interner = serde_json::from_str(&json).unwrap();
+/// assert_eq!(back.len(), 2);
+/// let get = back.intern_arc(get);
+/// assert!(ArcCStr::ptr_eq(&get, &back.get("GET").unwrap()));
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Interner {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        struct Table(Vec<ArcCStr>);
+
+        impl serde::Serialize for Table {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                crate::serde_dedup::serialize(&self.0, serializer)
+            }
+        }
+
+        let mut strings = Vec::new();
+        for shard in self.shards.iter() {
+            let shard = shard.lock().unwrap_or_else(|e| e.into_inner());
+            strings.extend(shard.iter().map(|e| ArcCStr::clone(&e.s)));
+        }
+        // the shards depend on the hasher, so sort to write the same table every time
+        strings.sort_unstable_by(|a, b| a.to_bytes().cmp(b.to_bytes()));
+        (self.folding, Table(strings)).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Interner {
+    fn deserialize<D>(deserializer: D) -> Result<Interner, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Table(Vec<ArcCStr>);
+
+        impl<'de> serde::Deserialize<'de> for Table {
+            fn deserialize<D>(deserializer: D) -> Result<Table, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                crate::serde_dedup::deserialize(deserializer).map(Table)
+            }
+        }
+
+        let (folding, Table(strings)) = <(CaseFolding, Table)>::deserialize(deserializer)?;
+        let interner = Interner::with_case_folding(folding);
+        for s in strings {
+            interner.intern_arc(s);
+        }
+        Ok(interner)
+    }
+}
+
+#[cfg(feature = "serde")]
+const CASE_FOLDINGS: &[&str] = &["None", "Ascii", "Unicode"];
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for CaseFolding {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let index = match *self {
+            CaseFolding::None => 0,
+            CaseFolding::Ascii => 1,
+            CaseFolding::Unicode => 2,
+        };
+        serializer.serialize_unit_variant("CaseFolding", index, CASE_FOLDINGS[index as usize])
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for CaseFolding {
+    fn deserialize<D>(deserializer: D) -> Result<CaseFolding, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::{self, VariantAccess as _};
+
+        struct FoldingVisitor;
+
+        impl<'de> de::Visitor<'de> for FoldingVisitor {
+            type Value = CaseFolding;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("a case folding")
+            }
+
+            // the variant is numbered in formats that are not self-describing
+            fn visit_u64<E>(self, v: u64) -> Result<CaseFolding, E>
+            where
+                E: de::Error,
+            {
+                match v {
+                    0 => Ok(CaseFolding::None),
+                    1 => Ok(CaseFolding::Ascii),
+                    2 => Ok(CaseFolding::Unicode),
+                    _ => Err(E::invalid_value(de::Unexpected::Unsigned(v), &"0, 1, or 2")),
+                }
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<CaseFolding, E>
+            where
+                E: de::Error,
+            {
+                match v {
+                    "None" => Ok(CaseFolding::None),
+                    "Ascii" => Ok(CaseFolding::Ascii),
+                    "Unicode" => Ok(CaseFolding::Unicode),
+                    _ => Err(E::unknown_variant(v, CASE_FOLDINGS)),
+                }
+            }
+
+            fn visit_enum<A>(self, data: A) -> Result<CaseFolding, A::Error>
+            where
+                A: de::EnumAccess<'de>,
+            {
+                let (Variant(folding), variant) = data.variant()?;
+                variant.unit_variant()?;
+                Ok(folding)
+            }
+        }
+
+        struct Variant(CaseFolding);
+
+        impl<'de> serde::Deserialize<'de> for Variant {
+            fn deserialize<D>(deserializer: D) -> Result<Variant, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                deserializer
+                    .deserialize_identifier(FoldingVisitor)
+                    .map(Variant)
+            }
+        }
+
+        deserializer.deserialize_enum("CaseFolding", CASE_FOLDINGS, FoldingVisitor)
+    }
+}
+
+fn global() -> &'static Interner {
+    static INIT: Once = Once::new();
+    static mut GLOBAL: Option<Interner> = None;
+    INIT.call_once(|| unsafe { GLOBAL = Some(Interner::new()) });
+    // GLOBAL is only written once, inside call_once, which also synchronizes with this read
+    unsafe {
+        (*std::ptr::addr_of!(GLOBAL))
+            .as_ref()
+            .expect("initialized above")
+    }
+}
+
+impl ArcCStr {
+    /// Interns `s` in a process-wide [`Interner`].
+    ///
+    /// All equal strings interned this way share one allocation, so they can be compared with
+    /// [`ArcCStr::ptr_eq`]. The global interner is only created once this is first called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let a = ArcCStr::intern("foo").unwrap();
+    /// let b = ArcCStr::intern(b"foo").unwrap();
+    /// assert!(ArcCStr::ptr_eq(&a, &b));
+    /// ```
+    pub fn intern<B: AsRef<[u8]>>(s: B) -> Result<ArcCStr, ArcCStrError> {
+        global().intern(s)
+    }
+
+    /// Drops the strings in the process-wide interner that are no longer used anywhere else.
+    ///
+    /// See [`Interner::purge`].
+    pub fn purge_interned() -> usize {
+        global().purge()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CaseFolding, Interner};
+    use crate::{ArcCStr, ArcCStrError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn shares() {
+        let interner = Interner::new();
+        let a = interner.intern("a").unwrap();
+        let b = interner.intern_arc(ArcCStr::try_from("a").unwrap());
+        assert!(ArcCStr::ptr_eq(&a, &b));
+        assert!(ArcCStr::ptr_eq(&a, &interner.get("a").unwrap()));
+        assert!(interner.get("b").is_none());
+        assert_eq!(
+            interner.intern("a\0"),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+        assert_eq!(interner.len(), 1);
+    }
+
+    #[test]
+    fn purge() {
+        let interner = Interner::new();
+        let keep = interner.intern("keep").unwrap();
+        interner.intern("drop").unwrap();
+        assert_eq!(interner.purge(), 1);
+        assert_eq!(interner.len(), 1);
+        assert!(ArcCStr::ptr_eq(&keep, &interner.intern("keep").unwrap()));
+    }
+
+    #[test]
+    fn case_folding() {
+        let ascii = Interner::with_case_folding(CaseFolding::Ascii);
+        let first = ascii.intern("SELECT").unwrap();
+        assert!(ArcCStr::ptr_eq(&first, &ascii.intern("select").unwrap()));
+        assert!(ArcCStr::ptr_eq(&first, &ascii.get("SeLeCt").unwrap()));
+        // only ASCII letters are folded
+        let e = ascii.intern("É").unwrap();
+        assert!(!ArcCStr::ptr_eq(&e, &ascii.intern("é").unwrap()));
+        assert_eq!(ascii.len(), 3);
+
+        let unicode = Interner::with_case_folding(CaseFolding::Unicode);
+        let e = unicode.intern("Éa").unwrap();
+        assert!(ArcCStr::ptr_eq(&e, &unicode.intern("éA").unwrap()));
+        assert_eq!(e.to_str(), Ok("Éa"));
+        // strings that are not UTF-8 are not folded
+        let bytes = unicode.intern(b"A\xff").unwrap();
+        assert!(unicode.get(b"a\xff").is_none());
+        assert!(ArcCStr::ptr_eq(&bytes, &unicode.get(b"A\xff").unwrap()));
+
+        let exact = Interner::new();
+        exact.intern("a").unwrap();
+        assert!(exact.get("A").is_none());
+    }
+
+    #[test]
+    fn report() {
+        let stats = |name: &str| Interner::report().into_iter().find(|s| s.name == name);
+        let interner = Interner::new();
+        assert_eq!(stats("test::report"), None);
+        interner.register("test::report");
+        interner.intern("a").unwrap();
+        interner.intern("bc").unwrap();
+        // moving the interner keeps it registered
+        let moved = interner;
+        let s = stats("test::report").unwrap();
+        assert_eq!((s.entries, s.bytes), (2, 5));
+        drop(moved);
+        assert_eq!(stats("test::report"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip() {
+        let interner = Interner::with_case_folding(CaseFolding::Ascii);
+        for s in ["b", "Host", "a", "HOST"] {
+            interner.intern(s).unwrap();
+        }
+        let json = serde_json::to_string(&interner).unwrap();
+        assert_eq!(json, r#"["Ascii",[["Host","a","b"],null]]"#);
+
+        let back: Interner = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.len(), 3);
+        let host = back.get("host").unwrap();
+        assert_eq!(host, "Host");
+        assert!(ArcCStr::ptr_eq(&host, &back.intern("HoSt").unwrap()));
+        assert_eq!(serde_json::to_string(&back).unwrap(), json);
+
+        serde_test::assert_tokens(
+            &CaseFolding::Unicode,
+            &[serde_test::Token::UnitVariant {
+                name: "CaseFolding",
+                variant: "Unicode",
+            }],
+        );
+        let err = serde_json::from_str::<Interner>(r#"["Title",[[],null]]"#).unwrap_err();
+        assert!(err.to_string().contains("unknown variant `Title`"));
+    }
+}
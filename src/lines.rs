@@ -0,0 +1,183 @@
+use crate::{ArcCStr, ArcCStrError};
+use std::convert::TryFrom;
+use std::io::{self, BufRead};
+
+impl ArcCStr {
+    /// Splits `input` on `terminator`, yielding each piece as a new `ArcCStr`.
+    ///
+    /// Like [`str::split_terminator`], a trailing empty piece is skipped. Pieces that contain a
+    /// `\0` yield an error, and splitting continues after them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let fields: Vec<_> = ArcCStr::split_terminator_owned(b"a;b;;c;", b';')
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(fields.len(), 4);
+    /// assert_eq!(fields[2], b"");
+    /// assert_eq!(fields[3], b"c");
+    /// ```
+    pub fn split_terminator_owned(input: &[u8], terminator: u8) -> SplitTerminatorOwned<'_> {
+        SplitTerminatorOwned {
+            rest: input,
+            terminator,
+        }
+    }
+
+    /// Reads lines from `reader`, yielding each line as a new `ArcCStr`.
+    ///
+    /// Like [`BufRead::lines`], lines are terminated by `\n` or `\r\n`, and the terminator is not
+    /// included. Unlike it, lines are not required to be UTF-8, and each line is copied once,
+    /// straight from the reader's buffer, unless it straddles two fills of that buffer. Lines that
+    /// contain a `\0` yield an error of kind [`io::ErrorKind::InvalidData`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let log: &[u8] = b"GET /\r\nPOST /login\n";
+    /// let lines: Vec<_> = ArcCStr::lines_owned(log).collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(lines.len(), 2);
+    /// assert_eq!(lines[0], b"GET /");
+    /// assert_eq!(lines[1], b"POST /login");
+    /// ```
+    pub fn lines_owned<R: BufRead>(reader: R) -> LinesOwned<R> {
+        LinesOwned {
+            reader,
+            partial: Vec::new(),
+        }
+    }
+}
+
+/// An iterator over the pieces of a byte slice, as `ArcCStr`s.
+///
+/// This is created by [`ArcCStr::split_terminator_owned`].
+#[derive(Debug, Clone)]
+pub struct SplitTerminatorOwned<'a> {
+    rest: &'a [u8],
+    terminator: u8,
+}
+
+impl<'a> Iterator for SplitTerminatorOwned<'a> {
+    type Item = Result<ArcCStr, ArcCStrError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let piece = match self.rest.iter().position(|&b| b == self.terminator) {
+            Some(i) => {
+                let piece = &self.rest[..i];
+                self.rest = &self.rest[i + 1..];
+                piece
+            }
+            None => std::mem::take(&mut self.rest),
+        };
+        Some(ArcCStr::try_from(piece))
+    }
+}
+
+/// An iterator over the lines of a reader, as `ArcCStr`s.
+///
+/// This is created by [`ArcCStr::lines_owned`].
+#[derive(Debug)]
+pub struct LinesOwned<R> {
+    reader: R,
+    // the start of a line that straddles the end of the reader's buffer
+    partial: Vec<u8>,
+}
+
+fn line(mut bytes: &[u8]) -> io::Result<ArcCStr> {
+    if bytes.last() == Some(&b'\r') {
+        bytes = &bytes[..bytes.len() - 1];
+    }
+    ArcCStr::try_from(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+impl<R: BufRead> Iterator for LinesOwned<R> {
+    type Item = io::Result<ArcCStr>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            };
+            if buf.is_empty() {
+                // end of input; the last line need not be terminated
+                if self.partial.is_empty() {
+                    return None;
+                }
+                let s = line(&self.partial);
+                self.partial.clear();
+                return Some(s);
+            }
+            match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => {
+                    let s = if self.partial.is_empty() {
+                        line(&buf[..i])
+                    } else {
+                        self.partial.extend_from_slice(&buf[..i]);
+                        let s = line(&self.partial);
+                        self.partial.clear();
+                        s
+                    };
+                    self.reader.consume(i + 1);
+                    return Some(s);
+                }
+                None => {
+                    self.partial.extend_from_slice(buf);
+                    let n = buf.len();
+                    self.reader.consume(n);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCStr, ArcCStrError};
+    use std::io::BufReader;
+
+    #[test]
+    fn split() {
+        let pieces: Vec<_> = ArcCStr::split_terminator_owned(b"a\0b,c,", b',').collect();
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0], Err(ArcCStrError::InteriorNul { pos: 1 }));
+        assert_eq!(pieces[1].as_ref().unwrap(), b"c");
+        assert_eq!(ArcCStr::split_terminator_owned(b"", b',').count(), 0);
+    }
+
+    #[test]
+    fn lines_straddle_buffer() {
+        let input: &[u8] = b"first line\r\nsecond\n\nlast, unterminated";
+        for cap in 1..=input.len() {
+            let lines: Vec<_> = ArcCStr::lines_owned(BufReader::with_capacity(cap, input))
+                .map(|l| l.unwrap().to_bytes().to_vec())
+                .collect();
+            assert_eq!(
+                lines,
+                [&b"first line"[..], b"second", b"", b"last, unterminated"],
+                "capacity {}",
+                cap
+            );
+        }
+    }
+
+    #[test]
+    fn lines_reject_nul() {
+        let mut lines = ArcCStr::lines_owned(&b"a\0\nb\n"[..]);
+        assert_eq!(
+            lines.next().unwrap().unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+        assert_eq!(lines.next().unwrap().unwrap(), b"b");
+        assert!(lines.next().is_none());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::raw::{Header, RawArcCStr};
+use crate::ArcCStr;
+use core::ffi::CStr;
+use core::fmt;
+
+/// A string in static memory that an [`ArcCStr`] can point to without allocating.
+///
+/// An `ArcCStr` expects its reference counts to sit right in front of the string bytes, which a
+/// plain `&'static CStr` does not have. A `StaticCStr` lays out a string literal the same way an
+/// `ArcCStr` allocation is laid out, but with counts that are marked as static, so that cloning
+/// and dropping the `ArcCStr`s that point to it do not touch them at all, and it is never freed.
+///
+/// `N` is the length of the string plus one for the null terminator. The [`literal!`] macro
+/// works that out, declares the `static`, and returns an `ArcCStr` pointing to it, which is
+/// usually all you need.
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::{ArcCStr, StaticCStr};
+///
+/// static GREETING: StaticCStr<6> = StaticCStr::new("hello");
+///
+/// let s = ArcCStr::from_static(&GREETING);
+/// assert_eq!(s.to_bytes(), b"hello");
+/// ```
+#[repr(C)]
+pub struct StaticCStr<const N: usize> {
+    header: Header,
+    // directly follows the header, just like in an allocation, since a header is a whole number
+    // of words and bytes need no alignment
+    bytes: [u8; N],
+}
+
+impl<const N: usize> StaticCStr<N> {
+    /// Lays out `s` like an `ArcCStr` allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is not `s.len() + 1`, or if `s` contains a `\0`. In a `static`, this is a
+    /// compile-time error.
+    pub const fn new(s: &str) -> Self {
+        let bytes = s.as_bytes();
+        assert!(bytes.len() + 1 == N, "N must be the string length plus one");
+        let mut buf = [0; N];
+        let mut i = 0;
+        while i < bytes.len() {
+            assert!(bytes[i] != 0, "string contains an internal \\0");
+            buf[i] = bytes[i];
+            i += 1;
+        }
+        StaticCStr {
+            header: Header::new_static(s),
+            bytes: buf,
+        }
+    }
+}
+
+impl<const N: usize> fmt::Debug for StaticCStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // new checked that the bytes are a C string
+        let s = unsafe { CStr::from_bytes_with_nul_unchecked(&self.bytes) };
+        fmt::Debug::fmt(s, f)
+    }
+}
+
+/// The string that every empty `ArcCStr` from [`ArcCStr::empty`] points to.
+static EMPTY: StaticCStr<1> = StaticCStr::new("");
+
+impl ArcCStr {
+    /// Returns an empty string, without allocating.
+    ///
+    /// Every call returns a pointer to the same static string, like [`literal!`] does, so this
+    /// is free, and so is cloning and dropping the result. [`Default`] returns the same string,
+    /// which makes `#[derive(Default)]` free for structs that hold `ArcCStr`s.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use arccstr::ArcCStr;
+    ///
+    /// let s = ArcCStr::empty();
+    /// assert!(s.to_bytes().is_empty());
+    /// assert!(ArcCStr::ptr_eq(&s, &ArcCStr::default()));
+    /// ```
+    #[inline]
+    pub fn empty() -> Self {
+        ArcCStr::from_static(&EMPTY)
+    }
+
+    /// Constructs an `ArcCStr` that points to a string in static memory.
+    ///
+    /// This does not allocate, and cloning or dropping the returned `ArcCStr`, or any clone of
+    /// it, does not touch a reference count. The string reports a very large strong count.
+    ///
+    /// See also the [`literal!`] macro, which declares the `StaticCStr` for you.
+    pub const fn from_static<const N: usize>(s: &'static StaticCStr<N>) -> Self {
+        // a static string is live forever, and the references to it are not counted
+        unsafe { ArcCStr::from_raw_arc(RawArcCStr::from_static(&s.header)) }
+    }
+}
+
+impl Default for ArcCStr {
+    /// Returns an empty string, without allocating. See [`ArcCStr::empty`].
+    #[inline]
+    fn default() -> Self {
+        ArcCStr::empty()
+    }
+}
+
+/// Constructs an [`ArcCStr`] from a string literal, without allocating.
+///
+/// The string is laid out in static memory as a [`StaticCStr`], and the returned `ArcCStr` is
+/// made with [`ArcCStr::from_static`], so cloning and dropping it is free. A literal that
+/// contains a `\0` fails to compile.
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::ArcCStr;
+///
+/// let s = arccstr::literal!("Content-Type");
+/// assert_eq!(s.to_str(), Ok("Content-Type"));
+///
+/// // clones share the static string
+/// assert!(ArcCStr::ptr_eq(&s, &s.clone()));
+/// ```
+#[macro_export]
+macro_rules! literal {
+    ($s:expr) => {{
+        const S: &str = $s;
+        static STORAGE: $crate::StaticCStr<{ S.len() + 1 }> = $crate::StaticCStr::new(S);
+        $crate::ArcCStr::from_static(&STORAGE)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ArcCStr, StaticCStr, WeakCStr};
+    use std::convert::TryFrom;
+
+    static SHARED: ArcCStr = crate::literal!("shared");
+
+    #[test]
+    fn never_counted() {
+        let s = crate::literal!("héllo");
+        assert_eq!(s.to_bytes_with_nul(), "héllo\0".as_bytes());
+        assert_eq!(s.as_str(), Some("héllo"));
+        let count = ArcCStr::strong_count(&s);
+        let clones = vec![s.clone(); 10];
+        assert_eq!(ArcCStr::strong_count(&s), count);
+        drop(clones);
+        assert_eq!(ArcCStr::strong_count(&s), count);
+
+        let weak = ArcCStr::downgrade(&s);
+        drop(s);
+        let s = WeakCStr::upgrade(&weak).unwrap();
+        assert_eq!(s.to_str(), Ok("héllo"));
+
+        assert!(ArcCStr::from_static(&super::EMPTY).to_bytes().is_empty());
+        assert_eq!(format!("{:?}", super::EMPTY), "\"\"");
+        assert!(ArcCStr::ptr_eq(&SHARED.clone(), &SHARED));
+    }
+
+    #[test]
+    fn empty_and_default() {
+        #[derive(Default)]
+        struct Row {
+            name: ArcCStr,
+            value: ArcCStr,
+        }
+        let row = Row::default();
+        assert!(row.name.to_bytes().is_empty());
+        assert!(ArcCStr::ptr_eq(&row.name, &row.value));
+        assert!(ArcCStr::ptr_eq(&row.name, &ArcCStr::empty()));
+        assert_eq!(ArcCStr::empty(), ArcCStr::try_from("").unwrap());
+    }
+
+    #[test]
+    fn never_mutable() {
+        let mut s = crate::literal!("abc");
+        assert!(ArcCStr::get_mut(&mut s).is_none());
+        assert!(ArcCStr::try_unwrap(s).is_err());
+    }
+
+    #[test]
+    fn image_is_not_static() {
+        let s = crate::literal!("abc");
+        let copy = unsafe { ArcCStr::from_allocation(s.to_allocation()) };
+        assert_eq!(copy, s);
+        assert_eq!(ArcCStr::strong_count(&copy), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_length() {
+        StaticCStr::<3>::new("abc");
+    }
+}
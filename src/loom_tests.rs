@@ -0,0 +1,110 @@
+//! Model checks of the reference counts with [loom](https://github.com/tokio-rs/loom).
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --release --lib loom_tests`. Under `cfg(loom)`,
+//! the atomics in the header (and in [`ArcCStrPool`](crate::ArcCStrPool) slabs) are loom's, so
+//! loom runs each test below under every interleaving, and every outcome of every atomic load,
+//! that the memory orderings allow. Loom does not see the string bytes or the allocator, so it
+//! complements the sanitizer and Miri runs rather than replacing them. Static strings are not
+//! available under loom, since loom's atomics cannot be created in a `static`.
+
+use crate::{ArcCStr, AtomicArcCStr, WeakCStr};
+use loom::sync::Arc;
+use loom::thread;
+use std::convert::TryFrom;
+
+#[test]
+fn clone_and_drop() {
+    loom::model(|| {
+        let s = ArcCStr::try_from("abc").unwrap();
+        let t = {
+            let s = ArcCStr::clone(&s);
+            thread::spawn(move || {
+                let c = ArcCStr::clone(&s);
+                assert_eq!(c.to_bytes(), b"abc");
+            })
+        };
+        let c = ArcCStr::clone(&s);
+        drop(s);
+        assert_eq!(c.to_bytes(), b"abc");
+        t.join().unwrap();
+        assert_eq!(ArcCStr::strong_count(&c), 1);
+    });
+}
+
+#[test]
+fn upgrade_races_last_drop() {
+    loom::model(|| {
+        let s = ArcCStr::try_from("abc").unwrap();
+        let weak = ArcCStr::downgrade(&s);
+        let t = thread::spawn(move || {
+            if let Some(s) = WeakCStr::upgrade(&weak) {
+                assert_eq!(s.to_bytes(), b"abc");
+            }
+        });
+        drop(s);
+        t.join().unwrap();
+    });
+}
+
+#[test]
+fn get_mut_races_weak() {
+    loom::model(|| {
+        let mut s = ArcCStr::try_from("abc").unwrap();
+        let weak = ArcCStr::downgrade(&s);
+        let t = thread::spawn(move || {
+            // either we see the string before it was changed, or not at all
+            if let Some(s) = WeakCStr::upgrade(&weak) {
+                assert!(s.to_bytes() == b"abc" || s.to_bytes() == b"ABC");
+            }
+        });
+        if let Some(mut m) = ArcCStr::get_mut(&mut s) {
+            m.make_ascii_uppercase();
+        }
+        t.join().unwrap();
+    });
+}
+
+#[test]
+fn try_unwrap_races_drop() {
+    loom::model(|| {
+        let s = ArcCStr::try_from("abc").unwrap();
+        let c = ArcCStr::clone(&s);
+        let t = thread::spawn(move || ArcCStr::try_unwrap(c).ok());
+        let mine = ArcCStr::try_unwrap(s).ok();
+        let theirs = t.join().unwrap();
+        // both may fail, but never both succeed
+        assert!(mine.is_none() || theirs.is_none());
+    });
+}
+
+#[test]
+fn pooled_strings_free_their_slab() {
+    loom::model(|| {
+        let mut pool = crate::ArcCStrPool::new();
+        let a = pool.alloc("a").unwrap();
+        let b = pool.alloc("b").unwrap();
+        drop(pool);
+        let t = thread::spawn(move || drop(a));
+        drop(b);
+        t.join().unwrap();
+    });
+}
+
+#[test]
+fn atomic_load_races_store() {
+    loom::model(|| {
+        let a = ArcCStr::try_from("a").unwrap();
+        let slot = Arc::new(AtomicArcCStr::new(ArcCStr::clone(&a)));
+        let t = {
+            let slot = Arc::clone(&slot);
+            thread::spawn(move || {
+                let s = slot.load();
+                assert!(s.to_bytes() == b"a" || s.to_bytes() == b"b");
+            })
+        };
+        slot.store(ArcCStr::try_from("b").unwrap());
+        drop(a);
+        t.join().unwrap();
+        assert_eq!(slot.load().to_bytes(), b"b");
+    });
+}
@@ -0,0 +1,234 @@
+use crate::{ArcCStr, AtomicArcCStr};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Mutex;
+
+/// A fixed set of named strings whose values can be replaced all at once.
+///
+/// `NamedStrings` is meant for configuration values that many threads read, and that are
+/// occasionally reloaded as a whole. Each name is given a slot when the `NamedStrings` is
+/// created, and each slot is an [`AtomicArcCStr`], so [`get`](NamedStrings::get) never takes a
+/// lock or waits for a reload. [`reload`](NamedStrings::reload) swaps in new values for any
+/// number of names, and [`snapshot`](NamedStrings::snapshot) sees either all of them or none of
+/// them, never a mix.
+///
+/// To make that possible without readers waiting for a reload to finish, there are two slots for
+/// each name: one set that readers use, and one that the next reload fills in before making it
+/// the set that readers use. A snapshot only has to start over if two reloads begin while it is
+/// reading the slots.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, NamedStrings};
+///
+/// let mut defaults = HashMap::new();
+/// defaults.insert("greeting", ArcCStr::try_from("hello").unwrap());
+/// defaults.insert("farewell", ArcCStr::try_from("bye").unwrap());
+/// let config = NamedStrings::new(defaults);
+/// assert_eq!(config.get("greeting").unwrap(), "hello");
+/// assert_eq!(config.get("missing"), None);
+///
+/// let mut next = HashMap::new();
+/// next.insert("greeting", ArcCStr::try_from("hi").unwrap());
+/// config.reload(next).unwrap();
+///
+/// let current = config.snapshot();
+/// assert_eq!(current["greeting"], "hi");
+/// // names that were not reloaded keep their values
+/// assert_eq!(current["farewell"], "bye");
+/// ```
+pub struct NamedStrings {
+    // two slots per name; readers use `slots[done % 2]`, and a reload fills in the other set
+    slots: [HashMap<&'static str, AtomicArcCStr>; 2],
+    // the number of reloads that have started, and that have finished
+    started: AtomicUsize,
+    done: AtomicUsize,
+    reloading: Mutex<()>,
+}
+
+impl NamedStrings {
+    /// Constructs a `NamedStrings` with a slot for each of the names in `strings`, holding its
+    /// initial value.
+    ///
+    /// The set of names cannot be changed later.
+    pub fn new(strings: HashMap<&'static str, ArcCStr>) -> Self {
+        let slots = || {
+            strings
+                .iter()
+                .map(|(&name, s)| (name, AtomicArcCStr::new(ArcCStr::clone(s))))
+                .collect()
+        };
+        NamedStrings {
+            slots: [slots(), slots()],
+            started: AtomicUsize::new(0),
+            done: AtomicUsize::new(0),
+            reloading: Mutex::new(()),
+        }
+    }
+
+    /// Returns the current string named `name`, or `None` if there is no such name.
+    pub fn get(&self, name: &str) -> Option<ArcCStr> {
+        // a reload may be filling in these slots again if two reloads have finished since we
+        // read `done`, but then each slot holds one value or the other, and both are recent
+        let current = &self.slots[self.done.load(SeqCst) % 2];
+        current.get(name).map(AtomicArcCStr::load)
+    }
+
+    /// Returns the current value of every name.
+    ///
+    /// The values all come from the same reload.
+    pub fn snapshot(&self) -> HashMap<&'static str, ArcCStr> {
+        loop {
+            let done = self.done.load(SeqCst);
+            let snapshot = self.slots[done % 2]
+                .iter()
+                .map(|(&name, slot)| (name, slot.load()))
+                .collect();
+            // the next reload fills in the other set of slots, but the one after that reuses the
+            // slots we just read, and may have been half-way through them
+            if self.started.load(SeqCst) <= done + 1 {
+                return snapshot;
+            }
+        }
+    }
+
+    /// Returns the names that have slots, in arbitrary order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.slots[0].keys().copied()
+    }
+
+    /// Replaces the values of all the names in `strings` at once.
+    ///
+    /// Names that are not in `strings` keep their values. If `strings` holds a name that has no
+    /// slot, nothing is replaced, and that name is returned as an error.
+    pub fn reload(&self, strings: HashMap<&'static str, ArcCStr>) -> Result<(), UnknownName> {
+        if let Some(&name) = strings
+            .keys()
+            .find(|&&name| !self.slots[0].contains_key(name))
+        {
+            return Err(UnknownName { name });
+        }
+
+        let _reloading = self.reloading.lock().unwrap_or_else(|e| e.into_inner());
+        let done = self.done.load(SeqCst);
+        // readers of the current slots that have not finished by now will have to start over if
+        // they are still reading when the reload after this one starts
+        self.started.store(done + 1, SeqCst);
+        let (current, next) = (&self.slots[done % 2], &self.slots[(done + 1) % 2]);
+        for (&name, slot) in next {
+            let value = match strings.get(name) {
+                Some(s) => ArcCStr::clone(s),
+                None => current[name].load(),
+            };
+            slot.store(value);
+        }
+        self.done.store(done + 1, SeqCst);
+        Ok(())
+    }
+
+    /// Replaces the value of the single name `name`.
+    ///
+    /// This is a [`reload`](NamedStrings::reload) of just that name, and fails the same way if
+    /// there is no slot for it.
+    pub fn set(&self, name: &'static str, value: ArcCStr) -> Result<(), UnknownName> {
+        let mut strings = HashMap::with_capacity(1);
+        strings.insert(name, value);
+        self.reload(strings)
+    }
+}
+
+impl fmt::Debug for NamedStrings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.snapshot().iter()).finish()
+    }
+}
+
+/// The error returned when reloading a [`NamedStrings`] with a name it has no slot for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnknownName {
+    name: &'static str,
+}
+
+impl UnknownName {
+    /// Returns the name that has no slot.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl fmt::Display for UnknownName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "there is no string named {:?}", self.name)
+    }
+}
+
+impl std::error::Error for UnknownName {}
+
+#[cfg(test)]
+mod tests {
+    use super::{NamedStrings, UnknownName};
+    use crate::ArcCStr;
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+    use std::thread;
+
+    fn strings(pairs: &[(&'static str, &str)]) -> HashMap<&'static str, ArcCStr> {
+        pairs
+            .iter()
+            .map(|&(name, s)| (name, ArcCStr::try_from(s).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn reload_replaces_named_values() {
+        let named = NamedStrings::new(strings(&[("a", "1"), ("b", "2")]));
+        let before = named.snapshot();
+
+        named.reload(strings(&[("b", "3")])).unwrap();
+        assert_eq!(named.get("a").unwrap(), "1");
+        assert_eq!(named.get("b").unwrap(), "3");
+        named.set("a", ArcCStr::try_from("4").unwrap()).unwrap();
+        assert_eq!(named.snapshot(), strings(&[("a", "4"), ("b", "3")]));
+        // old snapshots are unaffected
+        assert_eq!(before, strings(&[("a", "1"), ("b", "2")]));
+
+        assert_eq!(
+            named.reload(strings(&[("a", "5"), ("c", "6")])),
+            Err(UnknownName { name: "c" })
+        );
+        assert_eq!(named.get("a").unwrap(), "4");
+        assert_eq!(named.get("c"), None);
+        let mut names: Vec<_> = named.names().collect();
+        names.sort_unstable();
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[test]
+    fn snapshots_never_mix_reloads() {
+        let named = Arc::new(NamedStrings::new(strings(&[("x", "0"), ("y", "0")])));
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let named = Arc::clone(&named);
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        let snapshot = named.snapshot();
+                        assert_eq!(snapshot["x"], snapshot["y"]);
+                    }
+                })
+            })
+            .collect();
+        for i in 1..1000 {
+            let i = i.to_string();
+            named.reload(strings(&[("x", &i), ("y", &i)])).unwrap();
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
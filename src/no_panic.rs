@@ -0,0 +1,62 @@
+//! Checks that the hot paths cannot panic, for the `no-panic` feature.
+//!
+//! Each check wraps an operation in a guard whose destructor calls a function that does not
+//! exist. The destructor only runs if the operation unwinds, so the test binary only links if
+//! the optimizer has removed every path by which the operation could panic. That makes these
+//! checks only meaningful in optimized builds; run them with
+//! `cargo test --release --features no-panic`.
+
+use crate::ArcCStr;
+use std::convert::TryFrom;
+use std::hint::black_box;
+
+/// Runs `f`, failing to link unless it provably cannot panic.
+#[inline(always)]
+fn assert_no_panic<R>(f: impl FnOnce() -> R) -> R {
+    struct Guard;
+    impl Drop for Guard {
+        #[inline(always)]
+        fn drop(&mut self) {
+            extern "C" {
+                #[link_name = "\n\narccstr: an operation checked by the no-panic feature may panic\n\n"]
+                fn may_panic() -> !;
+            }
+            unsafe { may_panic() }
+        }
+    }
+    let guard = Guard;
+    let r = f();
+    core::mem::forget(guard);
+    r
+}
+
+fn strings() -> (ArcCStr, ArcCStr) {
+    (
+        black_box(ArcCStr::try_from("foo").unwrap()),
+        black_box(ArcCStr::try_from("bar").unwrap()),
+    )
+}
+
+#[test]
+fn clone_and_drop() {
+    let (a, _) = strings();
+    let b = assert_no_panic(|| ArcCStr::clone(&a));
+    assert_no_panic(move || drop(black_box(b)));
+    assert_no_panic(move || drop(black_box(a)));
+}
+
+#[test]
+fn deref() {
+    let (a, _) = strings();
+    let len = assert_no_panic(|| black_box(&*a).to_bytes().len() + a.len());
+    assert_eq!(len, 6);
+}
+
+#[test]
+fn compare() {
+    let (a, b) = strings();
+    assert!(!assert_no_panic(|| a == b));
+    assert!(assert_no_panic(|| a > b));
+    assert!(assert_no_panic(|| a == "foo"));
+    assert!(assert_no_panic(|| a.partial_cmp(&b)).is_some());
+}
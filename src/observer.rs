@@ -0,0 +1,135 @@
+//! A global hook for observing string allocations.
+
+use crate::ArcCStr;
+use std::error::Error;
+use std::fmt;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Receives notifications about the lifecycle of every [`ArcCStr`] in the process.
+///
+/// This is meant for profiling and monitoring tools that want to sample how strings are
+/// allocated and shared. Install an observer with [`set_alloc_observer`]. Each method is given a
+/// pointer to the string's first byte (which identifies its allocation, as in
+/// [`CStr::as_ptr`](std::ffi::CStr::as_ptr)) and its length in bytes, excluding the null
+/// terminator.
+///
+/// The methods are called on the thread performing the operation, and on every clone and drop,
+/// so they should be cheap. All of them do nothing by default.
+///
+/// Only available with the `alloc-observer` feature.
+pub trait AllocObserver: Send + Sync {
+    /// Called when a new string is allocated.
+    fn on_construct(&self, ptr: *const c_char, len: usize) {
+        let _ = (ptr, len);
+    }
+
+    /// Called when an `ArcCStr` is cloned, or a [`WeakCStr`](crate::WeakCStr) is upgraded.
+    fn on_clone(&self, ptr: *const c_char, len: usize) {
+        let _ = (ptr, len);
+    }
+
+    /// Called when an `ArcCStr` is dropped, just before its reference count is decremented.
+    fn on_drop(&self, ptr: *const c_char, len: usize) {
+        let _ = (ptr, len);
+    }
+}
+
+/// The error returned by [`set_alloc_observer`] if an observer has already been installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetAllocObserverError(());
+
+impl fmt::Display for SetAllocObserverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an allocation observer has already been set")
+    }
+}
+
+impl Error for SetAllocObserverError {}
+
+const UNINITIALIZED: usize = 0;
+const INITIALIZING: usize = 1;
+const INITIALIZED: usize = 2;
+
+static STATE: AtomicUsize = AtomicUsize::new(UNINITIALIZED);
+static mut OBSERVER: Option<&'static dyn AllocObserver> = None;
+
+/// Installs the global [`AllocObserver`].
+///
+/// This can only be done once in the lifetime of a process; later calls fail. Strings created
+/// before the observer was installed are still reported when they are cloned or dropped.
+///
+/// Only available with the `alloc-observer` feature.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use std::os::raw::c_char;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use arccstr::{AllocObserver, ArcCStr};
+///
+/// struct BytesAllocated(AtomicUsize);
+/// impl AllocObserver for BytesAllocated {
+///     fn on_construct(&self, _: *const c_char, len: usize) {
+///         self.0.fetch_add(len, Ordering::Relaxed);
+///     }
+/// }
+///
+/// static BYTES: BytesAllocated = BytesAllocated(AtomicUsize::new(0));
+/// arccstr::set_alloc_observer(&BYTES).unwrap();
+///
+/// let _foo = ArcCStr::try_from("foo").unwrap();
+/// assert_eq!(BYTES.0.load(Ordering::Relaxed), 3);
+/// assert!(arccstr::set_alloc_observer(&BYTES).is_err());
+/// ```
+pub fn set_alloc_observer(
+    observer: &'static dyn AllocObserver,
+) -> Result<(), SetAllocObserverError> {
+    match STATE.compare_exchange(
+        UNINITIALIZED,
+        INITIALIZING,
+        Ordering::Acquire,
+        Ordering::Relaxed,
+    ) {
+        Ok(_) => {
+            // we are the only thread that got to INITIALIZING, and no thread reads OBSERVER
+            // until STATE is INITIALIZED
+            unsafe { OBSERVER = Some(observer) };
+            STATE.store(INITIALIZED, Ordering::Release);
+            Ok(())
+        }
+        Err(_) => Err(SetAllocObserverError(())),
+    }
+}
+
+#[inline]
+fn observer() -> Option<&'static dyn AllocObserver> {
+    if STATE.load(Ordering::Acquire) == INITIALIZED {
+        // OBSERVER is never written again once STATE is INITIALIZED
+        unsafe { OBSERVER }
+    } else {
+        None
+    }
+}
+
+#[inline]
+pub(crate) fn construct(s: &ArcCStr) {
+    if let Some(o) = observer() {
+        o.on_construct(s.as_ptr(), s.to_bytes().len());
+    }
+}
+
+#[inline]
+pub(crate) fn clone(s: &ArcCStr) {
+    if let Some(o) = observer() {
+        o.on_clone(s.as_ptr(), s.to_bytes().len());
+    }
+}
+
+#[inline]
+pub(crate) fn drop(s: &ArcCStr) {
+    if let Some(o) = observer() {
+        o.on_drop(s.as_ptr(), s.to_bytes().len());
+    }
+}
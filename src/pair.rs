@@ -0,0 +1,177 @@
+use crate::{ArcCStr, ArcCStrError};
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::fmt;
+
+/// A reference-counted `KEY=VALUE` string, such as an environment variable.
+///
+/// The pair is stored as a single `ArcCStr` holding `KEY=VALUE`, in the format that `putenv`, the
+/// `envp` argument of `execve`, and similar C interfaces expect. Both sides can still be accessed
+/// without copying: the key as bytes, and the value as a `CStr`, since it shares the pair's
+/// terminator.
+///
+/// The key is never empty and never contains `=`, so the pair splits at the first `=`. The value
+/// may contain further `=`s.
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::ArcCStrPair;
+///
+/// let var = ArcCStrPair::new(b"PATH", b"/usr/bin:/bin").unwrap();
+/// assert_eq!(var.key(), b"PATH");
+/// assert_eq!(var.value().to_bytes(), b"/usr/bin:/bin");
+/// assert_eq!(var.as_c_str().to_bytes(), b"PATH=/usr/bin:/bin");
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArcCStrPair {
+    inner: ArcCStr,
+    // the position of the `=` between the key and the value
+    eq: usize,
+}
+
+impl ArcCStrPair {
+    /// Constructs a new pair from a key and a value, in a single allocation.
+    ///
+    /// Fails with [`ArcCStrError::InvalidPair`] if `key` is empty or contains `=`, and with
+    /// [`ArcCStrError::InteriorNul`] if either side contains a `\0`. The position of the `\0` is
+    /// given within the whole `KEY=VALUE` string.
+    pub fn new(key: &[u8], value: &[u8]) -> Result<Self, ArcCStrError> {
+        if key.is_empty() || key.contains(&b'=') {
+            return Err(ArcCStrError::InvalidPair);
+        }
+        ArcCStrError::check_no_nul(key)?;
+        ArcCStrError::check_no_nul(value).map_err(|e| match e {
+            ArcCStrError::InteriorNul { pos } => ArcCStrError::InteriorNul {
+                pos: key.len() + 1 + pos,
+            },
+            e => e,
+        })?;
+        let inner = unsafe { ArcCStr::try_concat_unchecked(&[key, b"=", value])? };
+        Ok(ArcCStrPair {
+            inner,
+            eq: key.len(),
+        })
+    }
+
+    /// Returns the key, which is everything before the first `=`.
+    #[inline]
+    pub fn key(&self) -> &[u8] {
+        &self.inner.to_bytes()[..self.eq]
+    }
+
+    /// Returns the value, which is everything after the first `=`.
+    #[inline]
+    pub fn value(&self) -> &CStr {
+        let value = &self.inner.to_bytes_with_nul()[self.eq + 1..];
+        // a suffix of a C string that still includes its terminator is itself a C string
+        unsafe { CStr::from_bytes_with_nul_unchecked(value) }
+    }
+
+    /// Returns the whole `KEY=VALUE` string.
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        &self.inner
+    }
+
+    /// Returns the whole `KEY=VALUE` string as an [`ArcCStr`].
+    #[inline]
+    pub fn as_arc_cstr(&self) -> &ArcCStr {
+        &self.inner
+    }
+}
+
+impl TryFrom<ArcCStr> for ArcCStrPair {
+    type Error = ArcCStrError;
+
+    /// Splits a `KEY=VALUE` string at its first `=`, without copying it.
+    ///
+    /// Fails with [`ArcCStrError::InvalidPair`] if the string has no `=`, or starts with one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::{ArcCStr, ArcCStrPair};
+    ///
+    /// let s = ArcCStr::try_from("OPTS=-v=2").unwrap();
+    /// let pair = ArcCStrPair::try_from(s).unwrap();
+    /// assert_eq!(pair.key(), b"OPTS");
+    /// assert_eq!(pair.value().to_bytes(), b"-v=2");
+    /// ```
+    fn try_from(inner: ArcCStr) -> Result<Self, Self::Error> {
+        match inner.to_bytes().iter().position(|&b| b == b'=') {
+            Some(eq) if eq > 0 => Ok(ArcCStrPair { inner, eq }),
+            _ => Err(ArcCStrError::InvalidPair),
+        }
+    }
+}
+
+impl From<ArcCStrPair> for ArcCStr {
+    /// Returns the whole `KEY=VALUE` string, without copying it.
+    #[inline]
+    fn from(pair: ArcCStrPair) -> Self {
+        pair.inner
+    }
+}
+
+impl AsRef<CStr> for ArcCStrPair {
+    fn as_ref(&self) -> &CStr {
+        &self.inner
+    }
+}
+
+impl fmt::Debug for ArcCStrPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl fmt::Display for ArcCStrPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCStrPair;
+    use crate::{ArcCStr, ArcCStrError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn new() {
+        let p = ArcCStrPair::new(b"K", b"").unwrap();
+        assert_eq!(p.key(), b"K");
+        assert_eq!(p.value().to_bytes_with_nul(), b"\0");
+        assert_eq!(p.to_string(), "K=");
+
+        assert_eq!(ArcCStrPair::new(b"", b"v"), Err(ArcCStrError::InvalidPair));
+        assert_eq!(
+            ArcCStrPair::new(b"a=b", b"v"),
+            Err(ArcCStrError::InvalidPair)
+        );
+        assert_eq!(
+            ArcCStrPair::new(b"\0", b"v"),
+            Err(ArcCStrError::InteriorNul { pos: 0 })
+        );
+        assert_eq!(
+            ArcCStrPair::new(b"KEY", b"v\0"),
+            Err(ArcCStrError::InteriorNul { pos: 5 })
+        );
+    }
+
+    #[test]
+    fn split() {
+        let s = ArcCStr::try_from("A=b=c").unwrap();
+        let p = ArcCStrPair::try_from(ArcCStr::clone(&s)).unwrap();
+        assert_eq!(p, ArcCStrPair::new(b"A", b"b=c").unwrap());
+        assert!(ArcCStr::ptr_eq(p.as_arc_cstr(), &s));
+        assert_eq!(ArcCStr::from(p), s);
+
+        for bad in ["", "A", "=b"] {
+            let bad = ArcCStr::try_from(bad).unwrap();
+            assert_eq!(ArcCStrPair::try_from(bad), Err(ArcCStrError::InvalidPair));
+        }
+    }
+}
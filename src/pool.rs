@@ -0,0 +1,308 @@
+use crate::raw::{RawArcCStr, ReleaseHook};
+use crate::{ArcCStr, ArcCStrError};
+use ::alloc::alloc::{self, Layout};
+use core::convert::TryFrom;
+use core::fmt;
+use core::mem::{align_of, size_of};
+use core::ptr::{self, NonNull};
+use core::sync::atomic::Ordering;
+#[cfg(not(loom))]
+use core::sync::atomic::{self, AtomicUsize};
+#[cfg(loom)]
+use loom::sync::atomic::{self, AtomicUsize};
+
+/// The size of the slabs a pool allocates, unless set with [`ArcCStrPool::with_slab_size`].
+const DEFAULT_SLAB: usize = 64 * 1024;
+
+/// Allocates many [`ArcCStr`]s out of a few large slabs of memory.
+///
+/// Allocating each string on its own means a call into the allocator per string, which can
+/// dominate the time it takes to ingest millions of small strings. A pool instead carves strings
+/// out of slabs that it allocates in one go, so making a string is little more than a copy.
+///
+/// The strings it hands out are ordinary `ArcCStr`s, which can be cloned, sent to other threads,
+/// and dropped in any order, and which may outlive the pool. A slab is freed once the pool has
+/// moved on to the next one and every string in it has been dropped; until then, even a single
+/// live string keeps its whole slab allocated. So pools suit batches of strings that die at
+/// about the same time, rather than a few long-lived strings among many short-lived ones.
+///
+/// Strings longer than a quarter of a slab are allocated on their own, as usual. Each pooled
+/// string takes two more words of memory than one allocated on its own, which say which slab it
+/// is in.
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::ArcCStrPool;
+///
+/// let mut pool = ArcCStrPool::new();
+/// let words: Vec<_> = "the quick brown fox"
+///     .split(' ')
+///     .map(|w| pool.alloc(w).unwrap())
+///     .collect();
+/// drop(pool);
+/// assert_eq!(words[3], "fox");
+/// ```
+pub struct ArcCStrPool {
+    // the slab that strings are carved from, which the pool holds a reference to
+    slab: Option<NonNull<Slab>>,
+    // the offset of the unused part of the slab
+    next: usize,
+    slab_size: usize,
+    // strings that take up more of a slab than this are allocated on their own
+    max_pooled: usize,
+}
+
+/// The start of every slab, followed by the strings carved from it.
+#[repr(C)]
+struct Slab {
+    // one for the pool while it carves strings from the slab, plus one for each live string
+    refs: AtomicUsize,
+    size: usize,
+}
+
+impl Slab {
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, align_of::<Slab>()).expect("slab too large")
+    }
+
+    /// Gives up a reference to the slab, and frees it if that was the last one.
+    ///
+    /// # Safety
+    ///
+    /// The caller must hold a reference to the slab, and not use it afterwards.
+    unsafe fn release(slab: NonNull<Slab>) {
+        if slab.as_ref().refs.fetch_sub(1, Ordering::Release) != 1 {
+            return;
+        }
+        // see RawArcCStr::decrement_strong_count
+        atomic::fence(Ordering::Acquire);
+        let size = slab.as_ref().size;
+        alloc::dealloc(slab.as_ptr() as *mut u8, Slab::layout(size))
+    }
+}
+
+/// The release hook of a pooled string, which gives up its reference to its slab.
+unsafe extern "C" fn release_string(slab: *const (), _: NonNull<u8>, _: usize) {
+    Slab::release(NonNull::new_unchecked(slab as *mut Slab))
+}
+
+// the pool only shares its slabs through atomic reference counts
+unsafe impl Send for ArcCStrPool {}
+unsafe impl Sync for ArcCStrPool {}
+
+impl ArcCStrPool {
+    /// Constructs a pool with slabs of 64 KiB.
+    ///
+    /// Nothing is allocated until the first string is.
+    pub fn new() -> Self {
+        Self::with_slab_size(DEFAULT_SLAB)
+    }
+
+    /// Constructs a pool with slabs of `size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is too small to hold a single string, which is much less than a
+    /// kilobyte.
+    pub fn with_slab_size(size: usize) -> Self {
+        let min = size_of::<Slab>() + 4 * Self::footprint(0).expect("no overflow");
+        assert!(
+            size >= min,
+            "slab size {} is less than the minimum of {}",
+            size,
+            min
+        );
+        ArcCStrPool {
+            slab: None,
+            next: 0,
+            slab_size: size,
+            max_pooled: (size - size_of::<Slab>()) / 4,
+        }
+    }
+
+    /// Constructs a pool whose first slab fits exactly one string of each of the given lengths,
+    /// however long they are.
+    ///
+    /// Returns `None` if the slab would be too large to allocate.
+    pub(crate) fn for_lengths<I: IntoIterator<Item = usize>>(lens: I) -> Option<Self> {
+        let mut size = size_of::<Slab>();
+        for len in lens {
+            size = size.checked_add(Self::footprint(len)?)?;
+        }
+        Layout::from_size_align(size, align_of::<Slab>()).ok()?;
+        Some(ArcCStrPool {
+            slab: None,
+            next: 0,
+            slab_size: size,
+            max_pooled: usize::MAX,
+        })
+    }
+
+    /// Returns the size of the slabs the pool allocates, in bytes.
+    pub fn slab_size(&self) -> usize {
+        self.slab_size
+    }
+
+    /// Constructs an `ArcCStr` holding a copy of `s`, allocated from the pool.
+    ///
+    /// Fails with [`ArcCStrError::InteriorNul`] if `s` contains a `\0`, and with
+    /// [`ArcCStrError::Alloc`] if a new slab could not be allocated.
+    pub fn alloc<B: AsRef<[u8]>>(&mut self, s: B) -> Result<ArcCStr, ArcCStrError> {
+        let s = s.as_ref();
+        ArcCStrError::check_no_nul(s)?;
+        let size = match Self::footprint(s.len()) {
+            Some(size) if size <= self.max_pooled => size,
+            // too large to be worth pooling
+            _ => return ArcCStr::try_from(s),
+        };
+        if self.slab.is_none() || self.next + size > self.slab_size {
+            self.refill()?;
+        }
+        let slab = self.slab.expect("just refilled");
+        unsafe {
+            let start = (slab.as_ptr() as *mut u8).add(self.next);
+            self.next += size;
+            slab.as_ref().refs.fetch_add(1, Ordering::Relaxed);
+            ptr::copy_nonoverlapping(
+                s.as_ptr(),
+                start.add(size_of::<ReleaseHook>() + RawArcCStr::DATA_OFFSET),
+                s.len(),
+            );
+            let release = ReleaseHook {
+                ctx: slab.as_ptr() as *const (),
+                release: release_string,
+            };
+            let raw = RawArcCStr::init_custom(NonNull::new_unchecked(start), s.len(), release);
+            let s = ArcCStr::from_raw_arc(raw);
+            #[cfg(feature = "alloc-observer")]
+            crate::observer::construct(&s);
+            Ok(s)
+        }
+    }
+
+    /// The number of bytes of a slab that a string of `len` bytes takes up, rounded up so that
+    /// the next string is aligned too.
+    fn footprint(len: usize) -> Option<usize> {
+        let size = RawArcCStr::custom_layout(len)?.size();
+        let align = RawArcCStr::layout(0).align();
+        size.checked_add(align - 1).map(|size| size & !(align - 1))
+    }
+
+    /// Moves on to a new slab, giving up the pool's reference to the current one.
+    fn refill(&mut self) -> Result<(), ArcCStrError> {
+        let ptr = unsafe { alloc::alloc(Slab::layout(self.slab_size)) };
+        let slab = NonNull::new(ptr as *mut Slab).ok_or(ArcCStrError::Alloc)?;
+        unsafe {
+            ptr::write(
+                slab.as_ptr(),
+                Slab {
+                    refs: AtomicUsize::new(1),
+                    size: self.slab_size,
+                },
+            );
+        }
+        if let Some(old) = self.slab.replace(slab) {
+            unsafe { Slab::release(old) };
+        }
+        self.next = size_of::<Slab>();
+        Ok(())
+    }
+}
+
+impl Drop for ArcCStrPool {
+    fn drop(&mut self) {
+        if let Some(slab) = self.slab.take() {
+            // the strings carved from it keep it alive for as long as they need it
+            unsafe { Slab::release(slab) };
+        }
+    }
+}
+
+impl Default for ArcCStrPool {
+    fn default() -> Self {
+        ArcCStrPool::new()
+    }
+}
+
+impl fmt::Debug for ArcCStrPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArcCStrPool")
+            .field("slab_size", &self.slab_size)
+            .field("slab_used", &self.slab.map_or(0, |_| self.next))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCStrPool;
+    use crate::{ArcCStr, ArcCStrError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn carves_strings() {
+        let mut pool = ArcCStrPool::with_slab_size(1024);
+        let strings: Vec<_> = (0..1000)
+            .map(|i| pool.alloc(i.to_string()).unwrap())
+            .collect();
+        for (i, s) in strings.iter().enumerate() {
+            assert_eq!(s.to_str(), Ok(&*i.to_string()));
+        }
+        // strings in the same slab are next to each other
+        let gap = strings[1].as_ptr() as usize - strings[0].as_ptr() as usize;
+        assert!(gap < 128);
+
+        let weak = ArcCStr::downgrade(&strings[500]);
+        drop(pool);
+        let keep = strings[500].clone();
+        drop(strings);
+        assert_eq!(keep, "500");
+        assert_eq!(weak.upgrade().unwrap(), "500");
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        let mut pool = ArcCStrPool::new();
+        let strings: Vec<_> = (0..100)
+            .map(|i| pool.alloc(format!("s{}", i)).unwrap())
+            .collect();
+        let handles: Vec<_> = strings
+            .chunks(10)
+            .map(|chunk| {
+                let chunk = chunk.to_vec();
+                std::thread::spawn(move || chunk.iter().map(|s| s.len()).sum::<usize>())
+            })
+            .collect();
+        drop(strings);
+        let total: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert_eq!(total, 10 * 2 + 90 * 3);
+    }
+
+    #[test]
+    fn large_and_invalid() {
+        let mut pool = ArcCStrPool::with_slab_size(1024);
+        let big = "x".repeat(1000);
+        let s = pool.alloc(&big).unwrap();
+        assert_eq!(s.to_bytes(), big.as_bytes());
+        assert_eq!(
+            pool.alloc("a\0b"),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+        assert_eq!(
+            format!("{:?}", pool),
+            "ArcCStrPool { slab_size: 1024, slab_used: 0 }"
+        );
+        let mut mutable = pool.alloc("abc").unwrap();
+        ArcCStr::get_mut(&mut mutable)
+            .unwrap()
+            .make_ascii_uppercase();
+        assert_eq!(mutable, ArcCStr::try_from("ABC").unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn slab_too_small() {
+        ArcCStrPool::with_slab_size(16);
+    }
+}
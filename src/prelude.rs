@@ -0,0 +1,16 @@
+//! The most commonly used types, for glob importing.
+//!
+//! This also brings [`TryFrom`] into scope, which the Rust 2018 prelude lacks but most ways of
+//! constructing an [`ArcCStr`] go through.
+//!
+//! # Examples
+//!
+//! ```
+//! use arccstr::prelude::*;
+//!
+//! let s = ArcCStr::try_from("foo").unwrap();
+//! assert_eq!(s, "foo");
+//! ```
+
+pub use crate::{ArcCStr, ArcCStrBuilder, ArcCStrError, ArcStr, WeakCStr};
+pub use core::convert::TryFrom;
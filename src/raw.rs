@@ -0,0 +1,1592 @@
+//! Low-level access to the allocations that back [`ArcCStr`](crate::ArcCStr).
+//!
+//! Most users should never need this module. It exists for advanced embedders (database pages,
+//! custom serializers, FFI layers) that need to manage `ArcCStr` allocations by hand without
+//! forking the crate.
+//!
+//! # Layout
+//!
+//! Every string lives in a single heap allocation laid out as
+//!
+//! ```text
+//! +--------+---------------------+----+
+//! | Header | string bytes (len)  | \0 |
+//! +--------+---------------------+----+
+//! ^ RawArcCStr::as_ptr()
+//!          ^ RawArcCStr::data()
+//! ```
+//!
+//! The allocation is aligned like [`Header`], and its exact [`Layout`] for a string of `len`
+//! bytes is given by [`RawArcCStr::layout`].
+//!
+//! A string that was not allocated by the global allocator, such as one made by
+//! [`ArcCStr::try_from_bytes_in`](crate::ArcCStr::try_from_bytes_in), has two more words in
+//! front of its header, which say how to release the allocation. Its header is marked so that
+//! [`RawArcCStr::deallocate`] knows to look for them. Such a string is still pointed to by its
+//! header, so nothing else about it differs.
+//!
+//! A string built with [`ArcCStrBuilder::build_keep_capacity`](crate::ArcCStrBuilder::build_keep_capacity)
+//! may have room for more bytes than it holds. Its header is marked as such, and the number of
+//! bytes it has room for is stored, as an unaligned `usize`, right after the terminator, so
+//! that `deallocate` can work out the layout it was allocated with.
+//!
+//! A string made by [`ArcCStr::from_external`](crate::ArcCStr::from_external) keeps its bytes
+//! outside the allocation, in memory that something else owns. Its allocation holds only the
+//! header, followed by a pointer to the string bytes in place of the bytes themselves, and its
+//! header is marked as [external](Header::is_external). [`RawArcCStr::data`] then points to
+//! that pointer rather than to the string; [`RawArcCStr::bytes`] points to the string bytes
+//! either way, and the invariants below are about the bytes it points to.
+//!
+//! The UTF-16 strings behind [`ArcCWStr`](crate::ArcCWStr) also use this layout, with a
+//! terminator of two bytes, and are marked as such in their header. They are never exposed as a
+//! `RawArcCStr`, and the invariants below do not hold for them.
+//!
+//! # Invariants
+//!
+//! A [`RawArcCStr`] is just a pointer, and is freely copyable. Unlike `ArcCStr` it does not own a
+//! reference, so nothing stops it from dangling. Every `unsafe` method on `RawArcCStr` requires
+//! that the allocation it points to is still live, meaning that it has not yet been deallocated.
+//! While that holds:
+//!
+//!  - the header is initialized, and the strong count equals the number of owners of the string
+//!    (e.g., the number of live `ArcCStr`s plus any references held manually through this
+//!    module);
+//!  - the weak count equals the number of weak references (e.g., live
+//!    [`WeakCStr`](crate::WeakCStr)s), plus one if the strong count is not zero;
+//!  - the string bytes contain no `\0`, and are followed by a `\0` terminator;
+//!  - the header's length is the number of string bytes, excluding the terminator;
+//!  - the string bytes are only ever mutated through the only reference to the string (see
+//!    [`RawArcCStr::is_unique`]);
+//!  - the header's UTF-8 flag, if set, says correctly whether the string bytes are UTF-8.
+//!
+//! The allocation is freed once both counts have dropped to zero.
+//!
+//! # Static strings
+//!
+//! A [`StaticCStr`](crate::StaticCStr) has the same layout as an allocation, but lives in static
+//! memory. Its header is marked as static, which makes the methods that change the reference
+//! counts do nothing, so its counts stay at a very large value, and it is never freed.
+
+use ::alloc::alloc::{self, GlobalAlloc, Layout};
+use ::alloc::boxed::Box;
+use ::alloc::vec;
+use core::ffi::CStr;
+use core::fmt;
+use core::mem::{align_of, size_of};
+use core::ptr::{self, NonNull};
+#[cfg(not(loom))]
+use core::sync::atomic;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
+#[cfg(loom)]
+use loom::sync::atomic;
+/// Gives up on a reference count that has exceeded `MAX_REFCOUNT`.
+///
+/// This aborts the process, or panics with the `panic-on-overflow` feature. Callers that
+/// incremented the count must undo that first, so that the count is intact if the panic is caught.
+#[cfg(not(feature = "saturating-refcount"))]
+#[cold]
+#[inline(never)]
+fn overflow() -> ! {
+    #[cfg(feature = "panic-on-overflow")]
+    panic!("reference count overflow");
+    #[cfg(all(not(feature = "panic-on-overflow"), feature = "std"))]
+    std::process::abort();
+    #[cfg(all(not(feature = "panic-on-overflow"), not(feature = "std")))]
+    {
+        // there is no abort in core, but panicking while already unwinding from a panic aborts,
+        // whatever the panic strategy
+        struct PanicOnDrop;
+        impl Drop for PanicOnDrop {
+            fn drop(&mut self) {
+                panic!("reference count overflow");
+            }
+        }
+        let _guard = PanicOnDrop;
+        panic!("reference count overflow");
+    }
+}
+
+/// A soft limit on the amount of references that may be made to an `ArcCStr`.
+///
+/// Going above this limit will abort your program (although not
+/// necessarily) at _exactly_ `MAX_REFCOUNT + 1` references. See `overflow`.
+pub(crate) const MAX_REFCOUNT: Count = Count::MAX >> 1;
+
+/// The strong count of a string that has become immortal because its count overflowed.
+///
+/// This sits halfway between `MAX_REFCOUNT` and `Count::MAX`, so that no realistic number of
+/// racing increments or decrements can move the count out of the saturated range.
+#[cfg(feature = "saturating-refcount")]
+pub(crate) const SATURATED: Count = MAX_REFCOUNT + (Count::MAX - MAX_REFCOUNT) / 2;
+
+/// The "acquire" half of the release/acquire pair that orders uses of a string before it is freed.
+///
+/// ThreadSanitizer does not support memory fences, so to avoid false positive reports we use an
+/// acquire load of the reference count instead when running under it (just like `std::sync::Arc`
+/// does).
+#[cfg(not(tsan))]
+macro_rules! acquire {
+    ($x:expr) => {
+        atomic::fence(Acquire)
+    };
+}
+
+#[cfg(tsan)]
+macro_rules! acquire {
+    ($x:expr) => {
+        $x.load(Acquire)
+    };
+}
+
+/// The header that precedes the string bytes in every `ArcCStr` allocation.
+#[repr(C)]
+pub struct Header {
+    // the header holds a pointer-sized field in front of the string in every configuration but
+    // this one, so the alignment has to be asked for (see `ReleaseHook`)
+    #[cfg(feature = "packed-header")]
+    _align: [usize; 0],
+    strong: AtomicCount,
+    // with the `packed-header` feature, the length shares the first word with the strong count
+    #[cfg(feature = "packed-header")]
+    len: Len,
+    weak: AtomicCount,
+    // on 64-bit targets, the top byte of the length holds the flags (see `FLAGS_SHIFT`)
+    #[cfg(flags_in_len)]
+    len: atomic::AtomicUsize,
+    #[cfg(not(any(feature = "packed-header", flags_in_len)))]
+    len: Len,
+    #[cfg(feature = "refcount-telemetry")]
+    max_strong: AtomicCount,
+    #[cfg(not(flags_in_len))]
+    flags: atomic::AtomicU8,
+    #[cfg(feature = "fingerprint")]
+    fingerprint: u8,
+    // spelled out so that the header has no uninitialized padding when copied as bytes
+    _pad: [u8; HEADER_PAD],
+}
+
+/// The integer type of the reference counts.
+///
+/// This is `usize`, or `u32` with the `u32-refcount` feature, which makes the header a word
+/// smaller on 64-bit targets (or two, with `refcount-telemetry`), at the cost of overflowing at
+/// `i32::MAX` references rather than at `isize::MAX`.
+#[cfg(not(feature = "u32-refcount"))]
+pub type Count = usize;
+/// The integer type of the reference counts.
+///
+/// This is `usize`, or `u32` with the `u32-refcount` feature, which makes the header a word
+/// smaller on 64-bit targets (or two, with `refcount-telemetry`), at the cost of overflowing at
+/// `i32::MAX` references rather than at `isize::MAX`.
+#[cfg(feature = "u32-refcount")]
+pub type Count = u32;
+
+/// The atomic version of [`Count`].
+#[cfg(not(feature = "u32-refcount"))]
+pub type AtomicCount = atomic::AtomicUsize;
+/// The atomic version of [`Count`].
+#[cfg(feature = "u32-refcount")]
+pub type AtomicCount = atomic::AtomicU32;
+
+/// The integer type of the length in the header.
+///
+/// This is `u32` with the `packed-header` feature, which packs the length and the strong count
+/// into the first word of the header, and the weak count and the flags into the second. That
+/// makes the header two words on 64-bit targets, so that a string takes up three words along with
+/// the pointer to it, but limits strings to 4 GiB.
+#[cfg(not(feature = "packed-header"))]
+pub(crate) type Len = usize;
+#[cfg(feature = "packed-header")]
+pub(crate) type Len = u32;
+
+/// The number of bytes taken up by the fields of the header.
+const HEADER_USED: usize = (2 + cfg!(feature = "refcount-telemetry") as usize) * size_of::<Count>()
+    + size_of::<Len>()
+    + !cfg!(flags_in_len) as usize
+    + cfg!(feature = "fingerprint") as usize;
+
+/// The number of bytes after the last field of the header, up to a multiple of `usize`.
+const HEADER_PAD: usize =
+    (size_of::<usize>() - HEADER_USED % size_of::<usize>()) % size_of::<usize>();
+
+/// How far up the length the flags are kept, when they share its word.
+///
+/// The top byte of a 64-bit length would only be needed for strings of 64 PiB or more, which
+/// cannot be allocated anyway, so keeping the flags there saves the header a word.
+#[cfg(flags_in_len)]
+const FLAGS_SHIFT: u32 = usize::BITS - 8;
+/// The bits of the length word that hold the length, when it also holds the flags.
+#[cfg(flags_in_len)]
+const LEN_MASK: usize = usize::MAX >> 8;
+
+/// Set in the header flags once the string bytes have been checked for UTF-8.
+const UTF8_CHECKED: u8 = 1 << 0;
+/// Set in the header flags, along with `UTF8_CHECKED`, if the string bytes are valid UTF-8.
+const UTF8_VALID: u8 = 1 << 1;
+/// Set in the header flags if the string lives in static memory, and must never be freed.
+const STATIC: u8 = 1 << 2;
+
+/// Set in the header flags if the allocation is released by the [`ReleaseHook`] hook in front of
+/// the header, rather than by the global allocator.
+const CUSTOM: u8 = 1 << 3;
+
+/// Set in the header flags if the string holds UTF-16 code units, and so has a terminator of
+/// two bytes (see [`RawArcCStr::try_allocate_wide`]).
+const WIDE: u8 = 1 << 4;
+
+/// Set in the header flags if the allocation has room for more bytes than the string holds, in
+/// which case its capacity is stored, unaligned, right after the terminator (see
+/// [`RawArcCStr::init_with_slack`]).
+const SLACK: u8 = 1 << 5;
+
+/// Set in the header flags if the string bytes live outside the allocation, which holds an
+/// [`External`] in their place.
+const EXTERNAL: u8 = 1 << 6;
+
+/// The strong and weak counts of a static string, which never change.
+#[cfg(not(loom))]
+const STATIC_COUNT: Count = MAX_REFCOUNT + 1;
+
+impl Header {
+    fn new(buf: &[u8]) -> Self {
+        Header {
+            #[cfg(feature = "packed-header")]
+            _align: [],
+            strong: AtomicCount::new(1),
+            weak: AtomicCount::new(1),
+            // the layout was checked against MAX_LEN when the string was allocated
+            #[cfg(flags_in_len)]
+            len: atomic::AtomicUsize::new(buf.len()),
+            #[cfg(not(flags_in_len))]
+            len: buf.len() as Len,
+            #[cfg(feature = "refcount-telemetry")]
+            max_strong: AtomicCount::new(1),
+            #[cfg(not(flags_in_len))]
+            flags: atomic::AtomicU8::new(0),
+            #[cfg(feature = "fingerprint")]
+            fingerprint: fingerprint(buf),
+            _pad: [0; HEADER_PAD],
+        }
+    }
+
+    /// The header of a static string holding `s`.
+    // loom's atomics cannot be constructed in a const fn
+    #[cfg(not(loom))]
+    pub(crate) const fn new_static(s: &str) -> Self {
+        Header {
+            #[cfg(feature = "packed-header")]
+            _align: [],
+            strong: AtomicCount::new(STATIC_COUNT),
+            weak: AtomicCount::new(STATIC_COUNT),
+            // no string literal comes anywhere near 4 GiB
+            #[cfg(flags_in_len)]
+            len: atomic::AtomicUsize::new(
+                s.len() | ((STATIC | UTF8_CHECKED | UTF8_VALID) as usize) << FLAGS_SHIFT,
+            ),
+            #[cfg(not(flags_in_len))]
+            len: s.len() as Len,
+            #[cfg(feature = "refcount-telemetry")]
+            max_strong: AtomicCount::new(STATIC_COUNT),
+            #[cfg(not(flags_in_len))]
+            flags: atomic::AtomicU8::new(STATIC | UTF8_CHECKED | UTF8_VALID),
+            #[cfg(feature = "fingerprint")]
+            fingerprint: fingerprint(s.as_bytes()),
+            _pad: [0; HEADER_PAD],
+        }
+    }
+
+    /// The flags, which are kept in the top byte of the length on 64-bit targets.
+    #[inline]
+    fn flags(&self) -> u8 {
+        #[cfg(flags_in_len)]
+        return (self.len.load(Relaxed) >> FLAGS_SHIFT) as u8;
+        #[cfg(not(flags_in_len))]
+        return self.flags.load(Relaxed);
+    }
+
+    /// Sets the given flags, leaving the others as they are.
+    #[inline]
+    fn set_flags(&self, flags: u8) {
+        #[cfg(flags_in_len)]
+        self.len.fetch_or((flags as usize) << FLAGS_SHIFT, Relaxed);
+        #[cfg(not(flags_in_len))]
+        self.flags.fetch_or(flags, Relaxed);
+    }
+
+    /// Clears the given flags, leaving the others as they are.
+    #[inline]
+    fn clear_flags(&self, flags: u8) {
+        #[cfg(flags_in_len)]
+        self.len
+            .fetch_and(!((flags as usize) << FLAGS_SHIFT), Relaxed);
+        #[cfg(not(flags_in_len))]
+        self.flags.fetch_and(!flags, Relaxed);
+    }
+
+    /// Returns true if the string lives in static memory, rather than in an allocation.
+    #[inline]
+    pub fn is_static(&self) -> bool {
+        self.flags() & STATIC != 0
+    }
+
+    /// Returns true if the string bytes live outside the allocation, as they do for a string made
+    /// by [`ArcCStr::from_external`](crate::ArcCStr::from_external).
+    #[inline]
+    pub fn is_external(&self) -> bool {
+        self.flags() & EXTERNAL != 0
+    }
+
+    /// The number of owners of the string.
+    pub fn strong(&self) -> &AtomicCount {
+        &self.strong
+    }
+
+    /// The number of weak references to the string, plus one while the strong count is not zero.
+    pub fn weak(&self) -> &AtomicCount {
+        &self.weak
+    }
+
+    /// The length of the string bytes, excluding the terminator.
+    #[inline]
+    #[allow(clippy::unnecessary_cast)] // `Len` is `usize` without the `packed-header` feature
+    pub fn len(&self) -> usize {
+        // the length never changes, only the flags next to it do
+        #[cfg(flags_in_len)]
+        return self.len.load(Relaxed) & LEN_MASK;
+        #[cfg(not(flags_in_len))]
+        return self.len as usize;
+    }
+
+    /// Returns true if the string is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The highest strong count the string has had so far.
+    ///
+    /// This is only approximate under concurrent clones, and is only updated by
+    /// [`RawArcCStr::increment_strong_count`], not by direct stores to [`strong`](Header::strong).
+    #[cfg(feature = "refcount-telemetry")]
+    pub fn max_strong(&self) -> &AtomicCount {
+        &self.max_strong
+    }
+
+    /// Whether the string bytes are valid UTF-8, or `None` if that has not been checked yet.
+    #[inline]
+    pub fn utf8(&self) -> Option<bool> {
+        let flags = self.flags();
+        if flags & UTF8_CHECKED == 0 {
+            None
+        } else {
+            Some(flags & UTF8_VALID != 0)
+        }
+    }
+
+    /// Records whether the string bytes are valid UTF-8, so that later checks can be skipped.
+    ///
+    /// # Safety
+    ///
+    /// `valid` must be true if and only if the string bytes are valid UTF-8.
+    #[inline]
+    pub unsafe fn set_utf8(&self, valid: bool) {
+        let flags = if valid {
+            UTF8_CHECKED | UTF8_VALID
+        } else {
+            UTF8_CHECKED
+        };
+        // the bytes only change while there is a single reference, so racing writers all store
+        // the same bits
+        self.set_flags(flags);
+    }
+
+    /// A one-byte hash of the string bytes, computed when the string was allocated.
+    ///
+    /// Strings with different fingerprints are never equal, which lets comparisons skip reading
+    /// the string bytes for most unequal pairs.
+    #[cfg(feature = "fingerprint")]
+    pub fn fingerprint(&self) -> u8 {
+        self.fingerprint
+    }
+}
+
+#[cfg(feature = "fingerprint")]
+const fn fingerprint(buf: &[u8]) -> u8 {
+    // FNV-1a, folded down to a byte, with a loop so that static strings can be fingerprinted at
+    // compile time
+    let mut h = 0x811c_9dc5u32;
+    let mut i = 0;
+    while i < buf.len() {
+        h = (h ^ buf[i] as u32).wrapping_mul(0x0100_0193);
+        i += 1;
+    }
+    (h ^ (h >> 8) ^ (h >> 16) ^ (h >> 24)) as u8
+}
+
+impl fmt::Debug for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut d = f.debug_struct("Header");
+        d.field("strong", &self.strong.load(Relaxed));
+        d.field("weak", &self.weak.load(Relaxed));
+        d.field("len", &self.len());
+        #[cfg(feature = "refcount-telemetry")]
+        d.field("max_strong", &self.max_strong.load(Relaxed));
+        d.field("utf8", &self.utf8());
+        d.field("static", &self.is_static());
+        #[cfg(feature = "fingerprint")]
+        d.field("fingerprint", &self.fingerprint);
+        d.finish()
+    }
+}
+
+/// A raw, non-owning pointer to an `ArcCStr` allocation.
+///
+/// See the [module-level documentation](self) for the layout and the invariants that the
+/// `unsafe` methods rely on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RawArcCStr {
+    ptr: NonNull<Header>,
+}
+
+/// How to release an allocation that was not made by the global allocator.
+///
+/// This is stored just in front of the header of such an allocation, which then starts at the
+/// hook, and is laid out as [`RawArcCStr::layout`] plus the size of a `ReleaseHook`.
+#[repr(C)]
+pub(crate) struct ReleaseHook {
+    /// Passed to `release` as is.
+    pub(crate) ctx: *const (),
+    /// Frees the allocation, given `ctx`, a pointer to the start of the allocation, and its size
+    /// (it is aligned like a `Header`). It may be called on any thread. It is `extern "C"` so
+    /// that the compiler knows it never unwinds, which keeps drops free of panics.
+    pub(crate) release: unsafe extern "C" fn(*const (), NonNull<u8>, usize),
+}
+
+/// What the allocation of an external string holds in place of the string bytes.
+#[repr(C)]
+pub(crate) struct External {
+    /// The string bytes, followed by their terminator, which the allocation does not own.
+    pub(crate) data: NonNull<u8>,
+    /// Passed to `release` as is.
+    pub(crate) ctx: *const (),
+    /// Lets go of whatever keeps `data` valid, given `ctx`, once the allocation has been freed.
+    /// It may be called on any thread, and is `extern "C"` for the same reason as
+    /// [`ReleaseHook::release`].
+    pub(crate) release: unsafe extern "C" fn(*const ()),
+}
+
+unsafe impl Send for RawArcCStr {}
+unsafe impl Sync for RawArcCStr {}
+
+impl RawArcCStr {
+    /// The offset of the string bytes from the start of the allocation.
+    pub const DATA_OFFSET: usize = size_of::<Header>();
+
+    /// The longest length the header can hold.
+    ///
+    /// This is `u32::MAX` with the `packed-header` feature. Otherwise, it is `usize::MAX` on
+    /// 32-bit targets, and 2<sup>56</sup> - 1 on 64-bit targets, where the top byte of the length
+    /// holds the header's flags. Either way, the size of the allocation overflows well before
+    /// the length does.
+    #[cfg(flags_in_len)]
+    pub const MAX_LEN: usize = LEN_MASK;
+    /// The longest length the header can hold.
+    ///
+    /// This is `u32::MAX` with the `packed-header` feature. Otherwise, it is `usize::MAX` on
+    /// 32-bit targets, and 2<sup>56</sup> - 1 on 64-bit targets, where the top byte of the length
+    /// holds the header's flags. Either way, the size of the allocation overflows well before
+    /// the length does.
+    #[cfg(not(flags_in_len))]
+    #[allow(clippy::unnecessary_cast)] // `Len` is `usize` without the `packed-header` feature
+    pub const MAX_LEN: usize = Len::MAX as usize;
+
+    /// The layout of the allocation that holds a string of `len` bytes (excluding the
+    /// terminator).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` exceeds [`MAX_LEN`](RawArcCStr::MAX_LEN), or if the total size overflows
+    /// `isize`.
+    pub fn layout(len: usize) -> Layout {
+        Self::try_layout(len).expect("string too long")
+    }
+
+    /// Like [`layout`](RawArcCStr::layout), but returns `None` instead of panicking.
+    pub fn try_layout(len: usize) -> Option<Layout> {
+        let size = Some(len)
+            .filter(|&len| len <= Self::MAX_LEN)
+            .and_then(|len| Self::DATA_OFFSET.checked_add(len))
+            .and_then(|sz| sz.checked_add(1))?;
+        Layout::from_size_align(size, align_of::<Header>()).ok()
+    }
+
+    /// Allocates a new string holding a copy of `buf`, with a strong count of one.
+    ///
+    /// The returned pointer owns that one reference; it must eventually be released with
+    /// [`decrement_strong_count`](RawArcCStr::decrement_strong_count) and
+    /// [`deallocate`](RawArcCStr::deallocate) (or be handed to an `ArcCStr`), or the string is
+    /// leaked.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must not contain any `\0` bytes.
+    pub unsafe fn allocate(buf: &[u8]) -> Self {
+        Self::try_allocate(buf).expect("could not allocate memory")
+    }
+
+    /// Like [`allocate`](RawArcCStr::allocate), but returns `None` if the allocation fails.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must not contain any `\0` bytes.
+    pub unsafe fn try_allocate(buf: &[u8]) -> Option<Self> {
+        Self::try_allocate_concat(&[buf])
+    }
+
+    /// Like [`try_allocate`](RawArcCStr::try_allocate), but for a string made up of `parts`.
+    ///
+    /// # Safety
+    ///
+    /// None of `parts` may contain any `\0` bytes.
+    pub(crate) unsafe fn try_allocate_concat(parts: &[&[u8]]) -> Option<Self> {
+        let len = parts.iter().map(|p| p.len()).sum();
+        let ptr = Self::allocate_uninit(len)?;
+        // copy in the string data
+        let mut at = ptr.as_ptr().add(Self::DATA_OFFSET);
+        for part in parts {
+            ptr::copy_nonoverlapping(part.as_ptr(), at, part.len());
+            at = at.add(part.len());
+        }
+        Some(Self::init(ptr, len))
+    }
+
+    /// Allocates a new string holding a copy of `buf` with `alloc`, with a strong count of one.
+    ///
+    /// The allocation has a [`ReleaseHook`] in front of the header that frees it with `alloc` once
+    /// the string is [`deallocate`](RawArcCStr::deallocate)d.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must not contain any `\0` bytes.
+    pub(crate) unsafe fn try_allocate_in<A: GlobalAlloc + Sync>(
+        buf: &[u8],
+        alloc: &'static A,
+    ) -> Option<Self> {
+        unsafe extern "C" fn release<A: GlobalAlloc>(
+            ctx: *const (),
+            ptr: NonNull<u8>,
+            size: usize,
+        ) {
+            let layout = Layout::from_size_align_unchecked(size, align_of::<Header>());
+            (*(ctx as *const A)).dealloc(ptr.as_ptr(), layout)
+        }
+
+        let layout = Self::custom_layout(buf.len())?;
+        let ptr = NonNull::new(alloc.alloc(layout))?;
+        ptr::copy_nonoverlapping(
+            buf.as_ptr(),
+            ptr.as_ptr()
+                .add(size_of::<ReleaseHook>() + Self::DATA_OFFSET),
+            buf.len(),
+        );
+        let release = ReleaseHook {
+            ctx: alloc as *const A as *const (),
+            release: release::<A>,
+        };
+        Some(Self::init_custom(ptr, buf.len(), release))
+    }
+
+    /// The layout of an allocation that holds a string of `len` bytes (excluding the
+    /// terminator) behind a [`ReleaseHook`], or `None` if `len` exceeds
+    /// [`MAX_LEN`](RawArcCStr::MAX_LEN) or the size overflows `isize`.
+    pub(crate) fn custom_layout(len: usize) -> Option<Layout> {
+        if len > Self::MAX_LEN {
+            return None;
+        }
+        let size = (size_of::<ReleaseHook>() + Self::DATA_OFFSET)
+            .checked_add(len)?
+            .checked_add(1)?;
+        Layout::from_size_align(size, align_of::<Header>()).ok()
+    }
+
+    /// Turns an allocation laid out as [`custom_layout`](RawArcCStr::custom_layout) into a
+    /// string that is freed by `release`, by writing the `ReleaseHook`, the header, and the
+    /// terminator.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be an allocation laid out as `custom_layout(len)`, and the first `len` bytes of
+    /// its string data must have been written, and contain no `\0`. `release` must free it.
+    pub(crate) unsafe fn init_custom(ptr: NonNull<u8>, len: usize, release: ReleaseHook) -> Self {
+        // a ReleaseHook is a whole number of words, so the header after it is still aligned
+        #[allow(clippy::cast_ptr_alignment)]
+        ptr::write(ptr.as_ptr() as *mut ReleaseHook, release);
+        let raw = Self::init(
+            NonNull::new_unchecked(ptr.as_ptr().add(size_of::<ReleaseHook>())),
+            len,
+        );
+        raw.header().set_flags(CUSTOM);
+        raw
+    }
+
+    /// Allocates a new string whose `len` bytes live at `external.data`, outside the allocation,
+    /// with a strong count of one.
+    ///
+    /// The string bytes are neither copied nor written to. The allocation holds just the header
+    /// and `external`, and `external.release` is called once the string is
+    /// [`deallocate`](RawArcCStr::deallocate)d. Returns `None` if the allocation fails, in which
+    /// case `external.release` is not called.
+    ///
+    /// # Safety
+    ///
+    /// `len` must not exceed [`MAX_LEN`](RawArcCStr::MAX_LEN), the `len` bytes at
+    /// `external.data` must contain no `\0` and be followed by a `\0`, and they must stay valid
+    /// and unchanged until `external.release` is called.
+    pub(crate) unsafe fn try_allocate_external(external: External, len: usize) -> Option<Self> {
+        let ptr = NonNull::new(alloc::alloc(Self::external_layout()))?;
+        let data = core::slice::from_raw_parts(external.data.as_ptr(), len);
+        // we set the pointer alignment in layout to be that of Header
+        #[allow(clippy::cast_ptr_alignment)]
+        ptr::write(ptr.as_ptr() as *mut Header, Header::new(data));
+        // the header is a whole number of words, so the External after it is still aligned
+        #[allow(clippy::cast_ptr_alignment)]
+        ptr::write(
+            ptr.as_ptr().add(Self::DATA_OFFSET) as *mut External,
+            external,
+        );
+        let raw = Self::from_ptr(ptr);
+        raw.header().set_flags(EXTERNAL);
+        Some(raw)
+    }
+
+    /// The layout of the allocation of an external string, whatever its length.
+    fn external_layout() -> Layout {
+        // neither part is anywhere near large enough to overflow
+        unsafe {
+            Layout::from_size_align_unchecked(
+                Self::DATA_OFFSET + size_of::<External>(),
+                align_of::<Header>(),
+            )
+        }
+    }
+
+    /// Allocates a new wide string holding the `len` UTF-16 code units yielded by `units`, with a
+    /// strong count of one.
+    ///
+    /// This is the allocation behind an [`ArcCWStr`](crate::ArcCWStr). It is laid out like any
+    /// other, except that the string bytes are the units in native byte order, the terminator is a
+    /// `0` unit, and so is two bytes long, and the header is marked so that
+    /// [`deallocate`](RawArcCStr::deallocate) frees both bytes. The header's length is the length
+    /// of the units in bytes. Such a string may contain `\0` bytes, so it must not be accessed as
+    /// a [`CStr`].
+    ///
+    /// # Safety
+    ///
+    /// `units` must yield at least `len` units, the first `len` of which must not be `0`.
+    pub(crate) unsafe fn try_allocate_wide<I: Iterator<Item = u16>>(
+        len: usize,
+        units: I,
+    ) -> Option<Self> {
+        let bytes = len.checked_mul(2)?;
+        // a byte string of one more byte has room for the whole terminator
+        let ptr = Self::allocate_uninit(bytes.checked_add(1)?)?;
+        // the string data follows a header of whole words, so it is aligned for u16
+        #[allow(clippy::cast_ptr_alignment)]
+        let data = ptr.as_ptr().add(Self::DATA_OFFSET) as *mut u16;
+        for (i, unit) in units.take(len).enumerate() {
+            data.add(i).write(unit);
+        }
+        data.add(len).write(0);
+        // this only writes the header and the first byte of the terminator again
+        let raw = Self::init(ptr, bytes);
+        raw.header().set_flags(WIDE);
+        Some(raw)
+    }
+
+    /// Allocates room for a string of up to `cap` bytes, without initializing any of it.
+    ///
+    /// The allocation is laid out as `layout(cap)`, and must eventually either be freed with
+    /// [`deallocate_uninit`](RawArcCStr::deallocate_uninit) or, once it has been shrunk to fit,
+    /// be turned into a string with [`init`](RawArcCStr::init). Returns `None` if `cap` is too
+    /// large for a layout (see [`try_layout`](RawArcCStr::try_layout)), or if the allocation
+    /// fails.
+    pub(crate) fn allocate_uninit(cap: usize) -> Option<NonNull<u8>> {
+        // the layout always has room for at least the terminator, so it is never zero-sized
+        NonNull::new(unsafe { alloc::alloc(Self::try_layout(cap)?) })
+    }
+
+    /// Resizes an allocation from [`allocate_uninit`](RawArcCStr::allocate_uninit) to hold up
+    /// to `new_cap` bytes, keeping the string bytes written so far.
+    ///
+    /// If `new_cap` is too large for a layout, or the allocation fails, this returns `None`, and
+    /// `ptr` is left as it was.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be an uninitialized allocation that currently has room for `cap` bytes.
+    pub(crate) unsafe fn reallocate_uninit(
+        ptr: NonNull<u8>,
+        cap: usize,
+        new_cap: usize,
+    ) -> Option<NonNull<u8>> {
+        let new_size = Self::try_layout(new_cap)?.size();
+        NonNull::new(alloc::realloc(ptr.as_ptr(), Self::layout(cap), new_size))
+    }
+
+    /// Frees an allocation from [`allocate_uninit`](RawArcCStr::allocate_uninit) that never
+    /// became a string.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be an uninitialized allocation that currently has room for `cap` bytes.
+    pub(crate) unsafe fn deallocate_uninit(ptr: NonNull<u8>, cap: usize) {
+        alloc::dealloc(ptr.as_ptr(), Self::layout(cap))
+    }
+
+    /// Turns an allocation from [`allocate_uninit`](RawArcCStr::allocate_uninit) into a string,
+    /// by writing its header and terminator.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be an uninitialized allocation with room for exactly `len` bytes, and the first
+    /// `len` bytes of its string data must have been written, and contain no `\0`.
+    pub(crate) unsafe fn init(ptr: NonNull<u8>, len: usize) -> Self {
+        let data = ptr.as_ptr().add(Self::DATA_OFFSET);
+        // add \0 terminator
+        *data.add(len) = 0u8;
+        // we set the pointer alignment in layout to be that of Header
+        #[allow(clippy::cast_ptr_alignment)]
+        let header = ptr.as_ptr() as *mut Header;
+        ptr::write(header, Header::new(core::slice::from_raw_parts(data, len)));
+        Self::from_ptr(ptr)
+    }
+
+    /// Like [`init`](RawArcCStr::init), but for an allocation with room for `cap` bytes, which
+    /// keeps its spare capacity.
+    ///
+    /// The capacity is recorded after the terminator so that the allocation can be freed with
+    /// the layout it was allocated with. If there is not enough spare room for that, the
+    /// allocation is shrunk to fit first, and `None` is returned if that fails, in which case
+    /// `ptr` is left as it was.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be an uninitialized allocation with room for `cap` bytes, and the first `len`
+    /// bytes of its string data must have been written, and contain no `\0`.
+    pub(crate) unsafe fn init_with_slack(ptr: NonNull<u8>, len: usize, cap: usize) -> Option<Self> {
+        debug_assert!(len <= cap);
+        if cap - len < size_of::<usize>() {
+            let ptr = if cap == len {
+                ptr
+            } else {
+                Self::reallocate_uninit(ptr, cap, len)?
+            };
+            return Some(Self::init(ptr, len));
+        }
+        let raw = Self::init(ptr, len);
+        let after = raw.data().as_ptr().add(len + 1);
+        ptr::write_unaligned(after as *mut usize, cap);
+        raw.header().set_flags(SLACK);
+        Some(raw)
+    }
+
+    /// Returns a copy of the whole allocation (header, string bytes, and terminator).
+    ///
+    /// The copied header has its strong and weak counts set to one, so the image describes a
+    /// freshly allocated string rather than a snapshot of this one's sharing. The header is stored
+    /// in native byte order, so images are only meaningful to the same build of this crate on the
+    /// same target.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live.
+    pub unsafe fn to_image(self) -> Box<[u8]> {
+        let bytes = self.as_cstr().to_bytes();
+        let len = bytes.len();
+        let mut image = vec![0u8; Self::layout(len).size()].into_boxed_slice();
+        let header = Header::new(bytes);
+        // a check that has already been done stays valid for the copy, but the copy is neither
+        // static nor released by a custom hook, and holds its bytes itself with no spare capacity
+        header.set_flags(self.header().flags() & !(STATIC | CUSTOM | SLACK | EXTERNAL));
+        ptr::copy_nonoverlapping(
+            &header as *const Header as *const u8,
+            image.as_mut_ptr(),
+            Self::DATA_OFFSET,
+        );
+        ptr::copy_nonoverlapping(
+            self.bytes().as_ptr(),
+            image.as_mut_ptr().add(Self::DATA_OFFSET),
+            len + 1,
+        );
+        image
+    }
+
+    /// Allocates a new string from an image produced by [`to_image`](RawArcCStr::to_image).
+    ///
+    /// The image is copied into a properly aligned allocation, and its header is adopted as-is.
+    ///
+    /// # Safety
+    ///
+    /// `image` must be a well-formed image: a header whose strong and weak counts are at least
+    /// one and whose length is that of the string bytes, followed by the string bytes, which
+    /// contain no `\0`, followed by a `\0` terminator that is the last byte of `image`. Images
+    /// returned by `to_image` satisfy this.
+    pub unsafe fn from_image(image: &[u8]) -> Self {
+        debug_assert!(image.len() > Self::DATA_OFFSET);
+        debug_assert_eq!(image.last(), Some(&0));
+        let len = image.len() - Self::DATA_OFFSET - 1;
+        debug_assert!(!image[Self::DATA_OFFSET..][..len].contains(&0));
+
+        let layout = Self::layout(len);
+        let ptr = NonNull::new(alloc::alloc(layout)).expect("could not allocate memory");
+        ptr::copy_nonoverlapping(image.as_ptr(), ptr.as_ptr(), layout.size());
+        let raw = Self::from_ptr(ptr);
+        debug_assert_eq!(raw.header().len(), len);
+        // the copy is in an allocation of its own, which must be freed like any other, and
+        // which is exactly as large as the image and holds the string bytes itself
+        raw.header().clear_flags(STATIC | CUSTOM | SLACK | EXTERNAL);
+        raw
+    }
+
+    /// Frees the allocation.
+    ///
+    /// # Safety
+    ///
+    /// The strong and weak counts must both have dropped to zero through
+    /// [`decrement_strong_count`](RawArcCStr::decrement_strong_count) or
+    /// [`decrement_weak_count`](RawArcCStr::decrement_weak_count) returning `true` (which also
+    /// synchronizes with all prior users of the string), and no other pointer to the allocation
+    /// may be used afterwards.
+    pub unsafe fn deallocate(self) {
+        // this layout was already checked when the string was allocated, so there is no need to
+        // check it again (which would add a panic to every drop)
+        let flags = self.header().flags();
+        if flags & EXTERNAL != 0 {
+            let External { ctx, release, .. } = ptr::read(self.data().as_ptr() as *const External);
+            alloc::dealloc(self.ptr.as_ptr() as *mut u8, Self::external_layout());
+            return release(ctx);
+        }
+        // a wide string's terminator takes up one more byte
+        let mut size = Self::DATA_OFFSET + self.header().len() + 1 + (flags & WIDE != 0) as usize;
+        if flags & SLACK != 0 {
+            // an allocation with spare capacity records it right after the terminator
+            let cap = ptr::read_unaligned(
+                self.data().as_ptr().add(self.header().len() + 1) as *const usize
+            );
+            size = Self::DATA_OFFSET + cap + 1;
+        }
+        if flags & CUSTOM != 0 {
+            let start = (self.ptr.as_ptr() as *mut u8).sub(size_of::<ReleaseHook>());
+            let ReleaseHook { ctx, release } = ptr::read(start as *const ReleaseHook);
+            return release(
+                ctx,
+                NonNull::new_unchecked(start),
+                size_of::<ReleaseHook>() + size,
+            );
+        }
+        let layout = Layout::from_size_align_unchecked(size, align_of::<Header>());
+        alloc::dealloc(self.ptr.as_ptr() as *mut u8, layout)
+    }
+
+    /// Reconstructs a `RawArcCStr` from a pointer to the start of an allocation.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been obtained from [`as_ptr`](RawArcCStr::as_ptr).
+    pub unsafe fn from_ptr(ptr: NonNull<u8>) -> Self {
+        RawArcCStr { ptr: ptr.cast() }
+    }
+
+    /// Points to the header of a static string.
+    #[cfg(not(loom))]
+    pub(crate) const fn from_static(header: &'static Header) -> Self {
+        // a reference is never null
+        RawArcCStr {
+            ptr: unsafe { NonNull::new_unchecked(header as *const Header as *mut Header) },
+        }
+    }
+
+    /// Reconstructs a `RawArcCStr` from a pointer to the first byte of the string data.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been obtained from [`data`](RawArcCStr::data).
+    pub unsafe fn from_data(ptr: NonNull<u8>) -> Self {
+        Self::from_ptr(NonNull::new_unchecked(ptr.as_ptr().sub(Self::DATA_OFFSET)))
+    }
+
+    /// A pointer to the start of the allocation (i.e., to the header).
+    pub fn as_ptr(self) -> NonNull<u8> {
+        self.ptr.cast()
+    }
+
+    /// A pointer to the first byte of the string data.
+    ///
+    /// This is where the string bytes are, unless the string is
+    /// [external](Header::is_external); see [`bytes`](RawArcCStr::bytes) for a pointer to them
+    /// either way.
+    pub fn data(self) -> NonNull<u8> {
+        // the data pointer is in bounds of the same allocation, so it cannot wrap to null
+        unsafe { NonNull::new_unchecked((self.ptr.as_ptr() as *mut u8).add(Self::DATA_OFFSET)) }
+    }
+
+    /// A pointer to the first byte of the string bytes, wherever they live.
+    ///
+    /// This is [`data`](RawArcCStr::data), unless the string is
+    /// [external](Header::is_external), in which case the allocation records where its bytes
+    /// are.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live.
+    #[inline]
+    pub unsafe fn bytes(self) -> NonNull<u8> {
+        if self.header().is_external() {
+            (*(self.data().as_ptr() as *const External)).data
+        } else {
+            self.data()
+        }
+    }
+
+    /// Returns a reference to the header.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live for all of `'a`.
+    #[inline]
+    pub unsafe fn header<'a>(self) -> &'a Header {
+        // We're doing *so* many dodgy things here, so let's go through it step-by-step:
+        //
+        //  - As long as the allocation is alive, we know that the pointer is still valid
+        //  - Header is (obviously) Sync, and we're just giving out a &
+        //  - We know that the first bit of memory pointer to by self.ptr contains a Header
+        //  - We know that the pointer is aligned like a Header
+        //
+        &*self.ptr.as_ptr()
+    }
+
+    /// Returns a reference to the string.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live for all of `'a`.
+    #[inline]
+    pub unsafe fn as_cstr<'a>(self) -> &'a CStr {
+        // Even more dodgy pointer stuff:
+        //
+        //  - As long as the allocation is alive, we know that the pointer is still valid
+        //  - CStr is Sync (and besides, we're only giving out an immutable pointer)
+        //  - We know that the first bit of memory pointer to by self.ptr contains a Header,
+        //    and *after* that comes the CStr we initially copied in.
+        //  - We know that the following bytes are a well-formed CStr (e.g., has a null
+        //    terminator and no internal nulls), because we checked that when we constructed it.
+        //  - The header holds the length of those bytes, so we don't need to search for the
+        //    terminator.
+        //
+        let bytes = core::slice::from_raw_parts(self.bytes().as_ptr(), self.header().len() + 1);
+        CStr::from_bytes_with_nul_unchecked(bytes)
+    }
+
+    /// Gets the current strong count.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live.
+    #[inline]
+    #[allow(clippy::unnecessary_cast)] // `Count` is `usize` without the `u32-refcount` feature
+    pub unsafe fn strong_count(self) -> usize {
+        self.header().strong.load(SeqCst) as usize
+    }
+
+    /// Increments the strong count by one.
+    ///
+    /// Aborts the process if the count exceeds `MAX_REFCOUNT` (`isize::MAX`, or `i32::MAX` with
+    /// the `u32-refcount` feature). With the `saturating-refcount`
+    /// feature, the string instead becomes immortal: its count stays pinned at a very large value,
+    /// and it is never freed. With the `panic-on-overflow` feature, this instead panics and leaves
+    /// the count unchanged.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, and the caller must hold a reference to it (so that the count
+    /// cannot concurrently drop to zero).
+    #[inline]
+    pub unsafe fn increment_strong_count(self) {
+        // Using a relaxed ordering is alright here, as knowledge of the
+        // original reference prevents other threads from erroneously deleting
+        // the object.
+        //
+        // As explained in the [Boost documentation][1], Increasing the
+        // reference counter can always be done with memory_order_relaxed: New
+        // references to an object can only be formed from an existing
+        // reference, and passing an existing reference from one thread to
+        // another must already provide any required synchronization.
+        //
+        // [1]: (www.boost.org/doc/libs/1_55_0/doc/html/atomic/usage_examples.html)
+        if self.header().is_static() {
+            return;
+        }
+        let old_size = self.header().strong.fetch_add(1, Relaxed);
+
+        // However we need to guard against massive refcounts in case someone
+        // is `mem::forget`ing Arcs. If we don't do this the count can overflow
+        // and users will use-after free. We racily saturate to `MAX_REFCOUNT` on
+        // the assumption that there aren't ~2 billion threads incrementing
+        // the reference count at once. This branch will never be taken in
+        // any realistic program.
+        //
+        // We abort because such a program is incredibly degenerate, and we
+        // don't care to support it. Code that must never abort (such as a
+        // plugin loaded into someone else's process) can opt into leaking the
+        // string instead, or into panicking.
+        if old_size > MAX_REFCOUNT {
+            #[cfg(feature = "saturating-refcount")]
+            {
+                self.header().strong.store(SATURATED, Relaxed);
+                // skips the telemetry below, if it is enabled
+                #[allow(clippy::needless_return)]
+                return;
+            }
+            #[cfg(not(feature = "saturating-refcount"))]
+            {
+                self.header().strong.fetch_sub(1, Relaxed);
+                overflow();
+            }
+        }
+
+        #[cfg(feature = "refcount-telemetry")]
+        self.header().max_strong.fetch_max(old_size + 1, Relaxed);
+    }
+
+    /// Decrements the strong count by one, and returns `true` if that released the last
+    /// reference (strong or weak).
+    ///
+    /// When this returns `true`, the caller is the sole remaining user of the allocation, and all
+    /// uses of the string through other references happen-before the return. The caller should
+    /// then [`deallocate`](RawArcCStr::deallocate) it. If the strong count dropped to zero but
+    /// weak references remain, the last of them to be released frees the allocation instead.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, and the caller must give up the reference it held.
+    #[inline]
+    pub unsafe fn decrement_strong_count(self) -> bool {
+        if self.header().is_static() {
+            return false;
+        }
+
+        // An immortal string's count must not be brought back down, or it could be freed while
+        // references it forgot about are still around.
+        #[cfg(feature = "saturating-refcount")]
+        if self.header().strong.load(Relaxed) > MAX_REFCOUNT {
+            return false;
+        }
+
+        // Because `fetch_sub` is already atomic, we do not need to synchronize
+        // with other threads unless we are going to delete the object.
+        if self.header().strong.fetch_sub(1, Release) != 1 {
+            return false;
+        }
+
+        // This fence is needed to prevent reordering of use of the data and
+        // deletion of the data.  Because it is marked `Release`, the decreasing
+        // of the reference count synchronizes with this `Acquire` fence. This
+        // means that use of the data happens before decreasing the reference
+        // count, which happens before this fence, which happens before the
+        // deletion of the data.
+        //
+        // As explained in the [Boost documentation][1],
+        //
+        // > It is important to enforce any possible access to the object in one
+        // > thread (through an existing reference) to *happen before* deleting
+        // > the object in a different thread. This is achieved by a "release"
+        // > operation after dropping a reference (any access to the object
+        // > through this reference must obviously happened before), and an
+        // > "acquire" operation before deleting the object.
+        //
+        // [1]: (www.boost.org/doc/libs/1_55_0/doc/html/atomic/usage_examples.html)
+        acquire!(self.header().strong);
+
+        // the strong references collectively held one weak reference, which we now release
+        self.decrement_weak_count()
+    }
+
+    /// Drops the strong count from one to zero, if the caller's reference is the only one.
+    ///
+    /// Returns `true` if it did. The caller then no longer holds a strong reference, but holds
+    /// the weak reference that the strong references collectively held instead, so it may keep
+    /// reading the string until it releases that with
+    /// [`decrement_weak_count`](RawArcCStr::decrement_weak_count). Weak references can no
+    /// longer be upgraded.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, and the caller must hold a strong reference to it, which it
+    /// gives up if this returns `true`.
+    #[inline]
+    pub unsafe fn try_release_unique(self) -> bool {
+        if self
+            .header()
+            .strong
+            .compare_exchange(1, 0, Relaxed, Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        // see decrement_strong_count
+        acquire!(self.header().strong);
+        true
+    }
+
+    /// Gets the current weak count.
+    ///
+    /// This includes the weak reference collectively held by the strong references, if any. It
+    /// is `Count::MAX` while [`is_unique`](RawArcCStr::is_unique) is running.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live.
+    #[inline]
+    #[allow(clippy::unnecessary_cast)] // `Count` is `usize` without the `u32-refcount` feature
+    pub unsafe fn weak_count(self) -> usize {
+        self.header().weak.load(SeqCst) as usize
+    }
+
+    /// Increments the weak count by one.
+    ///
+    /// Like [`increment_strong_count`](RawArcCStr::increment_strong_count), this aborts the
+    /// process (or, depending on the enabled features, makes the string immortal or panics) if the
+    /// count exceeds `MAX_REFCOUNT`.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, and the caller must hold a strong or weak reference to it.
+    #[inline]
+    pub unsafe fn increment_weak_count(self) {
+        if self.header().is_static() {
+            return;
+        }
+        let weak = &self.header().weak;
+        let mut n = weak.load(Relaxed);
+        loop {
+            // is_unique holds the count at Count::MAX while it checks for other references
+            if n == Count::MAX {
+                core::hint::spin_loop();
+                n = weak.load(Relaxed);
+                continue;
+            }
+            if n > MAX_REFCOUNT {
+                #[cfg(feature = "saturating-refcount")]
+                return;
+                #[cfg(not(feature = "saturating-refcount"))]
+                overflow();
+            }
+            // Acquire pairs with the Release in is_unique, so that a weak reference created
+            // after get_mut sees what was written through it.
+            match weak.compare_exchange_weak(n, n + 1, Acquire, Relaxed) {
+                Ok(_) => return,
+                Err(old) => n = old,
+            }
+        }
+    }
+
+    /// Returns `true` if the caller's strong reference is the only reference of any kind, strong
+    /// or weak, and the string bytes are its own to change.
+    ///
+    /// While this holds, and the caller keeps its reference to itself, no other thread can reach
+    /// the string, so the caller may mutate the string bytes (but not introduce a `\0`). To tell
+    /// the header about the change, see [`contents_changed`](RawArcCStr::contents_changed).
+    ///
+    /// While it checks the strong count, this briefly sets the weak count to `Count::MAX`, which
+    /// makes [`increment_weak_count`](RawArcCStr::increment_weak_count) wait.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, and the caller must hold a strong reference to it.
+    pub unsafe fn is_unique(self) -> bool {
+        let header = self.header();
+        // the bytes of an external string belong to someone else, who may not let us write them
+        if header.is_external() {
+            return false;
+        }
+        // Lock the weak count if we hold the only weak reference (the one the strong references
+        // share). Otherwise, a weak reference could be upgraded and then dropped between our
+        // looking at the two counts, and we would miss the new strong reference. Acquire pairs
+        // with the Release in decrement_weak_count, so that we see what weak references did
+        // before they were dropped.
+        if header
+            .weak
+            .compare_exchange(1, Count::MAX, Acquire, Relaxed)
+            .is_err()
+        {
+            return false;
+        }
+        // Acquire pairs with the Release in decrement_strong_count, for the same reason.
+        let unique = header.strong.load(Acquire) == 1;
+        // Release pairs with the Acquire in increment_weak_count.
+        header.weak.store(1, Release);
+        unique
+    }
+
+    /// Updates the header after the string bytes were changed through a unique reference.
+    ///
+    /// This forgets whether the bytes are UTF-8, and recomputes the fingerprint, if enabled.
+    ///
+    /// # Safety
+    ///
+    /// The caller must hold the only reference to the string, as checked by
+    /// [`is_unique`](RawArcCStr::is_unique), and the string bytes must still contain no `\0`.
+    pub unsafe fn contents_changed(self) {
+        self.header().clear_flags(UTF8_CHECKED | UTF8_VALID);
+        #[cfg(feature = "fingerprint")]
+        {
+            let fingerprint = fingerprint(self.as_cstr().to_bytes());
+            ptr::addr_of_mut!((*self.ptr.as_ptr()).fingerprint).write(fingerprint);
+        }
+    }
+
+    /// Decrements the weak count by one, and returns `true` if that released the last reference.
+    ///
+    /// As with [`decrement_strong_count`](RawArcCStr::decrement_strong_count), the caller should
+    /// [`deallocate`](RawArcCStr::deallocate) the string when this returns `true`.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, and the caller must give up the weak reference it held.
+    #[inline]
+    pub unsafe fn decrement_weak_count(self) -> bool {
+        if self.header().is_static() {
+            return false;
+        }
+        #[cfg(feature = "saturating-refcount")]
+        if self.header().weak.load(Relaxed) > MAX_REFCOUNT {
+            return false;
+        }
+
+        if self.header().weak.fetch_sub(1, Release) != 1 {
+            return false;
+        }
+        // see decrement_strong_count
+        acquire!(self.header().weak);
+        true
+    }
+
+    /// Increments the strong count by one, unless it has already dropped to zero.
+    ///
+    /// Returns `true` if the count was incremented. This is how a weak reference is upgraded to a
+    /// strong one.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, and the caller must hold a weak reference to it.
+    #[inline]
+    pub unsafe fn try_increment_strong_count(self) -> bool {
+        if self.header().is_static() {
+            return true;
+        }
+        let strong = &self.header().strong;
+        let mut n = strong.load(Relaxed);
+        loop {
+            if n == 0 {
+                return false;
+            }
+            if n > MAX_REFCOUNT {
+                #[cfg(feature = "saturating-refcount")]
+                return true;
+                #[cfg(not(feature = "saturating-refcount"))]
+                overflow();
+            }
+            // Acquire pairs with the Release in decrement_strong_count, in case the string is
+            // subsequently dropped through the new reference.
+            match strong.compare_exchange_weak(n, n + 1, Acquire, Relaxed) {
+                Ok(_) => break,
+                Err(old) => n = old,
+            }
+        }
+        #[cfg(feature = "refcount-telemetry")]
+        self.header().max_strong.fetch_max(n + 1, Relaxed);
+        true
+    }
+
+    /// Like [`increment_strong_count`](RawArcCStr::increment_strong_count), but for a string
+    /// that only one thread has references to, such as one held by [`RcCStr`](crate::RcCStr)s.
+    ///
+    /// The count is updated with a plain load and store rather than with an atomic
+    /// read-modify-write, which is much cheaper, but would lose updates made by other threads.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, the caller must hold a reference to it, and all references to
+    /// it, strong or weak, must be held by the current thread.
+    #[inline]
+    pub unsafe fn increment_strong_count_unsync(self) {
+        if self.header().is_static() {
+            return;
+        }
+        let strong = &self.header().strong;
+        let n = strong.load(Relaxed);
+        // see increment_strong_count; there are no racing increments here, so the count has not
+        // been bumped yet when it is found to be too large
+        if n > MAX_REFCOUNT {
+            #[cfg(feature = "saturating-refcount")]
+            {
+                strong.store(SATURATED, Relaxed);
+                #[allow(clippy::needless_return)]
+                return;
+            }
+            #[cfg(not(feature = "saturating-refcount"))]
+            overflow();
+        }
+        strong.store(n + 1, Relaxed);
+        #[cfg(feature = "refcount-telemetry")]
+        if n + 1 > self.header().max_strong.load(Relaxed) {
+            self.header().max_strong.store(n + 1, Relaxed);
+        }
+    }
+
+    /// Like [`decrement_strong_count`](RawArcCStr::decrement_strong_count), but for a string
+    /// that only one thread has references to. See
+    /// [`increment_strong_count_unsync`](RawArcCStr::increment_strong_count_unsync).
+    ///
+    /// # Safety
+    ///
+    /// The allocation must be live, the caller must give up the reference it held, and all
+    /// references to it, strong or weak, must be held by the current thread.
+    #[inline]
+    pub unsafe fn decrement_strong_count_unsync(self) -> bool {
+        if self.header().is_static() {
+            return false;
+        }
+        let strong = &self.header().strong;
+        let n = strong.load(Relaxed);
+        #[cfg(feature = "saturating-refcount")]
+        if n > MAX_REFCOUNT {
+            return false;
+        }
+        strong.store(n - 1, Relaxed);
+        if n != 1 {
+            return false;
+        }
+        // the strong references collectively held one weak reference, which we now release
+        let weak = &self.header().weak;
+        let n = weak.load(Relaxed);
+        #[cfg(feature = "saturating-refcount")]
+        if n > MAX_REFCOUNT {
+            return false;
+        }
+        weak.store(n - 1, Relaxed);
+        n == 1
+    }
+}
+
+impl fmt::Debug for RawArcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawArcCStr").field(&self.ptr).finish()
+    }
+}
+
+impl fmt::Pointer for RawArcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.ptr, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawArcCStr;
+
+    #[test]
+    fn manual_lifecycle() {
+        unsafe {
+            let raw = RawArcCStr::allocate(b"foo");
+            assert_eq!(raw.as_cstr().to_bytes_with_nul(), b"foo\0");
+            assert_eq!(raw.strong_count(), 1);
+
+            raw.increment_strong_count();
+            assert_eq!(raw.strong_count(), 2);
+            assert!(!raw.decrement_strong_count());
+            assert!(raw.decrement_strong_count());
+            raw.deallocate();
+        }
+    }
+
+    #[test]
+    fn image_round_trip() {
+        unsafe {
+            let raw = RawArcCStr::allocate(b"foo");
+            raw.increment_strong_count();
+            let image = raw.to_image();
+            assert_eq!(image.len(), RawArcCStr::layout(3).size());
+            assert_eq!(&image[RawArcCStr::DATA_OFFSET..], b"foo\0");
+
+            let copy = RawArcCStr::from_image(&image);
+            assert_ne!(copy, raw);
+            assert_eq!(copy.strong_count(), 1);
+            assert_eq!(copy.as_cstr(), raw.as_cstr());
+
+            assert!(copy.decrement_strong_count());
+            copy.deallocate();
+            assert!(!raw.decrement_strong_count());
+            assert!(raw.decrement_strong_count());
+            raw.deallocate();
+        }
+    }
+
+    #[test]
+    fn layout() {
+        let l = RawArcCStr::layout(3);
+        assert_eq!(l.size(), RawArcCStr::DATA_OFFSET + 4);
+        assert_eq!(
+            RawArcCStr::layout(0).align(),
+            std::mem::align_of::<super::Header>()
+        );
+    }
+
+    #[test]
+    fn utf8_flag() {
+        unsafe {
+            let r = RawArcCStr::allocate(b"foo");
+            assert_eq!(r.header().utf8(), None);
+            r.header().set_utf8(true);
+            assert_eq!(r.header().utf8(), Some(true));
+            let copy = RawArcCStr::from_image(&r.to_image());
+            assert_eq!(copy.header().utf8(), Some(true));
+            for r in [r, copy] {
+                assert!(r.decrement_strong_count());
+                r.deallocate();
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "fingerprint")]
+    fn fingerprint() {
+        unsafe {
+            let a = RawArcCStr::allocate(b"foo");
+            let b = RawArcCStr::allocate(b"foo");
+            assert_eq!(a.header().fingerprint(), b.header().fingerprint());
+            let c = RawArcCStr::from_image(&a.to_image());
+            assert_eq!(a.header().fingerprint(), c.header().fingerprint());
+            for r in [a, b, c] {
+                assert!(r.decrement_strong_count());
+                r.deallocate();
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(all(flags_in_len, not(loom)))]
+    fn flags_share_the_length_word() {
+        if !cfg!(any(
+            feature = "u32-refcount",
+            feature = "refcount-telemetry",
+            feature = "fingerprint"
+        )) {
+            // the strong and weak counts, and the length with the flags on top
+            assert_eq!(RawArcCStr::DATA_OFFSET, 3 * 8);
+        }
+        assert_eq!(RawArcCStr::MAX_LEN, usize::MAX >> 8);
+        assert!(RawArcCStr::try_layout(RawArcCStr::MAX_LEN + 1).is_none());
+        unsafe {
+            let r = RawArcCStr::allocate(b"folded");
+            assert_eq!(r.header().utf8(), None);
+            r.header().set_utf8(true);
+            assert_eq!(r.header().len(), 6);
+            assert_eq!(r.header().utf8(), Some(true));
+            assert!(!r.header().is_static());
+            r.contents_changed();
+            assert_eq!(r.header().utf8(), None);
+            assert_eq!(r.as_cstr().to_bytes(), b"folded");
+            assert!(r.decrement_strong_count());
+            r.deallocate();
+        }
+        let s = crate::literal!("static");
+        assert_eq!(s.len(), 6);
+        assert!(unsafe { crate::ArcCStr::as_raw_arc(&s).header() }.is_static());
+        assert_eq!(s.as_str(), Some("static"));
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "u32-refcount",
+        not(feature = "packed-header"),
+        target_pointer_width = "64"
+    ))]
+    fn u32_counts_save_a_word() {
+        // two counts in the first word, then the length and the flags, then the highest strong
+        // count and the fingerprint, if there are any, padded out to a word
+        let extra = cfg!(any(feature = "refcount-telemetry", feature = "fingerprint")) as usize;
+        assert_eq!(RawArcCStr::DATA_OFFSET, (2 + extra) * 8);
+        unsafe {
+            let r = RawArcCStr::allocate(b"small");
+            r.increment_strong_count();
+            r.increment_weak_count();
+            assert_eq!((r.strong_count(), r.weak_count()), (2, 2));
+            assert!(!r.decrement_weak_count());
+            assert!(!r.decrement_strong_count());
+            assert!(r.decrement_strong_count());
+            r.deallocate();
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "packed-header", target_pointer_width = "64"))]
+    fn packed_header_is_two_words() {
+        use std::panic::catch_unwind;
+        let words = 2 + cfg!(feature = "refcount-telemetry") as usize;
+        assert_eq!(RawArcCStr::DATA_OFFSET, words * 8);
+        assert!(catch_unwind(|| RawArcCStr::layout(u32::MAX as usize + 1)).is_err());
+        assert!(RawArcCStr::custom_layout(u32::MAX as usize + 1).is_none());
+        unsafe {
+            let r = RawArcCStr::allocate(b"packed");
+            r.increment_strong_count();
+            assert_eq!(r.header().len(), 6);
+            assert_eq!(r.as_cstr().to_bytes(), b"packed");
+            assert!(!r.decrement_strong_count());
+            assert_eq!(r.header().len(), 6);
+            assert!(r.decrement_strong_count());
+            r.deallocate();
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "saturating-refcount")]
+    fn saturates() {
+        use super::{Relaxed, MAX_REFCOUNT, SATURATED};
+        unsafe {
+            let r = RawArcCStr::allocate(b"immortal");
+            r.header().strong().store(MAX_REFCOUNT + 1, Relaxed);
+            r.increment_strong_count();
+            assert_eq!(r.header().strong().load(Relaxed), SATURATED);
+            assert!(!r.decrement_strong_count());
+            assert_eq!(r.header().strong().load(Relaxed), SATURATED);
+
+            // clean up by hand so the test does not leak
+            r.header().strong().store(1, Relaxed);
+            assert!(r.decrement_strong_count());
+            r.deallocate();
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "panic-on-overflow", not(feature = "saturating-refcount")))]
+    fn panics_on_overflow() {
+        use super::{Relaxed, MAX_REFCOUNT};
+        use std::panic::catch_unwind;
+        unsafe {
+            let r = RawArcCStr::allocate(b"overflowing");
+            r.header().strong().store(MAX_REFCOUNT + 1, Relaxed);
+            assert!(catch_unwind(|| r.increment_strong_count()).is_err());
+            assert!(catch_unwind(|| r.try_increment_strong_count()).is_err());
+            assert_eq!(r.header().strong().load(Relaxed), MAX_REFCOUNT + 1);
+
+            r.header().weak().store(MAX_REFCOUNT + 1, Relaxed);
+            assert!(catch_unwind(|| r.increment_weak_count()).is_err());
+            assert_eq!(r.header().weak().load(Relaxed), MAX_REFCOUNT + 1);
+
+            // clean up by hand so the test does not leak
+            r.header().strong().store(1, Relaxed);
+            r.header().weak().store(1, Relaxed);
+            assert!(r.decrement_strong_count());
+            r.deallocate();
+        }
+    }
+}
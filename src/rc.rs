@@ -0,0 +1,340 @@
+use crate::raw::RawArcCStr;
+use crate::{ArcCStr, ArcCStrError};
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Deref;
+
+/// A single-threaded reference-counted null-terminated string.
+///
+/// `RcCStr` is to [`ArcCStr`] what [`Rc`](alloc::rc::Rc) is to [`Arc`](alloc::sync::Arc): it
+/// cannot be sent to or shared with other threads, which lets it update its reference count
+/// without atomic read-modify-write instructions. That makes cloning and dropping noticeably
+/// cheaper in tight single-threaded loops, such as in a parser.
+///
+/// It uses the same allocation as an `ArcCStr`, so a string that is only referenced by a single
+/// `RcCStr` can be turned into an `ArcCStr` (and back) without copying it, once it is ready to be
+/// shared between threads. There is no weak version of `RcCStr`.
+///
+/// Like `ArcCStr`, it dereferences to [`CStr`], and its own methods are associated functions.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, RcCStr};
+///
+/// let tokens: Vec<RcCStr> = "let x = x"
+///     .split(' ')
+///     .map(|t| RcCStr::try_from(t).unwrap())
+///     .collect();
+/// let x = tokens[1].clone();
+/// assert_eq!(RcCStr::strong_count(&x), 2);
+/// drop(tokens);
+///
+/// // the only reference left, so this reuses the allocation
+/// let ptr = x.as_ptr();
+/// let shared = ArcCStr::from(x);
+/// assert_eq!(shared.as_ptr(), ptr);
+/// std::thread::spawn(move || assert_eq!(shared, "x")).join().unwrap();
+/// ```
+///
+/// An `RcCStr` cannot leave its thread:
+///
+/// ```compile_fail
+/// use std::convert::TryFrom;
+/// use arccstr::RcCStr;
+///
+/// let s = RcCStr::try_from("local").unwrap();
+/// std::thread::spawn(move || drop(s));
+/// ```
+pub struct RcCStr {
+    raw: RawArcCStr,
+    // every reference to the string must stay on one thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl RcCStr {
+    /// Takes over the single reference to a string that nothing else points to.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be live, and the caller must hold the only reference to it, strong or weak.
+    unsafe fn from_unique(raw: RawArcCStr) -> Self {
+        RcCStr {
+            raw,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Constructs a new `RcCStr` holding a copy of `buf`.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must not contain any `\0` bytes.
+    unsafe fn try_from_bytes_unchecked(buf: &[u8]) -> Result<Self, ArcCStrError> {
+        ArcCStrError::check_len(buf.len())?;
+        let raw = RawArcCStr::try_allocate(buf).ok_or(ArcCStrError::Alloc)?;
+        Ok(RcCStr::from_unique(raw))
+    }
+
+    /// Gets the number of pointers to this string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    /// use arccstr::RcCStr;
+    ///
+    /// let five = RcCStr::try_from("5").unwrap();
+    /// let _also_five = RcCStr::clone(&five);
+    /// assert_eq!(2, RcCStr::strong_count(&five));
+    /// ```
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        unsafe { this.raw.strong_count() }
+    }
+
+    /// Returns true if the two `RcCStr`s point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.raw == other.raw
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for RcCStr {
+    type Error = ArcCStrError;
+    fn try_from(b: &'a [u8]) -> Result<Self, Self::Error> {
+        ArcCStrError::check_no_nul(b)?;
+        unsafe { RcCStr::try_from_bytes_unchecked(b) }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for RcCStr {
+    type Error = ArcCStrError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let s = RcCStr::try_from(s.as_bytes())?;
+        unsafe { s.raw.header().set_utf8(true) };
+        Ok(s)
+    }
+}
+
+impl TryFrom<String> for RcCStr {
+    type Error = ArcCStrError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        RcCStr::try_from(&*s)
+    }
+}
+
+impl<'a> From<&'a CStr> for RcCStr {
+    fn from(s: &'a CStr) -> Self {
+        // a CStr has no internal \0
+        unsafe { RcCStr::from_unique(RawArcCStr::allocate(s.to_bytes())) }
+    }
+}
+
+impl From<RcCStr> for ArcCStr {
+    /// Converts the string into an `ArcCStr`, which can be shared between threads.
+    ///
+    /// If `s` is the only pointer to the string, its allocation is handed over as is. Otherwise,
+    /// the string is copied.
+    fn from(s: RcCStr) -> Self {
+        if RcCStr::strong_count(&s) != 1 {
+            return ArcCStr::from(&*s);
+        }
+        let s = mem::ManuallyDrop::new(s);
+        // there are no weak references to an RcCStr's string, so this is the only reference of
+        // any kind, and no other thread has seen the counts since they were last updated
+        let s = unsafe { ArcCStr::from_raw_arc(s.raw) };
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::construct(&s);
+        s
+    }
+}
+
+impl From<ArcCStr> for RcCStr {
+    /// Converts the string into an `RcCStr`.
+    ///
+    /// If `s` is the only pointer to the string, strong or weak, its allocation is handed over as
+    /// is. Otherwise, the string is copied.
+    fn from(s: ArcCStr) -> Self {
+        let raw = ArcCStr::as_raw_arc(&s);
+        if !unsafe { raw.is_unique() } {
+            return RcCStr::from(&*s);
+        }
+        #[cfg(feature = "alloc-observer")]
+        crate::observer::drop(&s);
+        // is_unique synchronized with every other thread that dropped a reference to the string
+        unsafe { RcCStr::from_unique(ArcCStr::into_raw_arc(s)) }
+    }
+}
+
+impl Clone for RcCStr {
+    #[inline]
+    fn clone(&self) -> RcCStr {
+        // we hold a reference, and all the others are on this thread too
+        unsafe { self.raw.increment_strong_count_unsync() };
+        RcCStr {
+            raw: self.raw,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Deref for RcCStr {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        // as long as this RcCStr is alive, so is the allocation
+        unsafe { self.raw.as_cstr() }
+    }
+}
+
+impl Drop for RcCStr {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            if self.raw.decrement_strong_count_unsync() {
+                self.raw.deallocate();
+            }
+        }
+    }
+}
+
+impl PartialEq for RcCStr {
+    fn eq(&self, other: &RcCStr) -> bool {
+        RcCStr::ptr_eq(self, other) || **self == **other
+    }
+}
+
+impl Eq for RcCStr {}
+
+impl PartialOrd for RcCStr {
+    fn partial_cmp(&self, other: &RcCStr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RcCStr {
+    fn cmp(&self, other: &RcCStr) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl PartialEq<str> for RcCStr {
+    fn eq(&self, other: &str) -> bool {
+        self.to_bytes() == other.as_bytes()
+    }
+}
+
+impl<'a> PartialEq<&'a str> for RcCStr {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.to_bytes() == other.as_bytes()
+    }
+}
+
+impl Hash for RcCStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl fmt::Debug for RcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for RcCStr {
+    /// Writes the string, replacing any invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER` (like `CStr::to_string_lossy`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl fmt::Pointer for RcCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.raw, f)
+    }
+}
+
+impl Borrow<CStr> for RcCStr {
+    fn borrow(&self) -> &CStr {
+        self
+    }
+}
+
+impl AsRef<CStr> for RcCStr {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+impl AsRef<[u8]> for RcCStr {
+    fn as_ref(&self) -> &[u8] {
+        self.to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RcCStr;
+    use crate::{ArcCStr, ArcCStrError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn clone_and_drop() {
+        let a = RcCStr::try_from("hello").unwrap();
+        let b = a.clone();
+        assert!(RcCStr::ptr_eq(&a, &b));
+        assert_eq!(RcCStr::strong_count(&a), 2);
+        drop(a);
+        assert_eq!(RcCStr::strong_count(&b), 1);
+        assert_eq!(b, "hello");
+        assert_eq!(format!("{} {:?}", b, b), "hello \"hello\"");
+        assert_eq!(
+            RcCStr::try_from("a\0b"),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+
+    #[test]
+    fn converts_without_copying_when_unique() {
+        let rc = RcCStr::try_from("shared").unwrap();
+        let ptr = rc.as_ptr();
+        let arc = ArcCStr::from(rc);
+        assert_eq!(arc.as_ptr(), ptr);
+        assert_eq!(arc.as_str(), Some("shared"));
+        assert_eq!(ArcCStr::strong_count(&arc), 1);
+        let rc = RcCStr::from(arc);
+        assert_eq!(rc.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn copies_when_shared() {
+        let rc = RcCStr::try_from("many").unwrap();
+        let other = rc.clone();
+        let arc = ArcCStr::from(rc);
+        assert_ne!(arc.as_ptr(), other.as_ptr());
+        assert_eq!(RcCStr::strong_count(&other), 1);
+
+        let weak = ArcCStr::downgrade(&arc);
+        let rc = RcCStr::from(arc);
+        assert_eq!(rc, other);
+        assert!(weak.upgrade().is_none());
+
+        #[cfg(not(loom))]
+        assert_eq!(
+            RcCStr::from(ArcCStr::empty()),
+            RcCStr::try_from("").unwrap()
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Serializing a `Vec<ArcCStr>` with each distinct string written only once.
+//!
+//! Use this module with `#[serde(with = "arccstr::serde_dedup")]` on a `Vec<ArcCStr>` field.
+//! When the vector contains duplicates, it is written as a dictionary of the distinct strings,
+//! in the order they first appear, followed by the index into that dictionary of each element.
+//! Otherwise the dictionary is just the vector itself, and no indices are written. When reading
+//! the vector back, elements with the same index share a single allocation.
+//!
+//! Either way the vector is written as a pair of a sequence of strings and an optional sequence
+//! of `u32` indices, so this works with formats that are not self-describing too.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::convert::TryFrom;
+//! use arccstr::ArcCStr;
+//!
+//! let column: Vec<_> = ["GET", "POST", "GET", "GET"]
+//!     .iter()
+//!     .map(|&s| ArcCStr::try_from(s).unwrap())
+//!     .collect();
+//!
+//! let mut json = Vec::new();
+//! arccstr::serde_dedup::serialize(&column, &mut serde_json::Serializer::new(&mut json)).unwrap();
+//! assert_eq!(json, br#"[["GET","POST"],[0,1,0,0]]"#);
+//!
+//! let mut de = serde_json::Deserializer::from_slice(&json);
+//! let back = arccstr::serde_dedup::deserialize(&mut de).unwrap();
+//! assert_eq!(back, column);
+//! assert!(ArcCStr::ptr_eq(&back[0], &back[3]));
+//! ```
+
+use crate::ArcCStr;
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Serializes `strings`, writing each distinct string only once.
+pub fn serialize<S>(strings: &[ArcCStr], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut index: HashMap<&ArcCStr, u32> = HashMap::with_capacity(strings.len());
+    let mut dict = Vec::new();
+    let mut indices = Vec::with_capacity(strings.len());
+    for s in strings {
+        let next = u32::try_from(dict.len())
+            .map_err(|_| S::Error::custom("more than u32::MAX distinct strings"))?;
+        let i = *index.entry(s).or_insert_with(|| {
+            dict.push(s);
+            next
+        });
+        indices.push(i);
+    }
+
+    if dict.len() == strings.len() {
+        (strings, None::<Vec<u32>>).serialize(serializer)
+    } else {
+        (dict, Some(indices)).serialize(serializer)
+    }
+}
+
+/// Deserializes a vector written by [`serialize`], sharing one allocation between equal strings.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<ArcCStr>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let (dict, indices) = <(Vec<ArcCStr>, Option<Vec<u32>>)>::deserialize(deserializer)?;
+    let indices = match indices {
+        Some(indices) => indices,
+        None => return Ok(dict),
+    };
+    indices
+        .into_iter()
+        .map(|i| {
+            dict.get(i as usize).cloned().ok_or_else(|| {
+                D::Error::custom(format_args!(
+                    "index {} out of range for {} distinct strings",
+                    i,
+                    dict.len()
+                ))
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    fn round_trip(strings: &[ArcCStr]) -> (String, Vec<ArcCStr>) {
+        let mut json = Vec::new();
+        super::serialize(strings, &mut serde_json::Serializer::new(&mut json)).unwrap();
+        let back = super::deserialize(&mut serde_json::Deserializer::from_slice(&json)).unwrap();
+        (String::from_utf8(json).unwrap(), back)
+    }
+
+    #[test]
+    fn no_duplicates() {
+        let strings = vec![
+            ArcCStr::try_from("a").unwrap(),
+            ArcCStr::try_from("b").unwrap(),
+        ];
+        let (json, back) = round_trip(&strings);
+        assert_eq!(json, r#"[["a","b"],null]"#);
+        assert_eq!(back, strings);
+        assert_eq!(round_trip(&[]), (String::from("[[],null]"), vec![]));
+    }
+
+    #[test]
+    fn shares_duplicates() {
+        let a = ArcCStr::try_from("a").unwrap();
+        // equal contents in separate allocations are deduplicated too
+        let strings = vec![
+            ArcCStr::clone(&a),
+            ArcCStr::try_from("b").unwrap(),
+            ArcCStr::try_from("a").unwrap(),
+        ];
+        let (json, back) = round_trip(&strings);
+        assert_eq!(json, r#"[["a","b"],[0,1,0]]"#);
+        assert_eq!(back, strings);
+        assert!(ArcCStr::ptr_eq(&back[0], &back[2]));
+    }
+
+    #[test]
+    fn index_out_of_range() {
+        let mut de = serde_json::Deserializer::from_str(r#"[["a"],[0,1]]"#);
+        let err = super::deserialize(&mut de).unwrap_err();
+        assert!(err.to_string().contains("index 1 out of range"));
+    }
+}
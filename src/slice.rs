@@ -0,0 +1,326 @@
+use crate::ArcCStr;
+use core::cmp::Ordering;
+use core::ffi::CStr;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::{Bound, Deref, RangeBounds};
+
+/// A suffix of an [`ArcCStr`] that shares its allocation.
+///
+/// Every suffix of a null-terminated string is itself a null-terminated string, so a suffix can
+/// be used anywhere a `CStr` is expected without copying. It holds a reference to the whole
+/// string, which stays alive for as long as the suffix does. It is created by
+/// [`ArcCStr::suffix`].
+///
+/// Suffixes are compared, ordered, and hashed by their contents, like `CStr`.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCStr;
+///
+/// let line = ArcCStr::try_from("GET /index.html").unwrap();
+/// let path = line.suffix(4);
+/// assert_eq!(path.to_bytes(), b"/index.html");
+/// assert!(ArcCStr::ptr_eq(path.parent(), &line));
+/// ```
+#[derive(Clone)]
+pub struct ArcCStrSuffix {
+    parent: ArcCStr,
+    offset: usize,
+}
+
+/// A range of the bytes of an [`ArcCStr`] that shares its allocation.
+///
+/// Unlike an [`ArcCStrSuffix`], a slice that ends before the end of the string has no null
+/// terminator, so it dereferences to `[u8]` rather than `CStr`. It holds a reference to the whole
+/// string, which stays alive for as long as the slice does. It is created by
+/// [`ArcCStr::slice`].
+///
+/// Slices are compared, ordered, and hashed by their contents, like `[u8]`.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCStr;
+///
+/// let line = ArcCStr::try_from("GET /index.html HTTP/1.1").unwrap();
+/// let tokens: Vec<_> = line.split_slices(b' ').collect();
+/// assert_eq!(&*tokens[1], b"/index.html");
+/// assert!(ArcCStr::ptr_eq(tokens[1].parent(), &line));
+/// ```
+#[derive(Clone)]
+pub struct ArcCStrSlice {
+    parent: ArcCStr,
+    start: usize,
+    end: usize,
+}
+
+impl ArcCStr {
+    /// Returns the part of the string from byte `offset` on, sharing this string's allocation.
+    ///
+    /// This only increments the reference count; the string is not copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is greater than the length of the string.
+    pub fn suffix(&self, offset: usize) -> ArcCStrSuffix {
+        assert!(
+            offset <= self.len(),
+            "suffix offset {} is out of range for a string of length {}",
+            offset,
+            self.len()
+        );
+        ArcCStrSuffix {
+            parent: ArcCStr::clone(self),
+            offset,
+        }
+    }
+
+    /// Returns the bytes of the string in `range`, sharing this string's allocation.
+    ///
+    /// This only increments the reference count; the string is not copied.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds, like slicing [`to_bytes`](CStr::to_bytes) would.
+    pub fn slice<R: RangeBounds<usize>>(&self, range: R) -> ArcCStrSlice {
+        let start = match range.start_bound() {
+            Bound::Included(&i) => i,
+            Bound::Excluded(&i) => i.checked_add(1).expect("range start overflows"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&i) => i.checked_add(1).expect("range end overflows"),
+            Bound::Excluded(&i) => i,
+            Bound::Unbounded => self.len(),
+        };
+        // panics just like slicing would
+        let _ = &self.to_bytes()[start..end];
+        ArcCStrSlice {
+            parent: ArcCStr::clone(self),
+            start,
+            end,
+        }
+    }
+
+    /// Returns an iterator over the parts of the string separated by `sep`, each sharing this
+    /// string's allocation.
+    ///
+    /// Like [`slice::split`], this yields empty parts between adjacent separators and at either
+    /// end.
+    pub fn split_slices(&self, sep: u8) -> impl Iterator<Item = ArcCStrSlice> + '_ {
+        let mut start = 0;
+        self.to_bytes().split(move |&b| b == sep).map(move |part| {
+            let slice = self.slice(start..start + part.len());
+            start += part.len() + 1;
+            slice
+        })
+    }
+}
+
+impl ArcCStrSuffix {
+    /// Returns the suffix as a `&CStr`.
+    #[inline]
+    pub fn as_c_str(&self) -> &CStr {
+        let bytes = &self.parent.to_bytes_with_nul()[self.offset..];
+        // a suffix of a C string that still includes its terminator is itself a C string
+        unsafe { CStr::from_bytes_with_nul_unchecked(bytes) }
+    }
+
+    /// Returns the whole string that this is a suffix of.
+    #[inline]
+    pub fn parent(&self) -> &ArcCStr {
+        &self.parent
+    }
+
+    /// Returns the position in [`parent`](ArcCStrSuffix::parent) where the suffix starts.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl ArcCStrSlice {
+    /// Returns the bytes of the slice.
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.parent.to_bytes()[self.start..self.end]
+    }
+
+    /// Returns the whole string that this is a slice of.
+    #[inline]
+    pub fn parent(&self) -> &ArcCStr {
+        &self.parent
+    }
+
+    /// Returns the range of [`parent`](ArcCStrSlice::parent) that the slice covers.
+    #[inline]
+    pub fn range(&self) -> core::ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// Copies the slice into a new `ArcCStr` of its own.
+    pub fn to_arc_cstr(&self) -> ArcCStr {
+        // a part of a string that contains no \0 contains no \0 either
+        unsafe { ArcCStr::from_bytes_unchecked(self.as_bytes()) }
+    }
+}
+
+impl Deref for ArcCStrSuffix {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl Deref for ArcCStrSlice {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl AsRef<CStr> for ArcCStrSuffix {
+    fn as_ref(&self) -> &CStr {
+        self
+    }
+}
+
+impl AsRef<[u8]> for ArcCStrSlice {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<ArcCStrSuffix> for ArcCStrSlice {
+    fn from(suffix: ArcCStrSuffix) -> Self {
+        ArcCStrSlice {
+            end: suffix.parent.len(),
+            start: suffix.offset,
+            parent: suffix.parent,
+        }
+    }
+}
+
+impl PartialEq for ArcCStrSuffix {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_c_str() == other.as_c_str()
+    }
+}
+impl Eq for ArcCStrSuffix {}
+
+impl PartialOrd for ArcCStrSuffix {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArcCStrSuffix {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_c_str().cmp(other.as_c_str())
+    }
+}
+
+impl Hash for ArcCStrSuffix {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_c_str().hash(state)
+    }
+}
+
+impl PartialEq for ArcCStrSlice {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+impl Eq for ArcCStrSlice {}
+
+impl PartialOrd for ArcCStrSlice {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArcCStrSlice {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl Hash for ArcCStrSlice {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state)
+    }
+}
+
+impl fmt::Debug for ArcCStrSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl fmt::Debug for ArcCStrSlice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"")?;
+        for &b in self.as_bytes() {
+            fmt::Display::fmt(&core::ascii::escape_default(b), f)?;
+        }
+        f.write_str("\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn suffix() {
+        let s = ArcCStr::try_from("abc").unwrap();
+        assert_eq!(s.suffix(0).to_bytes(), b"abc");
+        assert_eq!(s.suffix(2).to_bytes_with_nul(), b"c\0");
+        assert!(s.suffix(3).to_bytes().is_empty());
+        assert_eq!(ArcCStr::strong_count(&s), 1);
+        let suffix = s.suffix(1);
+        assert_eq!(ArcCStr::strong_count(&s), 2);
+        drop(s);
+        assert_eq!(format!("{:?}", suffix), "\"bc\"");
+        assert_eq!(suffix, ArcCStr::try_from("xbc").unwrap().suffix(1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn suffix_out_of_range() {
+        ArcCStr::try_from("abc").unwrap().suffix(4);
+    }
+
+    #[test]
+    fn slice() {
+        let s = ArcCStr::try_from("a,b\t,,c").unwrap();
+        assert_eq!(&*s.slice(..), b"a,b\t,,c");
+        assert_eq!(&*s.slice(2..=3), b"b\t");
+        assert_eq!(s.slice(2..4).range(), 2..4);
+        assert_eq!(format!("{:?}", s.slice(2..4)), "\"b\\t\"");
+        let parts: Vec<_> = s.split_slices(b',').collect();
+        let parts: Vec<&[u8]> = parts.iter().map(|p| &**p).collect();
+        assert_eq!(parts, [&b"a"[..], b"b\t", b"", b"c"]);
+
+        assert_eq!(s.slice(2..3).to_arc_cstr().to_bytes(), b"b");
+        assert_eq!(s.slice(6..).to_arc_cstr().to_bytes(), b"c");
+        let slice: crate::ArcCStrSlice = s.suffix(6).into();
+        assert_eq!(slice, s.slice(6..));
+        assert_eq!(ArcCStr::try_from("").unwrap().split_slices(b',').count(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn slice_out_of_range() {
+        ArcCStr::try_from("abc").unwrap().slice(2..4);
+    }
+}
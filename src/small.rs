@@ -0,0 +1,171 @@
+use crate::{ArcCStr, ArcCStrError};
+use core::convert::TryFrom;
+use core::ffi::CStr;
+use core::fmt;
+use core::ops::Deref;
+
+/// A fixed-capacity, stack-allocated null-terminated string.
+///
+/// `SmallCStr<N>` holds up to `N - 1` bytes of string data followed by a `\0` terminator in an
+/// inline `[u8; N]` buffer, so building one never touches the heap. This makes it a good fit for
+/// temporary C strings that only live for the duration of a single FFI call. If the string turns
+/// out to be needed for longer, it can be promoted to an [`ArcCStr`] with [`to_arc`].
+///
+/// `SmallCStr` dereferences to [`CStr`], just like `ArcCStr`.
+///
+/// [`to_arc`]: SmallCStr::to_arc
+///
+/// # Examples
+///
+/// ```
+/// use arccstr::SmallCStr;
+/// use std::convert::TryFrom;
+///
+/// let path = SmallCStr::<16>::try_from("/tmp/foo").unwrap();
+/// assert_eq!(path.to_bytes(), b"/tmp/foo");
+///
+/// // keep it around past the syscall
+/// let shared = path.to_arc();
+/// assert_eq!(&*shared, &*path);
+///
+/// // too long for the buffer (15 bytes + \0)
+/// assert!(SmallCStr::<16>::try_from("/tmp/a-much-longer-path").is_err());
+/// ```
+#[derive(Clone, Copy)]
+pub struct SmallCStr<const N: usize> {
+    len: usize,
+    buf: [u8; N],
+}
+
+impl<const N: usize> SmallCStr<N> {
+    // A zero-sized buffer has no room for the terminator.
+    const NONEMPTY: () = assert!(N > 0, "SmallCStr<N> requires N > 0");
+
+    /// Constructs a new, empty `SmallCStr`.
+    pub const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::NONEMPTY;
+        SmallCStr {
+            len: 0,
+            buf: [0; N],
+        }
+    }
+
+    /// Constructs a `SmallCStr` from `bytes`.
+    ///
+    /// Fails if `bytes` does not fit in the buffer along with its terminator, or if it contains
+    /// an internal `\0`.
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, ArcCStrError> {
+        let mut s = Self::new();
+        if bytes.len() >= N {
+            return Err(ArcCStrError::TooLong {
+                len: bytes.len(),
+                max_len: N - 1,
+            });
+        }
+        ArcCStrError::check_no_nul(bytes)?;
+        s.buf[..bytes.len()].copy_from_slice(bytes);
+        s.len = bytes.len();
+        Ok(s)
+    }
+
+    /// The maximum number of bytes (excluding the terminator) this `SmallCStr` can hold.
+    pub const fn capacity(&self) -> usize {
+        N - 1
+    }
+
+    /// Copies this string into a new heap-allocated [`ArcCStr`].
+    pub fn to_arc(&self) -> ArcCStr {
+        ArcCStr::from(&**self)
+    }
+}
+
+impl<const N: usize> Default for SmallCStr<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deref for SmallCStr<N> {
+    type Target = CStr;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        // we always keep a \0 at buf[len], and never let a \0 into buf[..len]
+        unsafe { CStr::from_bytes_with_nul_unchecked(&self.buf[..=self.len]) }
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a [u8]> for SmallCStr<N> {
+    type Error = ArcCStrError;
+    fn try_from(b: &'a [u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(b)
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a str> for SmallCStr<N> {
+    type Error = ArcCStrError;
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(s.as_bytes())
+    }
+}
+
+impl<'a, const N: usize> From<&'a SmallCStr<N>> for ArcCStr {
+    fn from(s: &'a SmallCStr<N>) -> Self {
+        s.to_arc()
+    }
+}
+
+impl<const N: usize> fmt::Debug for SmallCStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<const N: usize> PartialEq for SmallCStr<N> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+impl<const N: usize> Eq for SmallCStr<N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::SmallCStr;
+    use crate::{ArcCStr, ArcCStrError};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn fits_exactly() {
+        let s = SmallCStr::<4>::try_from("abc").unwrap();
+        assert_eq!(s.to_bytes_with_nul(), b"abc\0");
+        assert_eq!(s.capacity(), 3);
+        assert_eq!(
+            SmallCStr::<4>::try_from("abcd"),
+            Err(ArcCStrError::TooLong { len: 4, max_len: 3 })
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let s = SmallCStr::<1>::new();
+        assert_eq!(s.to_bytes_with_nul(), b"\0");
+        assert_eq!(s, SmallCStr::<1>::try_from("").unwrap());
+    }
+
+    #[test]
+    fn rejects_nul() {
+        assert_eq!(
+            SmallCStr::<8>::try_from(&b"a\0b"[..]),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+
+    #[test]
+    fn promote() {
+        let s = SmallCStr::<8>::try_from("hello").unwrap();
+        let a = s.to_arc();
+        assert_eq!(a, ArcCStr::try_from("hello").unwrap());
+        assert_eq!(ArcCStr::strong_count(&a), 1);
+    }
+}
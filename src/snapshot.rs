@@ -0,0 +1,82 @@
+use crate::ArcCStr;
+use alloc::collections::BTreeMap;
+use core::hash::{BuildHasher, Hasher};
+
+/// Hashes a whole collection of strings, reading each shared allocation only once.
+///
+/// This is meant for fingerprinting rows or snapshots that repeat the same few shared strings
+/// many times. The result depends only on the contents and order of `strings`, not on which of
+/// them share an allocation, and is computed as follows:
+///
+///  1. Each string gets a digest: the output of a fresh hasher from `hasher` that is given the
+///     string's length (with `write_usize`) followed by its bytes (with `write`), excluding the
+///     null terminator.
+///  2. The result is the output of another fresh hasher that is given the number of strings
+///     (with `write_usize`) followed by the digest of each string in order (with `write_u64`).
+///
+/// The digest of a string is computed once per allocation, so strings that are
+/// [`ptr_eq`](ArcCStr::ptr_eq) to an earlier one are not read again. Since the steps above are
+/// fixed, a deterministic hasher gives the same result across runs and processes, although
+/// `write_usize` makes it depend on the target's pointer width.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::convert::TryFrom;
+/// use std::hash::BuildHasherDefault;
+/// use arccstr::ArcCStr;
+///
+/// let hasher = BuildHasherDefault::<DefaultHasher>::default();
+/// let a = ArcCStr::try_from("a").unwrap();
+/// let shared = [a.clone(), a.clone(), a];
+/// let copies = ["a", "a", "a"].map(|s| ArcCStr::try_from(s).unwrap());
+/// assert_eq!(
+///     arccstr::hash_collection(&shared, &hasher),
+///     arccstr::hash_collection(&copies, &hasher),
+/// );
+/// ```
+pub fn hash_collection<B: BuildHasher>(strings: &[ArcCStr], hasher: &B) -> u64 {
+    let mut digests = BTreeMap::new();
+    let mut h = hasher.build_hasher();
+    h.write_usize(strings.len());
+    for s in strings {
+        let digest = *digests.entry(ArcCStr::sort_key(s)).or_insert_with(|| {
+            let mut h = hasher.build_hasher();
+            h.write_usize(s.len());
+            h.write(s.to_bytes());
+            h.finish()
+        });
+        h.write_u64(digest);
+    }
+    h.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hash_collection;
+    use crate::ArcCStr;
+    use std::collections::hash_map::DefaultHasher;
+    use std::convert::TryFrom;
+    use std::hash::BuildHasherDefault;
+
+    fn strings(s: &[&str]) -> Vec<ArcCStr> {
+        s.iter().map(|&s| ArcCStr::try_from(s).unwrap()).collect()
+    }
+
+    #[test]
+    fn hash() {
+        let hasher = BuildHasherDefault::<DefaultHasher>::default();
+        let h = |s: &[&str]| hash_collection(&strings(s), &hasher);
+        assert_eq!(h(&["a", "bc"]), h(&["a", "bc"]));
+        assert_ne!(h(&["a", "bc"]), h(&["bc", "a"]));
+        assert_ne!(h(&["a", "bc"]), h(&["ab", "c"]));
+        assert_ne!(h(&[]), h(&[""]));
+        assert_ne!(h(&[""]), h(&["", ""]));
+
+        let a = ArcCStr::try_from("a").unwrap();
+        let b = ArcCStr::try_from("b").unwrap();
+        let shared = [a.clone(), b, a];
+        assert_eq!(hash_collection(&shared, &hasher), h(&["a", "b", "a"]));
+    }
+}
@@ -0,0 +1,187 @@
+use crate::ArcCStr;
+use core::ffi::{c_char, CStr};
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+/// A pointer to an [`ArcCStr`], for passing as an argument to a variadic C function.
+///
+/// This is returned by [`ArcCStr::as_fmt_arg`]. It has the same representation as
+/// `*const c_char`, so it can be passed wherever a `printf`-style function expects a `%s`
+/// argument, but it borrows the string it points to. That makes it impossible to keep the
+/// pointer around after the string is dropped, which is easy to do by accident with
+/// [`CStr::as_ptr`].
+#[repr(transparent)]
+#[derive(Clone, Copy)]
+pub struct FmtArg<'a> {
+    ptr: *const c_char,
+    _string: PhantomData<&'a CStr>,
+}
+
+impl FmtArg<'_> {
+    /// Returns the pointer to the string.
+    pub fn as_ptr(self) -> *const c_char {
+        self.ptr
+    }
+}
+
+impl fmt::Debug for FmtArg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.ptr, f)
+    }
+}
+
+/// Keeps an [`ArcCStr`] alive while C code holds on to a pointer to it.
+///
+/// C APIs that register callbacks often keep the pointers they are given and only use them
+/// later, after the call that handed them over has returned. A guard holds a reference to the
+/// string, so that its [`as_ptr`](ArcCStrGuard::as_ptr) stays valid until the guard is dropped;
+/// store it next to whatever keeps the callback registered, and drop it once the callback can
+/// no longer run. It is created by [`ArcCStr::guard`].
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCStr;
+///
+/// let name = ArcCStr::try_from("worker-1").unwrap();
+/// let guard = name.guard();
+/// let retained_by_c = guard.as_ptr();
+/// drop(name);
+/// // still valid, since the guard holds a reference
+/// assert_eq!(unsafe { std::ffi::CStr::from_ptr(retained_by_c) }.to_bytes(), b"worker-1");
+/// drop(guard);
+/// ```
+#[must_use = "the string may be freed as soon as the guard is dropped"]
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct ArcCStrGuard {
+    s: ArcCStr,
+}
+
+impl ArcCStrGuard {
+    /// Returns the pointer to the string, which stays valid for as long as the guard lives.
+    pub fn as_ptr(&self) -> *const c_char {
+        self.s.as_ptr()
+    }
+
+    /// Returns the string the guard holds on to.
+    pub fn into_inner(self) -> ArcCStr {
+        self.s
+    }
+}
+
+impl Deref for ArcCStrGuard {
+    type Target = ArcCStr;
+
+    fn deref(&self) -> &ArcCStr {
+        &self.s
+    }
+}
+
+impl fmt::Debug for ArcCStrGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ArcCStrGuard").field(&self.s).finish()
+    }
+}
+
+impl ArcCStr {
+    /// Returns a pointer to the string for passing to a variadic C function, such as `printf`.
+    ///
+    /// The pointer is valid for as long as the `FmtArg` can be used, which the borrow checker
+    /// makes sure is no longer than this `ArcCStr` lives. A temporary `ArcCStr` lives until the
+    /// end of the statement that created it, so it can be formatted in the same call.
+    ///
+    /// If the C function keeps the pointer after it returns, use [`guard`](ArcCStr::guard)
+    /// instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(unix)] {
+    /// use std::convert::TryFrom;
+    /// use std::ffi::CStr;
+    /// use std::os::raw::{c_char, c_int};
+    /// use arccstr::ArcCStr;
+    ///
+    /// extern "C" {
+    ///     fn snprintf(buf: *mut c_char, len: usize, fmt: *const c_char, ...) -> c_int;
+    /// }
+    ///
+    /// let mut buf = [0 as c_char; 32];
+    /// let fmt = b"hello, %s!\0".as_ptr() as *const c_char;
+    /// let name = ArcCStr::try_from("world").unwrap();
+    /// unsafe { snprintf(buf.as_mut_ptr(), buf.len(), fmt, name.as_fmt_arg()) };
+    /// assert_eq!(unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes(), b"hello, world!");
+    /// # }
+    /// ```
+    pub fn as_fmt_arg(&self) -> FmtArg<'_> {
+        FmtArg {
+            ptr: self.as_ptr(),
+            _string: PhantomData,
+        }
+    }
+
+    /// Returns a guard that keeps the string alive, for C code that holds on to a pointer to it.
+    ///
+    /// See [`ArcCStrGuard`].
+    pub fn guard(&self) -> ArcCStrGuard {
+        ArcCStrGuard {
+            s: ArcCStr::clone(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    #[test]
+    fn fmt_arg() {
+        let s = ArcCStr::try_from("abc").unwrap();
+        assert_eq!(s.as_fmt_arg().as_ptr(), s.as_ptr());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn snprintf() {
+        use std::os::raw::c_int;
+        extern "C" {
+            fn snprintf(buf: *mut c_char, len: usize, fmt: *const c_char, ...) -> c_int;
+        }
+
+        let mut buf = [0 as c_char; 32];
+        let fmt = b"%s=%s\0".as_ptr() as *const c_char;
+        let key = ArcCStr::try_from("key").unwrap();
+        let n = unsafe {
+            snprintf(
+                buf.as_mut_ptr(),
+                buf.len(),
+                fmt,
+                key.as_fmt_arg(),
+                // a temporary lives until the end of the call
+                ArcCStr::try_from("value").unwrap().as_fmt_arg(),
+            )
+        };
+        assert_eq!(n, 9);
+        assert_eq!(
+            unsafe { CStr::from_ptr(buf.as_ptr()) }.to_bytes(),
+            b"key=value"
+        );
+    }
+
+    #[test]
+    fn guard() {
+        let s = ArcCStr::try_from("abc").unwrap();
+        let guard = s.guard();
+        assert_eq!(ArcCStr::strong_count(&s), 2);
+        assert_eq!(guard.as_ptr(), s.as_ptr());
+        assert_eq!(*guard, s);
+        drop(guard);
+        assert_eq!(ArcCStr::strong_count(&s), 1);
+        assert!(ArcCStr::ptr_eq(&s.guard().into_inner(), &s));
+    }
+}
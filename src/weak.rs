@@ -0,0 +1,156 @@
+use crate::raw::RawArcCStr;
+use crate::ArcCStr;
+use core::fmt;
+
+/// A weak reference to an [`ArcCStr`] string.
+///
+/// `WeakCStr` is to `ArcCStr` what [`std::sync::Weak`] is to [`std::sync::Arc`]: it does not keep
+/// the string alive, and must be [`upgrade`](WeakCStr::upgrade)d to an `ArcCStr` to access the
+/// string, which fails if every `ArcCStr` pointing to the string has been dropped. This is useful
+/// for caches that should not keep strings around on their own, such as a
+/// [`WeakCStrMap`](crate::WeakCStrMap).
+///
+/// A `WeakCStr` is created with [`ArcCStr::downgrade`]. The string's allocation is only freed
+/// once all `ArcCStr`s and `WeakCStr`s pointing to it have been dropped.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCStr;
+///
+/// let five = ArcCStr::try_from("5").unwrap();
+/// let weak_five = ArcCStr::downgrade(&five);
+///
+/// assert_eq!(weak_five.upgrade().unwrap(), b"5");
+/// drop(five);
+/// assert!(weak_five.upgrade().is_none());
+/// ```
+pub struct WeakCStr {
+    raw: RawArcCStr,
+}
+
+unsafe impl Send for WeakCStr {}
+unsafe impl Sync for WeakCStr {}
+
+impl WeakCStr {
+    /// Takes over one weak reference held through `raw`.
+    pub(crate) unsafe fn from_raw_arc(raw: RawArcCStr) -> Self {
+        WeakCStr { raw }
+    }
+
+    /// Attempts to upgrade to an [`ArcCStr`].
+    ///
+    /// Returns `None` if the string has no strong pointers left.
+    pub fn upgrade(&self) -> Option<ArcCStr> {
+        unsafe {
+            if self.raw.try_increment_strong_count() {
+                let s = ArcCStr::from_raw_arc(self.raw);
+                #[cfg(feature = "alloc-observer")]
+                crate::observer::clone(&s);
+                Some(s)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// Gets the number of [`ArcCStr`] pointers to the string.
+    pub fn strong_count(&self) -> usize {
+        unsafe { self.raw.strong_count() }
+    }
+
+    /// Gets the number of `WeakCStr` pointers to the string, or zero if it has no strong pointers
+    /// left.
+    pub fn weak_count(&self) -> usize {
+        unsafe {
+            let weak = self.raw.weak_count();
+            if self.raw.strong_count() > 0 {
+                // one weak reference is held collectively by the strong ones
+                weak - 1
+            } else {
+                0
+            }
+        }
+    }
+
+    /// Returns true if the two `WeakCStr`s point to the same allocation.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl Clone for WeakCStr {
+    fn clone(&self) -> Self {
+        unsafe {
+            self.raw.increment_weak_count();
+            WeakCStr::from_raw_arc(self.raw)
+        }
+    }
+}
+
+impl Drop for WeakCStr {
+    fn drop(&mut self) {
+        unsafe {
+            if self.raw.decrement_weak_count() {
+                self.raw.deallocate();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for WeakCStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(WeakCStr)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn counts() {
+        let s = ArcCStr::try_from("foo").unwrap();
+        let w = ArcCStr::downgrade(&s);
+        let w2 = w.clone();
+        assert_eq!(ArcCStr::weak_count(&s), 2);
+        assert_eq!(w.strong_count(), 1);
+        assert_eq!(w.weak_count(), 2);
+        assert!(w.ptr_eq(&w2));
+
+        let s2 = w.upgrade().unwrap();
+        assert_eq!(ArcCStr::strong_count(&s), 2);
+        drop(s);
+        drop(s2);
+        assert_eq!(w.strong_count(), 0);
+        assert_eq!(w.weak_count(), 0);
+        assert!(w2.upgrade().is_none());
+    }
+
+    #[test]
+    fn outlives_string() {
+        // the last weak reference frees the allocation
+        let w = ArcCStr::downgrade(&ArcCStr::try_from("foo").unwrap());
+        assert!(w.upgrade().is_none());
+        drop(w);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn upgrade_races_drop() {
+        use std::thread;
+        for _ in 0..100 {
+            let s = ArcCStr::try_from("race").unwrap();
+            let w = ArcCStr::downgrade(&s);
+            let t = thread::spawn(move || {
+                if let Some(s) = w.upgrade() {
+                    assert_eq!(s, b"race");
+                }
+            });
+            drop(s);
+            t.join().unwrap();
+        }
+    }
+}
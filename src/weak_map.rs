@@ -0,0 +1,243 @@
+use crate::{ArcCStr, WeakCStr};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::mem;
+
+/// A map from strings to values that does not keep its keys alive.
+///
+/// Each key is held as a [`WeakCStr`], so a `WeakCStrMap` can attach metadata to strings that
+/// are owned elsewhere without pinning them in memory. Once every [`ArcCStr`] pointing to a key
+/// has been dropped, its entry acts as if it had been removed, and is evicted the next time the
+/// map touches it, or during one of the occasional sweeps that inserting triggers. Only keys are
+/// weak; a value is dropped when its entry is evicted.
+///
+/// Keys are looked up by content, like in a `HashMap<ArcCStr, V>`, so any string with the same
+/// bytes finds the entry, whether or not it shares the key's allocation.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::{ArcCStr, WeakCStrMap};
+///
+/// let mut lengths = WeakCStrMap::new();
+/// let name = ArcCStr::try_from("config.toml").unwrap();
+/// lengths.insert(&name, 512);
+/// assert_eq!(lengths.get("config.toml"), Some(&512));
+///
+/// // the map does not keep the key alive, and forgets it once it is gone
+/// drop(name);
+/// assert_eq!(lengths.get("config.toml"), None);
+/// lengths.remove_dead();
+/// assert!(lengths.is_empty());
+/// ```
+pub struct WeakCStrMap<V> {
+    // entries grouped by the hash of their key's contents, since a dead key cannot be hashed again
+    buckets: HashMap<u64, Vec<(WeakCStr, V)>>,
+    hasher: RandomState,
+    len: usize,
+    // the length at which inserting next sweeps out every dead entry
+    sweep_at: usize,
+}
+
+impl<V> WeakCStrMap<V> {
+    /// Constructs an empty `WeakCStrMap`.
+    pub fn new() -> Self {
+        WeakCStrMap {
+            buckets: HashMap::new(),
+            hasher: RandomState::new(),
+            len: 0,
+            sweep_at: 16,
+        }
+    }
+
+    // BuildHasher::hash_one is newer than our minimum supported Rust version
+    #[allow(clippy::manual_hash_one)]
+    fn hash(&self, key: &[u8]) -> u64 {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Removes the entries in `bucket` whose keys have died, and returns the position of the one
+    /// whose key is `key`, if any.
+    fn evict_and_find(
+        bucket: &mut Vec<(WeakCStr, V)>,
+        len: &mut usize,
+        key: &[u8],
+    ) -> Option<usize> {
+        let before = bucket.len();
+        bucket.retain(|(k, _)| k.strong_count() != 0);
+        *len -= before - bucket.len();
+        bucket.iter().position(|(k, _)| is_live_key(k, key))
+    }
+
+    /// Inserts `value` under `key`, returning the value previously stored under an equal key.
+    ///
+    /// The entry holds a weak reference to `key` itself from now on, even if it was already
+    /// present under an equal string in another allocation.
+    pub fn insert(&mut self, key: &ArcCStr, value: V) -> Option<V> {
+        if self.len >= self.sweep_at {
+            self.remove_dead();
+            self.sweep_at = usize::max(2 * self.len, 16);
+        }
+
+        let hash = self.hash(key.to_bytes());
+        let bucket = self.buckets.entry(hash).or_default();
+        let weak = ArcCStr::downgrade(key);
+        match Self::evict_and_find(bucket, &mut self.len, key.to_bytes()) {
+            Some(i) => {
+                let (k, v) = &mut bucket[i];
+                *k = weak;
+                Some(mem::replace(v, value))
+            }
+            None => {
+                bucket.push((weak, value));
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns the value stored under a live key equal to `key`.
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> Option<&V> {
+        let key = key.as_ref();
+        self.buckets
+            .get(&self.hash(key))?
+            .iter()
+            .find(|(k, _)| is_live_key(k, key))
+            .map(|(_, v)| v)
+    }
+
+    /// Returns the value stored under a live key equal to `key`, for modifying it.
+    pub fn get_mut<K: AsRef<[u8]>>(&mut self, key: K) -> Option<&mut V> {
+        let key = key.as_ref();
+        let bucket = self.buckets.get_mut(&self.hash(key))?;
+        let i = Self::evict_and_find(bucket, &mut self.len, key)?;
+        Some(&mut bucket[i].1)
+    }
+
+    /// Removes the entry under a live key equal to `key`, and returns its value.
+    pub fn remove<K: AsRef<[u8]>>(&mut self, key: K) -> Option<V> {
+        let key = key.as_ref();
+        let hash = self.hash(key);
+        let bucket = self.buckets.get_mut(&hash)?;
+        let found =
+            Self::evict_and_find(bucket, &mut self.len, key).map(|i| bucket.swap_remove(i).1);
+        if found.is_some() {
+            self.len -= 1;
+        }
+        if bucket.is_empty() {
+            self.buckets.remove(&hash);
+        }
+        found
+    }
+
+    /// Evicts every entry whose key has died.
+    pub fn remove_dead(&mut self) {
+        let len = &mut self.len;
+        self.buckets.retain(|_, bucket| {
+            let before = bucket.len();
+            bucket.retain(|(k, _)| k.strong_count() != 0);
+            *len -= before - bucket.len();
+            !bucket.is_empty()
+        });
+    }
+
+    /// Returns the number of entries, including those whose keys have died but that have not been
+    /// evicted yet.
+    ///
+    /// Call [`remove_dead`](WeakCStrMap::remove_dead) first for the number of live entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the map has no entries, including entries whose keys have died.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the entries whose keys are still alive, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (ArcCStr, &V)> + '_ {
+        self.buckets
+            .values()
+            .flatten()
+            .filter_map(|(k, v)| Some((k.upgrade()?, v)))
+    }
+}
+
+/// Returns true if `k` is still alive, and equal to `key`.
+fn is_live_key(k: &WeakCStr, key: &[u8]) -> bool {
+    // an entry whose key has died counts as removed, even before it is evicted
+    match k.upgrade() {
+        Some(k) => k.to_bytes() == key,
+        None => false,
+    }
+}
+
+impl<V> Default for WeakCStrMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for WeakCStrMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeakCStrMap;
+    use crate::ArcCStr;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn evicts_dead_keys() {
+        let mut map = WeakCStrMap::new();
+        let a = ArcCStr::try_from("a").unwrap();
+        let b = ArcCStr::try_from("b").unwrap();
+        assert_eq!(map.insert(&a, 1), None);
+        assert_eq!(map.insert(&b, 2), None);
+        assert_eq!(map.len(), 2);
+
+        // found by content, from any allocation
+        let other_a = ArcCStr::try_from("a").unwrap();
+        assert_eq!(map.get(other_a.to_bytes()), Some(&1));
+        assert_eq!(map.insert(&other_a, 3), Some(1));
+        // the entry now follows the newer key
+        drop(a);
+        *map.get_mut("a").unwrap() += 1;
+        assert_eq!(map.get("a"), Some(&4));
+
+        drop(b);
+        assert_eq!(map.get("b"), None);
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.len(), 1);
+        let live: Vec<_> = map.iter().collect();
+        assert_eq!(live, [(ArcCStr::clone(&other_a), &4)]);
+        assert_eq!(format!("{:?}", map), "{\"a\": 4}");
+
+        assert_eq!(map.remove("a"), Some(4));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn sweeps_while_inserting() {
+        let mut map = WeakCStrMap::new();
+        let keep = ArcCStr::try_from("keep").unwrap();
+        map.insert(&keep, ());
+        for i in 0..1000 {
+            map.insert(&ArcCStr::try_from(i.to_string()).unwrap(), ());
+        }
+        // dead entries never pile up to more than twice the live ones, plus the initial slack
+        assert!(map.len() <= 32);
+        map.remove_dead();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get("keep"), Some(&()));
+    }
+}
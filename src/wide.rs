@@ -0,0 +1,333 @@
+use crate::raw::RawArcCStr;
+use crate::ArcCStrError;
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::fmt::{self, Write};
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::slice;
+#[cfg(feature = "std")]
+use std::ffi::OsStr;
+
+/// A thread-safe reference-counted null-terminated UTF-16 string, for Windows APIs.
+///
+/// `ArcCWStr` is the wide counterpart of [`ArcCStr`](crate::ArcCStr): it holds `u16` code units
+/// followed by a `0` unit, in the same kind of reference-counted allocation, and is cloned,
+/// shared, and dropped the same way. A string is encoded as UTF-16 once, when the `ArcCWStr` is
+/// created, and [`as_ptr`](ArcCWStr::as_ptr) can then be passed to any number of calls that take
+/// an `LPCWSTR`.
+///
+/// It dereferences to the units, excluding the terminator, as a `[u16]`. Like a wide string
+/// from Windows itself, it is not required to be valid UTF-16, but cannot contain a `0` unit
+/// before its terminator.
+///
+/// # Examples
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use arccstr::ArcCWStr;
+///
+/// let title = ArcCWStr::try_from("Grüße").unwrap();
+/// assert_eq!(title.len(), 5);
+/// assert_eq!(title.as_slice_with_nul().last(), Some(&0));
+/// assert_eq!(title.to_string_lossy(), "Grüße");
+///
+/// // what a call like `SetWindowTextW(hwnd, title.as_ptr())` would read
+/// let units = unsafe { std::slice::from_raw_parts(title.as_ptr(), 6) };
+/// assert_eq!(units, &[0x47, 0x72, 0xfc, 0xdf, 0x65, 0][..]);
+/// ```
+pub struct ArcCWStr {
+    raw: RawArcCStr,
+}
+
+// the string is immutable, and its counts are atomic
+unsafe impl Send for ArcCWStr {}
+unsafe impl Sync for ArcCWStr {}
+
+impl ArcCWStr {
+    /// Constructs a new `ArcCWStr` holding the `len` units yielded by `units`.
+    ///
+    /// # Safety
+    ///
+    /// `units` must yield at least `len` units, the first `len` of which must not be `0`.
+    unsafe fn from_units_unchecked<I: Iterator<Item = u16>>(
+        len: usize,
+        units: I,
+    ) -> Result<Self, ArcCStrError> {
+        // the second byte of the terminator counts towards the length the header has to hold
+        ArcCStrError::check_len_at_most(len.saturating_mul(2), RawArcCStr::MAX_LEN - 1)?;
+        let raw = RawArcCStr::try_allocate_wide(len, units).ok_or(ArcCStrError::Alloc)?;
+        Ok(ArcCWStr { raw })
+    }
+
+    /// Returns a pointer to the first unit of the string, which is followed by a `0` unit.
+    ///
+    /// The pointer is valid for as long as `self`, or a clone of it, is alive.
+    #[inline]
+    pub fn as_ptr(&self) -> *const u16 {
+        self.raw.data().as_ptr() as *const u16
+    }
+
+    /// Returns the units of the string, including the `0` unit that terminates it.
+    #[inline]
+    pub fn as_slice_with_nul(&self) -> &[u16] {
+        // the terminator follows the units
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len() + 1) }
+    }
+
+    /// Decodes the string into a `String`, replacing invalid UTF-16 with
+    /// `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self)
+    }
+
+    /// Decodes the string into an `OsString`, which is lossless on Windows.
+    ///
+    /// Elsewhere, this is the same as [`to_string_lossy`](ArcCWStr::to_string_lossy).
+    #[cfg(feature = "std")]
+    pub fn to_os_string(&self) -> std::ffi::OsString {
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStringExt;
+            std::ffi::OsString::from_wide(self)
+        }
+        #[cfg(not(windows))]
+        {
+            std::ffi::OsString::from(self.to_string_lossy())
+        }
+    }
+
+    /// Gets the number of pointers to this string.
+    ///
+    /// Like [`ArcCStr::strong_count`](crate::ArcCStr::strong_count), the result may be out of
+    /// date by the time it is used, if the string is shared with other threads.
+    #[inline]
+    pub fn strong_count(this: &Self) -> usize {
+        unsafe { this.raw.strong_count() }
+    }
+
+    /// Returns true if the two `ArcCWStr`s point to the same allocation.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.raw == other.raw
+    }
+}
+
+impl<'a> TryFrom<&'a str> for ArcCWStr {
+    type Error = ArcCStrError;
+
+    /// Encodes the string as UTF-16, failing if it contains a `\0`.
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        // only U+0000 is encoded with a 0 unit, and it is also the only character with a 0 byte
+        ArcCStrError::check_no_nul(s.as_bytes())?;
+        let len = s.encode_utf16().count();
+        unsafe { ArcCWStr::from_units_unchecked(len, s.encode_utf16()) }
+    }
+}
+
+impl TryFrom<String> for ArcCWStr {
+    type Error = ArcCStrError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        ArcCWStr::try_from(&*s)
+    }
+}
+
+impl<'a> TryFrom<&'a [u16]> for ArcCWStr {
+    type Error = ArcCStrError;
+
+    /// Copies the units, failing if any of them is `0`.
+    fn try_from(units: &'a [u16]) -> Result<Self, Self::Error> {
+        if let Some(pos) = units.iter().position(|&u| u == 0) {
+            return Err(ArcCStrError::InteriorNul { pos });
+        }
+        unsafe { ArcCWStr::from_units_unchecked(units.len(), units.iter().copied()) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> TryFrom<&'a OsStr> for ArcCWStr {
+    type Error = ArcCStrError;
+
+    /// Encodes the OS string as UTF-16, failing if it contains a `\0`.
+    ///
+    /// On Windows, this is lossless, even for strings that are not valid Unicode. Elsewhere, it
+    /// fails with [`NotUnicode`](ArcCStrError::NotUnicode) if the string is not valid Unicode.
+    fn try_from(s: &'a OsStr) -> Result<Self, Self::Error> {
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+            if let Some(pos) = s.encode_wide().position(|u| u == 0) {
+                return Err(ArcCStrError::InteriorNul { pos });
+            }
+            let len = s.encode_wide().count();
+            unsafe { ArcCWStr::from_units_unchecked(len, s.encode_wide()) }
+        }
+        #[cfg(not(windows))]
+        {
+            ArcCWStr::try_from(s.to_str().ok_or(ArcCStrError::NotUnicode)?)
+        }
+    }
+}
+
+impl Clone for ArcCWStr {
+    #[inline]
+    fn clone(&self) -> ArcCWStr {
+        // we hold a reference, so the allocation is live
+        unsafe { self.raw.increment_strong_count() };
+        ArcCWStr { raw: self.raw }
+    }
+}
+
+impl Deref for ArcCWStr {
+    type Target = [u16];
+
+    #[inline]
+    fn deref(&self) -> &[u16] {
+        // the header holds the length of the units in bytes
+        let len = unsafe { self.raw.header() }.len() / 2;
+        unsafe { slice::from_raw_parts(self.as_ptr(), len) }
+    }
+}
+
+impl Drop for ArcCWStr {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            if self.raw.decrement_strong_count() {
+                self.raw.deallocate();
+            }
+        }
+    }
+}
+
+impl PartialEq for ArcCWStr {
+    fn eq(&self, other: &ArcCWStr) -> bool {
+        ArcCWStr::ptr_eq(self, other) || **self == **other
+    }
+}
+
+impl Eq for ArcCWStr {}
+
+impl PartialOrd for ArcCWStr {
+    fn partial_cmp(&self, other: &ArcCWStr) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArcCWStr {
+    /// Compares the strings unit by unit, which orders characters outside of the Basic
+    /// Multilingual Plane differently from `str`.
+    fn cmp(&self, other: &ArcCWStr) -> Ordering {
+        (**self).cmp(&**other)
+    }
+}
+
+impl PartialEq<str> for ArcCWStr {
+    fn eq(&self, other: &str) -> bool {
+        self.iter().copied().eq(other.encode_utf16())
+    }
+}
+
+impl<'a> PartialEq<&'a str> for ArcCWStr {
+    fn eq(&self, other: &&'a str) -> bool {
+        *self == **other
+    }
+}
+
+impl Hash for ArcCWStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl fmt::Debug for ArcCWStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl fmt::Display for ArcCWStr {
+    /// Writes the string, replacing invalid UTF-16 with `U+FFFD REPLACEMENT CHARACTER`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in char::decode_utf16(self.iter().copied()) {
+            f.write_char(c.unwrap_or(char::REPLACEMENT_CHARACTER))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Pointer for ArcCWStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Pointer::fmt(&self.raw, f)
+    }
+}
+
+impl AsRef<[u16]> for ArcCWStr {
+    fn as_ref(&self) -> &[u16] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArcCWStr;
+    use crate::ArcCStrError;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn encodes_once() {
+        let s = ArcCWStr::try_from("a€𝄞").unwrap();
+        assert_eq!(&*s, &[0x61, 0x20ac, 0xd834, 0xdd1e][..]);
+        assert_eq!(
+            s.as_slice_with_nul(),
+            &[0x61, 0x20ac, 0xd834, 0xdd1e, 0][..]
+        );
+        assert_eq!(s, "a€𝄞");
+        assert_eq!(format!("{} {:?}", s, s), "a€𝄞 \"a€𝄞\"");
+
+        let clone = s.clone();
+        assert!(ArcCWStr::ptr_eq(&s, &clone));
+        assert_eq!(ArcCWStr::strong_count(&s), 2);
+        drop(s);
+        assert_eq!(ArcCWStr::strong_count(&clone), 1);
+
+        let empty = ArcCWStr::try_from("").unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(unsafe { *empty.as_ptr() }, 0);
+    }
+
+    #[test]
+    fn from_units() {
+        // a lone surrogate is allowed, as it is on Windows
+        let lone = ArcCWStr::try_from(&[0x61, 0xd800][..]).unwrap();
+        assert_eq!(lone.to_string_lossy(), "a\u{fffd}");
+        assert_eq!(format!("{}", lone), "a\u{fffd}");
+        assert!(lone < ArcCWStr::try_from("b").unwrap());
+        assert_eq!(
+            ArcCWStr::try_from(&[0x61, 0, 0x62][..]),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+        assert_eq!(
+            ArcCWStr::try_from("a\0b"),
+            Err(ArcCStrError::InteriorNul { pos: 1 })
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn from_os_str() {
+        use std::ffi::OsStr;
+        let s = ArcCWStr::try_from(OsStr::new("C:\\Windows")).unwrap();
+        assert_eq!(s, "C:\\Windows");
+        assert_eq!(s.to_os_string(), OsStr::new("C:\\Windows"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            assert_eq!(
+                ArcCWStr::try_from(OsStr::from_bytes(b"\xff")),
+                Err(ArcCStrError::NotUnicode)
+            );
+        }
+    }
+}